@@ -0,0 +1,341 @@
+use graphql_parser::query as q;
+use std::collections::{BTreeMap, HashMap};
+use std::mem;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use graph::prelude::*;
+
+/// A cached query result.
+struct CachedResult {
+    result: q::Value,
+    /// Approximate size of the result in bytes.
+    size: usize,
+    /// Tick of the most recent lookup or insert of the result.
+    last_used: u64,
+}
+
+/// Cached query results of a single subgraph.
+#[derive(Default)]
+struct SubgraphResults {
+    /// Incremented whenever the results of the subgraph are invalidated.
+    generation: u64,
+    /// Query results by cache key.
+    results: HashMap<String, CachedResult>,
+}
+
+#[derive(Default)]
+struct CacheState {
+    subgraphs: HashMap<SubgraphId, SubgraphResults>,
+    /// Cached results ordered from least to most recently used.
+    lru: BTreeMap<u64, (SubgraphId, String)>,
+    /// Total size of all cached results in bytes.
+    bytes: usize,
+    /// Source of `last_used` ticks.
+    clock: u64,
+}
+
+impl CacheState {
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    fn evict_least_recently_used(&mut self) {
+        let tick = match self.lru.keys().next() {
+            Some(tick) => *tick,
+            None => return,
+        };
+        let (subgraph_id, key) = self.lru.remove(&tick).unwrap();
+        if let Some(cached) = self
+            .subgraphs
+            .get_mut(&subgraph_id)
+            .and_then(|subgraph| subgraph.results.remove(&key))
+        {
+            self.bytes -= cached.size;
+        }
+    }
+}
+
+/// In-memory cache for the results of GraphQL queries.
+///
+/// Query results only change when the entities of the subgraph they are
+/// run against change. Results are therefore cached per subgraph and all
+/// results of a subgraph are dropped when it is invalidated, e.g. because
+/// the store reported an entity change for it or because it was removed.
+///
+/// The cache holds results of up to `max_bytes` in total. Once it is full,
+/// the least recently used results are evicted to make room for new ones.
+pub struct QueryCache {
+    max_bytes: usize,
+    state: Mutex<CacheState>,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+impl QueryCache {
+    /// Creates a new query cache that holds up to `max_bytes` of results.
+    pub fn new(max_bytes: usize) -> Self {
+        QueryCache {
+            max_bytes,
+            state: Mutex::new(CacheState::default()),
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns the key under which the result of a query is cached.
+    ///
+    /// Queries that only differ in formatting or in the order of their
+    /// variables map to the same key.
+    pub fn cache_key(query: &Query) -> String {
+        let variables = query
+            .variables
+            .as_ref()
+            .map(|variables| variables.iter().collect::<BTreeMap<_, _>>());
+
+        format!(
+            "{}\n{:?}\n{:?}",
+            query.document, query.operation_name, variables
+        )
+    }
+
+    /// Returns the current generation of the results of a subgraph.
+    ///
+    /// Results must be inserted with the generation obtained before the
+    /// query was executed, so that results of queries that raced with an
+    /// invalidation are not cached.
+    pub fn generation(&self, subgraph_id: &SubgraphId) -> u64 {
+        self.state
+            .lock()
+            .unwrap()
+            .subgraphs
+            .get(subgraph_id)
+            .map_or(0, |subgraph| subgraph.generation)
+    }
+
+    /// Looks up the result of a query against a subgraph.
+    pub fn get(&self, subgraph_id: &SubgraphId, key: &str) -> Option<q::Value> {
+        let mut state = self.state.lock().unwrap();
+        let tick = state.tick();
+
+        let last_used = state
+            .subgraphs
+            .get_mut(subgraph_id)
+            .and_then(|subgraph| subgraph.results.get_mut(key))
+            .map(|cached| mem::replace(&mut cached.last_used, tick));
+
+        let result = match last_used {
+            Some(last_used) => {
+                let entry = state.lru.remove(&last_used).unwrap();
+                state.lru.insert(tick, entry);
+                state.subgraphs[subgraph_id]
+                    .results
+                    .get(key)
+                    .map(|cached| cached.result.clone())
+            }
+            None => None,
+        };
+
+        match result {
+            Some(_) => self.hits.fetch_add(1, Ordering::SeqCst),
+            None => self.misses.fetch_add(1, Ordering::SeqCst),
+        };
+
+        result
+    }
+
+    /// Caches the result of a query against a subgraph.
+    ///
+    /// The result is not cached if the subgraph was invalidated since
+    /// `generation` was obtained.
+    pub fn insert(&self, subgraph_id: SubgraphId, generation: u64, key: String, result: q::Value) {
+        let size = key.len() + result_size(&result);
+        if size > self.max_bytes {
+            return;
+        }
+
+        let mut state = self.state.lock().unwrap();
+
+        let current = state
+            .subgraphs
+            .get(&subgraph_id)
+            .map_or(0, |subgraph| subgraph.generation);
+        if current != generation {
+            return;
+        }
+
+        // Replace an existing result for the same query
+        if let Some(cached) = state
+            .subgraphs
+            .get_mut(&subgraph_id)
+            .and_then(|subgraph| subgraph.results.remove(&key))
+        {
+            state.lru.remove(&cached.last_used);
+            state.bytes -= cached.size;
+        }
+
+        while state.bytes + size > self.max_bytes && !state.lru.is_empty() {
+            state.evict_least_recently_used();
+        }
+
+        let tick = state.tick();
+        state.lru.insert(tick, (subgraph_id.clone(), key.clone()));
+        state.bytes += size;
+        state
+            .subgraphs
+            .entry(subgraph_id)
+            .or_insert_with(SubgraphResults::default)
+            .results
+            .insert(
+                key,
+                CachedResult {
+                    result,
+                    size,
+                    last_used: tick,
+                },
+            );
+    }
+
+    /// Drops all results cached for a subgraph, e.g. because its entities
+    /// changed or because it was removed.
+    pub fn invalidate(&self, subgraph_id: &SubgraphId) {
+        let mut state = self.state.lock().unwrap();
+
+        let results = {
+            let subgraph = state
+                .subgraphs
+                .entry(subgraph_id.clone())
+                .or_insert_with(SubgraphResults::default);
+            subgraph.generation += 1;
+            mem::replace(&mut subgraph.results, HashMap::new())
+        };
+
+        for cached in results.values() {
+            state.lru.remove(&cached.last_used);
+            state.bytes -= cached.size;
+        }
+    }
+
+    /// Returns the number of cached results.
+    pub fn entries(&self) -> usize {
+        self.state.lock().unwrap().lru.len()
+    }
+
+    /// Returns the total size of all cached results in bytes.
+    pub fn bytes(&self) -> usize {
+        self.state.lock().unwrap().bytes
+    }
+
+    /// Returns the number of lookups that found a cached result.
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::SeqCst)
+    }
+
+    /// Returns the number of lookups that didn't find a cached result.
+    pub fn misses(&self) -> usize {
+        self.misses.load(Ordering::SeqCst)
+    }
+}
+
+/// Returns the approximate number of bytes a query result occupies in
+/// memory.
+fn result_size(value: &q::Value) -> usize {
+    let nested = match value {
+        q::Value::Variable(s) | q::Value::String(s) | q::Value::Enum(s) => s.len(),
+        q::Value::List(values) => values.iter().map(result_size).sum(),
+        q::Value::Object(fields) => fields
+            .iter()
+            .map(|(name, value)| name.len() + result_size(value))
+            .sum(),
+        _ => 0,
+    };
+    mem::size_of::<q::Value>() + nested
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use graphql_parser;
+
+    fn query(document: &str) -> Query {
+        Query {
+            schema: Schema {
+                id: String::from("subgraph"),
+                document: graphql_parser::parse_schema("type Query { a: Int }").unwrap(),
+            },
+            document: graphql_parser::parse_query(document).unwrap(),
+            variables: None,
+            operation_name: None,
+        }
+    }
+
+    #[test]
+    fn normalizes_queries() {
+        assert_eq!(
+            QueryCache::cache_key(&query("{ musicians { name } }")),
+            QueryCache::cache_key(&query("{\n  musicians {\n    name\n  }\n}")),
+        );
+        assert_ne!(
+            QueryCache::cache_key(&query("{ musicians { name } }")),
+            QueryCache::cache_key(&query("{ bands { name } }")),
+        );
+    }
+
+    #[test]
+    fn caches_results_until_invalidated() {
+        let cache = QueryCache::new(1000);
+        let id = String::from("subgraph");
+
+        assert_eq!(cache.get(&id, "query"), None);
+        let generation = cache.generation(&id);
+        cache.insert(
+            id.clone(),
+            generation,
+            "query".to_owned(),
+            q::Value::Int(1.into()),
+        );
+        assert_eq!(cache.get(&id, "query"), Some(q::Value::Int(1.into())));
+
+        // Results are dropped once the subgraph is invalidated
+        cache.invalidate(&id);
+        assert_eq!(cache.get(&id, "query"), None);
+        assert_eq!(cache.entries(), 0);
+        assert_eq!(cache.bytes(), 0);
+
+        // Results of queries that raced with an invalidation are not cached
+        cache.insert(id.clone(), generation, "query".to_owned(), q::Value::Null);
+        assert_eq!(cache.entries(), 0);
+
+        assert_eq!(cache.hits(), 1);
+        assert_eq!(cache.misses(), 2);
+    }
+
+    #[test]
+    fn evicts_least_recently_used_results() {
+        let size = 1 + result_size(&q::Value::Null);
+        let cache = QueryCache::new(2 * size);
+        let id = String::from("subgraph");
+
+        cache.insert(id.clone(), 0, "a".to_owned(), q::Value::Null);
+        cache.insert(id.clone(), 0, "b".to_owned(), q::Value::Null);
+        assert_eq!(cache.get(&id, "a"), Some(q::Value::Null));
+        cache.insert(id.clone(), 0, "c".to_owned(), q::Value::Null);
+
+        assert_eq!(cache.entries(), 2);
+        assert_eq!(cache.bytes(), 2 * size);
+        assert_eq!(cache.get(&id, "b"), None);
+        assert_eq!(cache.get(&id, "a"), Some(q::Value::Null));
+        assert_eq!(cache.get(&id, "c"), Some(q::Value::Null));
+
+        // Results larger than the cache are not cached at all
+        cache.insert(
+            id.clone(),
+            0,
+            "d".to_owned(),
+            q::Value::String("x".repeat(2 * size)),
+        );
+        assert_eq!(cache.get(&id, "d"), None);
+        assert_eq!(cache.entries(), 2);
+    }
+}
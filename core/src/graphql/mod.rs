@@ -1,3 +1,5 @@
+mod cache;
 mod runner;
 
+pub use self::cache::QueryCache;
 pub use self::runner::GraphQlRunner;
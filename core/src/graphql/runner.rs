@@ -1,12 +1,25 @@
 use futures::future;
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
+use graph::components::metrics::{GRAPHQL_QUERY_DURATION, GRAPHQL_QUERY_ERRORS};
 use graph::prelude::{GraphQlRunner as GraphQlRunnerTrait, *};
 use graph_graphql::prelude::*;
+use graph_graphql::schema::ast;
+
+use super::QueryCache;
 
 /// GraphQL runner implementation for The Graph.
 pub struct GraphQlRunner<S> {
     logger: Logger,
     store: Arc<S>,
+    query_timeout: Duration,
+    max_depth: u8,
+    max_complexity: u64,
+    query_cache: Arc<QueryCache>,
+    /// Subgraphs whose entity changes invalidate their cached results.
+    watched_subgraphs: Mutex<HashSet<SubgraphId>>,
 }
 
 impl<S> GraphQlRunner<S>
@@ -15,10 +28,112 @@ where
 {
     /// Creates a new query runner.
     pub fn new(logger: &Logger, store: Arc<S>) -> Self {
+        // Limit the time and resources a single query may use, so that
+        // e.g. deeply nested queries cannot bring down the node. The
+        // defaults leave room for introspection queries and for nesting
+        // a few list fields with their default `first` of 100
+        let query_timeout = parse_env_var("GRAPH_GRAPHQL_QUERY_TIMEOUT")
+            .unwrap_or_else(|e| panic!("{}", e))
+            .map(Duration::from_secs)
+            .unwrap_or(Duration::from_secs(60));
+        let max_depth = parse_env_var("GRAPH_GRAPHQL_MAX_DEPTH")
+            .unwrap_or_else(|e| panic!("{}", e))
+            .unwrap_or(32);
+        let max_complexity = parse_env_var("GRAPH_GRAPHQL_MAX_COMPLEXITY")
+            .unwrap_or_else(|e| panic!("{}", e))
+            .unwrap_or(10_000_000);
+
+        // Cache query results until the entities of the subgraph change,
+        // using up to this many bytes; a size of 0 disables the cache
+        let query_cache_size = parse_env_var("GRAPH_QUERY_CACHE_MAX_BYTES")
+            .unwrap_or_else(|e| panic!("{}", e))
+            .unwrap_or(100 * 1024 * 1024);
+
         GraphQlRunner {
             logger: logger.new(o!("component" => "GraphQlRunner")),
             store: store,
+            query_timeout,
+            max_depth,
+            max_complexity,
+            query_cache: Arc::new(QueryCache::new(query_cache_size)),
+            watched_subgraphs: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Returns the cache for query results, e.g. to inspect its hit and
+    /// miss counters.
+    pub fn query_cache(&self) -> Arc<QueryCache> {
+        self.query_cache.clone()
+    }
+
+    fn execute(&self, query: &Query) -> QueryResult {
+        // The deadline bounds both the execution of the query and the
+        // store queries it makes
+        let deadline = Instant::now() + self.query_timeout;
+
+        execute_query(
+            query,
+            QueryExecutionOptions {
+                logger: self.logger.clone(),
+                resolver: StoreResolver::new(&self.logger, self.store.clone())
+                    .with_deadline(deadline),
+                deadline: Some(deadline),
+                max_depth: self.max_depth,
+                max_complexity: Some(self.max_complexity),
+            },
+        )
+    }
+
+    /// Subscribes to the entity changes of a subgraph, unless that has
+    /// already happened, and invalidates its cached results whenever the
+    /// store reports a change. Removing a subgraph deletes its entities,
+    /// which invalidates its results as well.
+    fn watch_subgraph(&self, schema: &Schema) {
+        if !self
+            .watched_subgraphs
+            .lock()
+            .unwrap()
+            .insert(schema.id.clone())
+        {
+            return;
+        }
+
+        let entities = ast::get_object_type_definitions(&schema.document)
+            .into_iter()
+            .map(|object_type| (schema.id.clone(), object_type.name.clone()))
+            .collect();
+
+        let query_cache = self.query_cache.clone();
+        let subgraph_id = schema.id.clone();
+        tokio::spawn(self.store.subscribe(entities).for_each(move |_| {
+            query_cache.invalidate(&subgraph_id);
+            Ok(())
+        }));
+    }
+
+    /// Executes a query or looks up its result in the query cache.
+    fn execute_with_cache(&self, query: &Query) -> QueryResult {
+        self.watch_subgraph(&query.schema);
+
+        let key = QueryCache::cache_key(query);
+        if let Some(data) = self.query_cache.get(&query.schema.id, &key) {
+            return QueryResult::new(Some(data));
         }
+
+        // Only cache the result if the subgraph didn't change while the
+        // query was being executed
+        let generation = self.query_cache.generation(&query.schema.id);
+        let result = self.execute(query);
+
+        // Only cache results without errors
+        if result.errors.is_none() {
+            if let Some(ref data) = result.data {
+                self.query_cache
+                    .insert(query.schema.id.clone(), generation, key, data.clone());
+            }
+        }
+
+        result
     }
 }
 
@@ -27,13 +142,14 @@ where
     S: Store,
 {
     fn run_query(&self, query: Query) -> QueryResultFuture {
-        let result = execute_query(
-            &query,
-            QueryExecutionOptions {
-                logger: self.logger.clone(),
-                resolver: StoreResolver::new(&self.logger, self.store.clone()),
-            },
-        );
+        let timer = GRAPHQL_QUERY_DURATION.start_timer();
+        let result = self.execute_with_cache(&query);
+        timer.observe_duration();
+
+        if result.errors.is_some() {
+            GRAPHQL_QUERY_ERRORS.inc();
+        }
+
         Box::new(future::ok(result))
     }
 
@@ -43,6 +159,8 @@ where
             SubscriptionExecutionOptions {
                 logger: self.logger.clone(),
                 resolver: StoreResolver::new(&self.logger, self.store.clone()),
+                max_depth: self.max_depth,
+                max_complexity: Some(self.max_complexity),
             },
         );
 
@@ -7,6 +7,7 @@ extern crate graph_graphql;
 #[cfg(test)]
 extern crate graph_mock;
 extern crate graph_runtime_wasm;
+extern crate graphql_parser;
 extern crate itertools;
 extern crate serde;
 #[macro_use]
@@ -20,7 +21,8 @@ mod graphql;
 mod log;
 mod subgraph;
 
-pub use graphql::GraphQlRunner;
+pub use graphql::{GraphQlRunner, QueryCache};
 pub use log::elastic::{elastic_logger, ElasticDrainConfig, ElasticLoggingConfig};
+pub use log::recent::{subgraph_log_store_logger, RecentSubgraphLogs};
 pub use log::split::split_logger;
 pub use subgraph::{SubgraphInstanceManager, SubgraphProvider, SubgraphProviderWithNames};
@@ -1,17 +1,20 @@
 use chrono::prelude::{SecondsFormat, Utc};
-use itertools;
 use reqwest;
 use serde::ser::Serializer as SerdeSerializer;
-use std::fmt;
-use std::fmt::Write;
+use std::cmp;
+use std::collections::{HashMap, VecDeque};
+use std::iter;
 use std::sync::Mutex;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
+use graph::components::metrics::ELASTICSEARCH_DROPPED_LOGS;
 use graph::prelude::tokio::timer::Interval;
 use graph::prelude::*;
 use graph::serde_json;
 use graph::slog_async;
 
+use super::log_text;
+
 use std::result::Result;
 
 /// General configuration parameters for Elasticsearch logging.
@@ -62,35 +65,6 @@ struct ElasticLog {
     meta: ElasticLogMeta,
 }
 
-/// A super-simple slog Serializer for concatenating key/value arguments.
-struct SimpleKVSerializer {
-    kvs: Vec<(String, String)>,
-}
-
-impl SimpleKVSerializer {
-    /// Creates a new `SimpleKVSerializer`.
-    fn new() -> Self {
-        SimpleKVSerializer {
-            kvs: Default::default(),
-        }
-    }
-
-    /// Collects all key/value arguments into a single, comma-separated string.
-    /// Returns the number of key/value pairs and the string itself.
-    fn finish(self) -> (usize, String) {
-        (
-            self.kvs.len(),
-            itertools::join(self.kvs.iter().map(|(k, v)| format!("{}: {}", k, v)), ", "),
-        )
-    }
-}
-
-impl Serializer for SimpleKVSerializer {
-    fn emit_arguments(&mut self, key: Key, val: &fmt::Arguments) -> slog::Result {
-        Ok(self.kvs.push((key.into(), format!("{}", val))))
-    }
-}
-
 /// Configuration for `ElasticDrain`.
 #[derive(Clone, Debug)]
 pub struct ElasticDrainConfig {
@@ -104,6 +78,184 @@ pub struct ElasticDrainConfig {
     pub subgraph_id: String,
     /// The batching interval.
     pub flush_interval: Duration,
+    /// The maximum number of logs to buffer. When the buffer is full, the
+    /// oldest logs are dropped.
+    pub max_buffer_size: usize,
+}
+
+/// Maximum delay between attempts to send logs while Elasticsearch fails.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(300);
+
+/// Logs waiting to be sent to Elasticsearch.
+///
+/// The buffer holds at most `max_size` logs; when it is full, the oldest logs
+/// are dropped to make room for new ones.
+struct LogBuffer {
+    logs: VecDeque<ElasticLog>,
+    max_size: usize,
+    /// Number of logs dropped since the last call to `take_dropped`.
+    dropped: usize,
+}
+
+impl LogBuffer {
+    fn new(max_size: usize) -> Self {
+        LogBuffer {
+            logs: VecDeque::new(),
+            max_size,
+            dropped: 0,
+        }
+    }
+
+    /// Appends a log to the buffer.
+    fn push(&mut self, log: ElasticLog) {
+        self.logs.push_back(log);
+        self.truncate();
+    }
+
+    /// Puts logs that couldn't be sent back in front of the buffer, so that
+    /// they are sent before any newer logs.
+    fn requeue(&mut self, logs: Vec<ElasticLog>) {
+        for log in logs.into_iter().rev() {
+            self.logs.push_front(log);
+        }
+        self.truncate();
+    }
+
+    /// Removes all logs from the buffer.
+    fn take_all(&mut self) -> Vec<ElasticLog> {
+        self.logs.drain(..).collect()
+    }
+
+    /// Returns the number of logs dropped since the last call and resets it.
+    fn take_dropped(&mut self) -> usize {
+        let dropped = self.dropped;
+        self.dropped = 0;
+        dropped
+    }
+
+    /// Drops the oldest logs until the buffer is within its size limit.
+    fn truncate(&mut self) {
+        while self.logs.len() > self.max_size {
+            self.logs.pop_front();
+            self.dropped += 1;
+        }
+    }
+}
+
+/// Response of the Elasticsearch `_bulk` API.
+#[derive(Debug, Deserialize)]
+struct BulkResponse {
+    /// Whether any of the items failed.
+    errors: bool,
+    /// Results for the items of the request, in the same order, each keyed
+    /// by the action (e.g. `"index"`).
+    items: Vec<HashMap<String, BulkItemResult>>,
+}
+
+/// Result of a single item of a `_bulk` request.
+#[derive(Debug, Deserialize)]
+struct BulkItemResult {
+    status: u16,
+    error: Option<serde_json::Value>,
+}
+
+impl BulkItemResult {
+    fn is_success(&self) -> bool {
+        self.status >= 200 && self.status < 300
+    }
+
+    /// Returns true if the item may succeed when retried, e.g. because
+    /// Elasticsearch was overloaded.
+    fn is_retryable(&self) -> bool {
+        self.status == 429 || self.status >= 500
+    }
+}
+
+/// Splits the logs sent in a `_bulk` request into the logs that should be
+/// sent again and the errors of the logs that Elasticsearch rejected.
+///
+/// Logs without a result in the response are sent again.
+fn partition_bulk_response(
+    logs: Vec<ElasticLog>,
+    response: BulkResponse,
+) -> (Vec<ElasticLog>, Vec<String>) {
+    let mut retry = vec![];
+    let mut rejected = vec![];
+
+    if !response.errors && response.items.len() == logs.len() {
+        return (retry, rejected);
+    }
+
+    let results = response
+        .items
+        .into_iter()
+        .map(|item| item.into_iter().next().map(|(_, result)| result))
+        .chain(iter::repeat(None));
+
+    for (log, result) in logs.into_iter().zip(results) {
+        match result {
+            Some(ref result) if result.is_success() => (),
+            Some(ref result) if !result.is_retryable() => rejected.push(format!(
+                "{} (status {}): {}",
+                log.id,
+                result.status,
+                result.error.clone().unwrap_or(serde_json::Value::Null)
+            )),
+            _ => retry.push(log),
+        }
+    }
+
+    (retry, rejected)
+}
+
+/// Exponential backoff between attempts to send logs to Elasticsearch.
+struct Backoff {
+    /// Number of consecutive failed attempts.
+    failures: u32,
+    /// Earliest time at which to make the next attempt.
+    next_attempt: Instant,
+}
+
+impl Backoff {
+    fn new() -> Self {
+        Backoff {
+            failures: 0,
+            next_attempt: Instant::now(),
+        }
+    }
+
+    fn is_waiting(&self) -> bool {
+        Instant::now() < self.next_attempt
+    }
+
+    fn succeed(&mut self) {
+        self.failures = 0;
+        self.next_attempt = Instant::now();
+    }
+
+    /// Records a failed attempt and returns the delay before the next one.
+    fn fail(&mut self, flush_interval: Duration) -> Duration {
+        let delay = retry_delay(flush_interval, self.failures);
+        self.failures += 1;
+        self.next_attempt = Instant::now() + delay;
+        delay
+    }
+}
+
+/// Delay before the next attempt to send logs after `failures` consecutive
+/// failed attempts.
+fn retry_delay(flush_interval: Duration, failures: u32) -> Duration {
+    cmp::min(
+        flush_interval * 2u32.pow(cmp::min(failures, 16)),
+        MAX_RETRY_DELAY,
+    )
+}
+
+/// Counts logs dropped for the given reason.
+fn count_dropped_logs(subgraph_id: &str, reason: &str, count: usize) {
+    ELASTICSEARCH_DROPPED_LOGS
+        .with_label_values(&[subgraph_id, reason])
+        .inc_by(count as i64);
 }
 
 /// An slog `Drain` for logging to Elasticsearch.
@@ -128,19 +280,25 @@ pub struct ElasticDrainConfig {
 ///   }
 /// }
 /// ```
+///
+/// Logs are buffered and sent in batches. Batches that fail to be sent are
+/// retried with an exponential backoff; logs are only dropped when the
+/// buffer is full or when Elasticsearch rejects them. Dropped logs are
+/// counted in the `elasticsearch_dropped_logs_total` metric.
 pub struct ElasticDrain {
     config: ElasticDrainConfig,
     error_logger: Logger,
-    logs: Arc<Mutex<Vec<ElasticLog>>>,
+    logs: Arc<Mutex<LogBuffer>>,
 }
 
 impl ElasticDrain {
     /// Creates a new `ElasticDrain`.
     pub fn new(config: ElasticDrainConfig, error_logger: Logger) -> Self {
+        let max_buffer_size = config.max_buffer_size;
         let drain = ElasticDrain {
             config,
             error_logger,
-            logs: Arc::new(Mutex::new(vec![])),
+            logs: Arc::new(Mutex::new(LogBuffer::new(max_buffer_size))),
         };
         drain.periodically_flush_logs();
         drain
@@ -152,94 +310,174 @@ impl ElasticDrain {
         let logs = self.logs.clone();
         let config = self.config.clone();
 
+        let backoff = Arc::new(Mutex::new(Backoff::new()));
+
         tokio::spawn(
             Interval::new_interval(self.config.flush_interval)
-                .for_each(move |_| {
-                    let mut logs = logs.lock().unwrap();
+                .map_err(move |e| {
+                    error!(
+                        interval_error_logger,
+                        "Error in Elasticsearch logger flush interval: {}", e
+                    );
+                }).for_each(move |_| {
+                    // Wait for the backoff after failed attempts to pass
+                    if backoff.lock().unwrap().is_waiting() {
+                        return future::Either::A(future::ok(()));
+                    }
+
+                    let (batch, dropped) = {
+                        let mut logs = logs.lock().unwrap();
+                        (logs.take_all(), logs.take_dropped())
+                    };
+
+                    if dropped > 0 {
+                        count_dropped_logs(&config.subgraph_id, "buffer_full", dropped);
+                        warn!(
+                            flush_logger,
+                            "Dropped {} logs because the Elasticsearch log buffer was full",
+                            dropped
+                        );
+                    }
 
                     // Do nothing if there are no logs to flush
-                    if logs.is_empty() {
-                        return Ok(());
+                    if batch.is_empty() {
+                        return future::Either::A(future::ok(()));
                     }
 
                     trace!(
                         flush_logger,
                         "Flushing {} logs to Elasticsearch",
-                        logs.len()
+                        batch.len()
                     );
 
-                    // The Elasticsearch batch API takes requests with the following format:
-                    // ```ignore
-                    // action_and_meta_data\n
-                    // optional_source\n
-                    // action_and_meta_data\n
-                    // optional_source\n
-                    // ```
-                    // For more details, see:
-                    // https://www.elastic.co/guide/en/elasticsearch/reference/current/docs-bulk.html
-                    //
-                    // We're assembly the request body in the same way below:
-                    let batch_body = logs.iter().fold(String::from(""), |mut out, log| {
-                        // Try to serialize the log itself to a JSON string
-                        match serde_json::to_string(log) {
-                            Ok(log_line) => {
-                                // Serialize the action line to a string
-                                let action_line = json!({
-                                    "index": {
-                                        "_index": config.index,
-                                        "_type": config.document_type,
-                                        "_id": log.id,
+                    let logs = logs.clone();
+                    let backoff = backoff.clone();
+                    let flush_logger = flush_logger.clone();
+                    let config = config.clone();
+
+                    future::Either::B(Self::send_logs(&config, batch, &flush_logger).then(
+                        move |result| {
+                            let retry = match result {
+                                Ok((retry, rejected)) => {
+                                    if !rejected.is_empty() {
+                                        count_dropped_logs(
+                                            &config.subgraph_id,
+                                            "rejected",
+                                            rejected.len(),
+                                        );
+                                        error!(
+                                            flush_logger,
+                                            "Elasticsearch rejected {} logs", rejected.len();
+                                            "first_error" => &rejected[0]
+                                        );
                                     }
-                                }).to_string();
-
-                                // Combine the two lines with newlines, make sure there is
-                                // a newline at the end as well
-                                out.push_str(format!("{}\n{}\n", action_line, log_line).as_str());
-                            }
-                            Err(e) => {
-                                error!(
+                                    retry
+                                }
+                                Err((retry, e)) => {
+                                    error!(
+                                        flush_logger,
+                                        "Failed to send logs to Elasticsearch: {}", e
+                                    );
+                                    retry
+                                }
+                            };
+
+                            // Back off while Elasticsearch fails to accept logs
+                            if retry.is_empty() {
+                                backoff.lock().unwrap().succeed();
+                            } else {
+                                let delay = backoff.lock().unwrap().fail(config.flush_interval);
+
+                                debug!(
                                     flush_logger,
-                                    "Failed to serialize Elasticsearch log to JSON: {}", e
+                                    "Retrying to send {} logs to Elasticsearch in {:?}",
+                                    retry.len(),
+                                    delay
                                 );
+                                logs.lock().unwrap().requeue(retry);
                             }
-                        };
-
-                        out
-                    });
-
-                    // Build the batch API URL
-                    let mut batch_url = reqwest::Url::parse(config.general.endpoint.as_str())
-                        .expect("invalid Elasticsearch URL");
-                    batch_url.set_path("_bulk");
-
-                    // Send batch of logs to Elasticsearch
-                    let client = reqwest::Client::new();
-                    client
-                        .post(batch_url)
-                        .header("Content-Type", "application/json")
-                        .basic_auth(
-                            config.general.username.clone().unwrap_or("".into()),
-                            config.general.password.clone(),
-                        ).body(batch_body)
-                        .send()
-                        .and_then(|response| response.error_for_status())
-                        .map_err(|e| {
-                            // Log if there was a problem sending the logs
-                            error!(flush_logger, "Failed to send logs to Elasticsearch: {}", e);
-                        }).ok();
-
-                    // Clear the logs, so the next batch can be recorded
-                    logs.clear();
-
-                    Ok(())
-                }).map_err(move |e| {
-                    error!(
-                        interval_error_logger,
-                        "Error in Elasticsearch logger flush interval: {}", e
-                    );
+
+                            Ok(())
+                        },
+                    ))
                 }),
         );
     }
+
+    /// Sends a batch of logs to Elasticsearch.
+    ///
+    /// Resolves to the logs that need to be sent again and the errors of the
+    /// logs that Elasticsearch rejected. Fails with all logs that were sent
+    /// if the request as a whole failed.
+    fn send_logs(
+        config: &ElasticDrainConfig,
+        logs: Vec<ElasticLog>,
+        logger: &Logger,
+    ) -> impl Future<Item = (Vec<ElasticLog>, Vec<String>), Error = (Vec<ElasticLog>, String)> {
+        // The Elasticsearch batch API takes requests with the following format:
+        // ```ignore
+        // action_and_meta_data\n
+        // optional_source\n
+        // action_and_meta_data\n
+        // optional_source\n
+        // ```
+        // For more details, see:
+        // https://www.elastic.co/guide/en/elasticsearch/reference/current/docs-bulk.html
+        //
+        // We're assembly the request body in the same way below; logs that
+        // can't be serialized are dropped, so that the items in the response
+        // correspond to the logs that were sent
+        let mut batch_body = String::new();
+        let mut sent_logs = Vec::with_capacity(logs.len());
+        for log in logs {
+            // Try to serialize the log itself to a JSON string
+            match serde_json::to_string(&log) {
+                Ok(log_line) => {
+                    // Serialize the action line to a string
+                    let action_line = json!({
+                        "index": {
+                            "_index": config.index,
+                            "_type": config.document_type,
+                            "_id": log.id,
+                        }
+                    }).to_string();
+
+                    // Combine the two lines with newlines, make sure there is
+                    // a newline at the end as well
+                    batch_body.push_str(format!("{}\n{}\n", action_line, log_line).as_str());
+                    sent_logs.push(log);
+                }
+                Err(e) => {
+                    count_dropped_logs(&config.subgraph_id, "invalid", 1);
+                    error!(
+                        logger,
+                        "Failed to serialize Elasticsearch log to JSON: {}", e
+                    );
+                }
+            };
+        }
+
+        // Build the batch API URL
+        let mut batch_url = reqwest::Url::parse(config.general.endpoint.as_str())
+            .expect("invalid Elasticsearch URL");
+        batch_url.set_path("_bulk");
+
+        // Send batch of logs to Elasticsearch
+        reqwest::async::Client::new()
+            .post(batch_url)
+            .header("Content-Type", "application/json")
+            .basic_auth(
+                config.general.username.clone().unwrap_or("".into()),
+                config.general.password.clone(),
+            ).body(batch_body)
+            .send()
+            .and_then(|response| response.error_for_status())
+            .and_then(|mut response| response.json::<BulkResponse>())
+            .then(move |result| match result {
+                Ok(response) => Ok(partition_bulk_response(sent_logs, response)),
+                Err(e) => Err((sent_logs, e.to_string())),
+            })
+    }
 }
 
 impl Drain for ElasticDrain {
@@ -250,35 +488,12 @@ impl Drain for ElasticDrain {
         let timestamp = Utc::now().to_rfc3339_opts(SecondsFormat::Nanos, true);
         let id = format!("{}-{}", self.config.subgraph_id, timestamp);
 
-        // Serialize logger arguments
-        let mut serializer = SimpleKVSerializer::new();
-        record
-            .kv()
-            .serialize(record, &mut serializer)
-            .expect("failed to serializer logger arguments");
-        let (n_logger_kvs, logger_kvs) = serializer.finish();
-
-        // Serialize log message arguments
-        let mut serializer = SimpleKVSerializer::new();
-        values
-            .serialize(record, &mut serializer)
-            .expect("failed to serialize log message arguments");
-        let (n_value_kvs, value_kvs) = serializer.finish();
-
-        let mut text = format!("{}", record.msg());
-        if n_logger_kvs > 0 {
-            write!(text, ", {}", logger_kvs);
-        }
-        if n_value_kvs > 0 {
-            write!(text, ", {}", value_kvs);
-        }
-
         // Prepare log document
         let log = ElasticLog {
             id: id.clone(),
             subgraph_id: self.config.subgraph_id.clone(),
             timestamp,
-            text,
+            text: log_text(record, values),
             level: record.level(),
             meta: ElasticLogMeta {
                 module: record.module().into(),
@@ -287,7 +502,7 @@ impl Drain for ElasticDrain {
             },
         };
 
-        // Push the log into the queue
+        // Push the log into the queue, possibly dropping older logs
         let mut logs = self.logs.lock().unwrap();
         logs.push(log);
 
@@ -307,3 +522,90 @@ pub fn elastic_logger(config: ElasticDrainConfig, error_logger: Logger) -> Logge
         .fuse();
     Logger::root(async_drain, o!())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(id: &str) -> ElasticLog {
+        ElasticLog {
+            id: String::from(id),
+            subgraph_id: String::from("subgraph"),
+            timestamp: String::from("2018-11-08T00:54:52.589258000Z"),
+            text: String::from("text"),
+            level: Level::Info,
+            meta: ElasticLogMeta {
+                module: String::from("module"),
+                line: 1,
+                column: 1,
+            },
+        }
+    }
+
+    fn ids(logs: &[ElasticLog]) -> Vec<&str> {
+        logs.iter().map(|log| log.id.as_str()).collect()
+    }
+
+    fn item(status: u16) -> HashMap<String, BulkItemResult> {
+        let mut item = HashMap::new();
+        item.insert(
+            String::from("index"),
+            BulkItemResult {
+                status,
+                error: if status >= 300 {
+                    Some(json!({ "type": "error" }))
+                } else {
+                    None
+                },
+            },
+        );
+        item
+    }
+
+    #[test]
+    fn buffer_drops_oldest_logs_when_full() {
+        let mut buffer = LogBuffer::new(2);
+        buffer.push(log("a"));
+        buffer.push(log("b"));
+        buffer.push(log("c"));
+
+        assert_eq!(buffer.take_dropped(), 1);
+        assert_eq!(buffer.take_dropped(), 0);
+        assert_eq!(ids(&buffer.take_all()), vec!["b", "c"]);
+        assert!(buffer.take_all().is_empty());
+    }
+
+    #[test]
+    fn buffer_sends_requeued_logs_first() {
+        let mut buffer = LogBuffer::new(3);
+        buffer.push(log("c"));
+        buffer.push(log("d"));
+        buffer.requeue(vec![log("a"), log("b")]);
+
+        assert_eq!(buffer.take_dropped(), 1);
+        assert_eq!(ids(&buffer.take_all()), vec!["b", "c", "d"]);
+    }
+
+    #[test]
+    fn bulk_response_retries_and_rejects_items() {
+        let logs = vec![log("a"), log("b"), log("c"), log("d")];
+        let response = BulkResponse {
+            errors: true,
+            items: vec![item(201), item(429), item(400)],
+        };
+
+        let (retry, rejected) = partition_bulk_response(logs, response);
+
+        assert_eq!(ids(&retry), vec!["b", "d"]);
+        assert_eq!(rejected.len(), 1);
+        assert!(rejected[0].starts_with("c (status 400)"));
+    }
+
+    #[test]
+    fn retry_delay_is_bounded() {
+        let interval = Duration::from_secs(5);
+        assert_eq!(retry_delay(interval, 0), Duration::from_secs(5));
+        assert_eq!(retry_delay(interval, 2), Duration::from_secs(20));
+        assert_eq!(retry_delay(interval, 100), MAX_RETRY_DELAY);
+    }
+}
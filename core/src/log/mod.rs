@@ -1,2 +1,66 @@
+use itertools;
+use std::fmt;
+use std::fmt::Write;
+
+use graph::slog::*;
+
 pub mod elastic;
+pub mod recent;
 pub mod split;
+
+/// A super-simple slog Serializer for concatenating key/value arguments.
+struct SimpleKVSerializer {
+    kvs: Vec<(String, String)>,
+}
+
+impl SimpleKVSerializer {
+    /// Creates a new `SimpleKVSerializer`.
+    fn new() -> Self {
+        SimpleKVSerializer {
+            kvs: Default::default(),
+        }
+    }
+
+    /// Collects all key/value arguments into a single, comma-separated string.
+    /// Returns the number of key/value pairs and the string itself.
+    fn finish(self) -> (usize, String) {
+        (
+            self.kvs.len(),
+            itertools::join(self.kvs.iter().map(|(k, v)| format!("{}: {}", k, v)), ", "),
+        )
+    }
+}
+
+impl Serializer for SimpleKVSerializer {
+    fn emit_arguments(&mut self, key: Key, val: &fmt::Arguments) -> Result {
+        Ok(self.kvs.push((key.into(), format!("{}", val))))
+    }
+}
+
+/// Formats a log record as its message followed by the key/value arguments
+/// of the record and of the logger.
+fn log_text(record: &Record, values: &OwnedKVList) -> String {
+    // Serialize logger arguments
+    let mut serializer = SimpleKVSerializer::new();
+    record
+        .kv()
+        .serialize(record, &mut serializer)
+        .expect("failed to serializer logger arguments");
+    let (n_logger_kvs, logger_kvs) = serializer.finish();
+
+    // Serialize log message arguments
+    let mut serializer = SimpleKVSerializer::new();
+    values
+        .serialize(record, &mut serializer)
+        .expect("failed to serialize log message arguments");
+    let (n_value_kvs, value_kvs) = serializer.finish();
+
+    let mut text = format!("{}", record.msg());
+    if n_logger_kvs > 0 {
+        write!(text, ", {}", logger_kvs);
+    }
+    if n_value_kvs > 0 {
+        write!(text, ", {}", value_kvs);
+    }
+    text
+}
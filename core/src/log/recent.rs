@@ -0,0 +1,189 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+use graph::chrono::Utc;
+use graph::prelude::*;
+use graph::slog_async;
+
+use super::log_text;
+
+/// Keeps the most recent logs of each subgraph in memory.
+///
+/// At most `max_logs_per_subgraph` logs are kept per subgraph; older logs
+/// are dropped as new ones come in.
+pub struct RecentSubgraphLogs {
+    max_logs_per_subgraph: usize,
+    logs: Mutex<HashMap<SubgraphId, VecDeque<SubgraphLogEntry>>>,
+}
+
+impl RecentSubgraphLogs {
+    /// Creates a new, empty `RecentSubgraphLogs`.
+    pub fn new(max_logs_per_subgraph: usize) -> Self {
+        RecentSubgraphLogs {
+            max_logs_per_subgraph,
+            logs: Default::default(),
+        }
+    }
+}
+
+impl SubgraphLogStore for RecentSubgraphLogs {
+    fn add_log(&self, entry: SubgraphLogEntry) {
+        if self.max_logs_per_subgraph == 0 {
+            return;
+        }
+
+        let mut logs = self.logs.lock().unwrap();
+        let subgraph_logs = logs
+            .entry(entry.subgraph_id.clone())
+            .or_insert_with(VecDeque::new);
+        if subgraph_logs.len() >= self.max_logs_per_subgraph {
+            subgraph_logs.pop_front();
+        }
+        subgraph_logs.push_back(entry);
+    }
+
+    fn find_logs(
+        &self,
+        subgraph_id: &SubgraphId,
+        filter: &SubgraphLogFilter,
+    ) -> Vec<SubgraphLogEntry> {
+        let logs = self.logs.lock().unwrap();
+        logs.get(subgraph_id)
+            .map(|subgraph_logs| {
+                subgraph_logs
+                    .iter()
+                    .rev()
+                    .filter(|entry| filter.matches(entry))
+                    .take(filter.first.unwrap_or(usize::max_value()))
+                    .cloned()
+                    .collect()
+            }).unwrap_or_default()
+    }
+
+    fn remove_logs(&self, subgraph_id: &SubgraphId) {
+        self.logs.lock().unwrap().remove(subgraph_id);
+    }
+}
+
+/// An slog `Drain` that writes the logs of a subgraph to a `SubgraphLogStore`.
+pub struct SubgraphLogStoreDrain<L> {
+    subgraph_id: SubgraphId,
+    log_store: Arc<L>,
+}
+
+impl<L> Drain for SubgraphLogStoreDrain<L>
+where
+    L: SubgraphLogStore,
+{
+    type Ok = ();
+    type Err = ();
+
+    fn log(&self, record: &Record, values: &OwnedKVList) -> Result<Self::Ok, Self::Err> {
+        self.log_store.add_log(SubgraphLogEntry {
+            subgraph_id: self.subgraph_id.clone(),
+            timestamp: Utc::now(),
+            level: record.level(),
+            text: log_text(record, values),
+        });
+        Ok(())
+    }
+}
+
+/// Creates a new asynchronous logger that writes the logs of a subgraph to
+/// a `SubgraphLogStore`.
+pub fn subgraph_log_store_logger<L>(subgraph_id: SubgraphId, log_store: Arc<L>) -> Logger
+where
+    L: SubgraphLogStore,
+{
+    let drain = SubgraphLogStoreDrain {
+        subgraph_id,
+        log_store,
+    };
+    let async_drain = slog_async::Async::new(drain.fuse()).build().fuse();
+    Logger::root(async_drain, o!())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use graph::chrono::{Duration, TimeZone};
+
+    fn entry(subgraph_id: &str, second: u32, level: Level, text: &str) -> SubgraphLogEntry {
+        SubgraphLogEntry {
+            subgraph_id: String::from(subgraph_id),
+            timestamp: Utc.ymd(2018, 11, 8).and_hms(0, 0, second),
+            level,
+            text: String::from(text),
+        }
+    }
+
+    fn texts(logs: Vec<SubgraphLogEntry>) -> Vec<String> {
+        logs.into_iter().map(|entry| entry.text).collect()
+    }
+
+    fn logs() -> RecentSubgraphLogs {
+        let logs = RecentSubgraphLogs::new(3);
+        logs.add_log(entry("a", 0, Level::Info, "dropped"));
+        logs.add_log(entry("a", 1, Level::Error, "Handler failed"));
+        logs.add_log(entry("a", 2, Level::Debug, "Processing block"));
+        logs.add_log(entry("a", 3, Level::Warning, "Retrying call"));
+        logs.add_log(entry("b", 4, Level::Error, "Other subgraph"));
+        logs
+    }
+
+    #[test]
+    fn keeps_most_recent_logs_per_subgraph() {
+        let logs = logs();
+        let filter = SubgraphLogFilter::default();
+
+        assert_eq!(
+            texts(logs.find_logs(&String::from("a"), &filter)),
+            vec!["Retrying call", "Processing block", "Handler failed"]
+        );
+        assert_eq!(
+            texts(logs.find_logs(&String::from("b"), &filter)),
+            vec!["Other subgraph"]
+        );
+
+        logs.remove_logs(&String::from("a"));
+        assert!(logs.find_logs(&String::from("a"), &filter).is_empty());
+    }
+
+    #[test]
+    fn filters_logs() {
+        let logs = logs();
+        let id = String::from("a");
+        let start = Utc.ymd(2018, 11, 8).and_hms(0, 0, 0);
+
+        let level = SubgraphLogFilter {
+            level: Some(Level::Warning),
+            ..SubgraphLogFilter::default()
+        };
+        assert_eq!(
+            texts(logs.find_logs(&id, &level)),
+            vec!["Retrying call", "Handler failed"]
+        );
+
+        let time_range = SubgraphLogFilter {
+            from: Some(start + Duration::seconds(2)),
+            to: Some(start + Duration::seconds(2)),
+            ..SubgraphLogFilter::default()
+        };
+        assert_eq!(
+            texts(logs.find_logs(&id, &time_range)),
+            vec!["Processing block"]
+        );
+
+        let text = SubgraphLogFilter {
+            text: Some(String::from("HANDLER")),
+            ..SubgraphLogFilter::default()
+        };
+        assert_eq!(texts(logs.find_logs(&id, &text)), vec!["Handler failed"]);
+
+        let first = SubgraphLogFilter {
+            first: Some(1),
+            ..SubgraphLogFilter::default()
+        };
+        assert_eq!(texts(logs.find_logs(&id, &first)), vec!["Retrying call"]);
+    }
+}
@@ -63,38 +63,52 @@ where
         self.hosts.iter().any(|host| host.matches_log(log))
     }
 
-    fn process_log(
+    fn heap_size(&self) -> usize {
+        self.hosts.iter().map(|host| host.heap_size()).sum()
+    }
+
+    fn process_logs(
         &self,
         logger: &Logger,
         block: Arc<EthereumBlock>,
-        transaction: Arc<Transaction>,
-        log: Log,
+        logs: Vec<Log>,
         entity_operations: Vec<EntityOperation>,
     ) -> Box<Future<Item = Vec<EntityOperation>, Error = Error> + Send> {
         let logger = logger.to_owned();
 
-        // Identify runtime hosts that will handle this event
-        let matching_hosts: Vec<_> = self
-            .hosts
-            .iter()
-            .filter(|host| host.matches_log(&log))
-            .cloned()
-            .collect();
+        // Identify the runtime hosts that will handle each event, in the same
+        // order the corresponding data sources appear in the subgraph manifest;
+        // consecutive events for the same host are passed to it as one batch
+        let mut batches: Vec<(Arc<T::Host>, Vec<(Arc<Transaction>, Arc<Log>)>)> = vec![];
+        for log in logs {
+            let transaction = match block.transaction_for_log(&log) {
+                Some(transaction) => Arc::new(transaction),
+                None => return Box::new(future::err(format_err!("Found no transaction for event"))),
+            };
+            let log = Arc::new(log);
 
-        let log = Arc::new(log);
+            for host in self.hosts.iter().filter(|host| host.matches_log(&log)) {
+                let extends_last_batch = batches
+                    .last()
+                    .map_or(false, |(batch_host, _)| Arc::ptr_eq(batch_host, host));
+
+                if extends_last_batch {
+                    batches
+                        .last_mut()
+                        .unwrap()
+                        .1
+                        .push((transaction.clone(), log.clone()));
+                } else {
+                    batches.push((host.clone(), vec![(transaction.clone(), log.clone())]));
+                }
+            }
+        }
 
-        // Process the log in each host in the same order the corresponding
-        // data sources appear in the subgraph manifest
-        Box::new(stream::iter_ok(matching_hosts).fold(
+        // Process the batches one after the other
+        Box::new(stream::iter_ok(batches).fold(
             entity_operations,
-            move |entity_operations, host| {
-                host.process_log(
-                    &logger,
-                    block.clone(),
-                    transaction.clone(),
-                    log.clone(),
-                    entity_operations,
-                )
+            move |entity_operations, (host, logs)| {
+                host.process_logs(&logger, block.clone(), logs, entity_operations)
             },
         ))
     }
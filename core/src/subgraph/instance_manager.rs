@@ -1,14 +1,17 @@
 use failure::*;
 use futures::sync::mpsc::{channel, Receiver, Sender};
+use graph::components::metrics::BLOCK_PROCESSING_DURATION;
 use graph::components::subgraph::SubgraphProviderEvent;
 use graph::prelude::{SubgraphInstance as SubgraphInstanceTrait, *};
 use std::collections::HashMap;
 use std::sync::RwLock;
+use std::thread;
 use std::time::Duration;
 
 use super::SubgraphInstance;
 use elastic_logger;
 use split_logger;
+use subgraph_log_store_logger;
 use ElasticDrainConfig;
 use ElasticLoggingConfig;
 
@@ -21,17 +24,19 @@ pub struct SubgraphInstanceManager {
 
 impl SubgraphInstanceManager {
     /// Creates a new runtime manager.
-    pub fn new<B, S, T>(
+    pub fn new<B, S, T, L>(
         logger: &Logger,
         store: Arc<S>,
         host_builder: T,
         block_stream_builder: B,
         elastic_config: Option<ElasticLoggingConfig>,
+        log_store: Arc<L>,
     ) -> Self
     where
         S: Store + ChainStore,
         T: RuntimeHostBuilder,
         B: BlockStreamBuilder + 'static,
+        L: SubgraphLogStore,
     {
         let logger = logger.new(o!("component" => "SubgraphInstanceManager"));
 
@@ -46,6 +51,7 @@ impl SubgraphInstanceManager {
             host_builder,
             block_stream_builder,
             elastic_config,
+            log_store,
         );
 
         SubgraphInstanceManager {
@@ -55,33 +61,46 @@ impl SubgraphInstanceManager {
     }
 
     /// Handle incoming events from subgraph providers.
-    fn handle_subgraph_events<B, S, T>(
+    fn handle_subgraph_events<B, S, T, L>(
         logger: Logger,
         receiver: Receiver<SubgraphProviderEvent>,
         store: Arc<S>,
         host_builder: T,
         block_stream_builder: B,
         elastic_config: Option<ElasticLoggingConfig>,
+        log_store: Arc<L>,
     ) where
         S: Store + ChainStore,
         T: RuntimeHostBuilder,
         B: BlockStreamBuilder + 'static,
+        L: SubgraphLogStore,
     {
         // Subgraph instance shutdown senders
         let instances: InstanceShutdownMap = Default::default();
 
+        // Limit the number of logs buffered per subgraph while Elasticsearch
+        // is slow or unavailable
+        let elastic_max_buffered_logs = parse_env_var("GRAPH_ELASTIC_MAX_BUFFERED_LOGS")
+            .unwrap_or_else(|e| panic!("{}", e))
+            .unwrap_or(10000);
+
         tokio::spawn(receiver.for_each(move |event| {
             use self::SubgraphProviderEvent::*;
 
             match event {
                 SubgraphStart(manifest) => {
-                    // Write subgraph logs to the terminal and, if enabled, Elasticsearch
+                    // Write subgraph logs to the terminal, to the log store (so that
+                    // they can be queried) and, if enabled, Elasticsearch
                     let term_logger = logger.new(o!("subgraph_id" => manifest.id.clone()));
+                    let local_logger = split_logger(
+                        term_logger.clone(),
+                        subgraph_log_store_logger(manifest.id.clone(), log_store.clone()),
+                    );
                     let logger = elastic_config
                         .clone()
                         .map(|elastic_config| {
                             split_logger(
-                                term_logger.clone(),
+                                local_logger.clone(),
                                 elastic_logger(
                                     ElasticDrainConfig {
                                         general: elastic_config,
@@ -89,11 +108,12 @@ impl SubgraphInstanceManager {
                                         document_type: String::from("log"),
                                         subgraph_id: String::from(manifest.id.clone()),
                                         flush_interval: Duration::from_secs(5),
+                                        max_buffer_size: elastic_max_buffered_logs,
                                     },
                                     term_logger.clone(),
                                 ),
                             )
-                        }).unwrap_or(term_logger);
+                        }).unwrap_or(local_logger);
 
                     info!(logger, "Start subgraph");
 
@@ -109,6 +129,12 @@ impl SubgraphInstanceManager {
                 }
                 SubgraphStop(id) => {
                     info!(logger, "Stopping subgraph"; "subgraph_id" => &id);
+                    Self::stop_subgraph(instances.clone(), id.clone());
+                    log_store.remove_logs(&id);
+                }
+                SubgraphPause(id) => {
+                    // Keep the logs around, they are still useful while paused
+                    info!(logger, "Pausing subgraph"; "subgraph_id" => &id);
                     Self::stop_subgraph(instances.clone(), id);
                 }
             };
@@ -134,6 +160,26 @@ impl SubgraphInstanceManager {
         let id_for_block = manifest.id.clone();
         let id_for_err = manifest.id.clone();
 
+        // Create the indexes for the full-text searches declared in the schema
+        // in the background, as building them can take a while
+        let full_text_definitions = manifest.schema.full_text_definitions();
+        if !full_text_definitions.is_empty() {
+            let index_logger = logger.clone();
+            let index_store = store.clone();
+            let index_id = manifest.id.clone();
+            thread::spawn(move || {
+                index_store
+                    .create_full_text_indexes(index_id, full_text_definitions)
+                    .map_err(|e| {
+                        error!(
+                            index_logger,
+                            "Failed to create full-text search indexes: {}", e
+                        )
+                    })
+                    .ok();
+            });
+        }
+
         // Request a block stream for this subgraph
         let block_stream_canceler = CancelGuard::new();
         let block_stream = block_stream_builder
@@ -166,6 +212,10 @@ impl SubgraphInstanceManager {
 
                     info!(logger, "Processing events from block");
 
+                    let timer = BLOCK_PROCESSING_DURATION
+                        .with_label_values(&[id.as_str()])
+                        .start_timer();
+
                     // Extract logs relevant to the subgraph
                     let logs: Vec<_> = block
                         .transaction_receipts
@@ -189,31 +239,18 @@ impl SubgraphInstanceManager {
                     let block_for_transact = block_for_process.clone();
                     let logger_for_process = logger;
                     let logger_for_transact = logger_for_process.clone();
-                    stream::iter_ok::<_, CancelableError<Error>>(logs)
-                        .fold(vec![], move |entity_operations, log| {
-                            let logger = logger_for_process.clone();
-                            let instance = instance.clone();
-                            let block = block_for_process.clone();
-
-                            let transaction = block
-                                .transaction_for_log(&log)
-                                .map(Arc::new)
-                                .ok_or_else(|| format_err!("Found no transaction for event"));
-
-                            future::result(transaction).and_then(move |transaction| {
-                                instance
-                                    .process_log(
-                                        &logger,
-                                        block,
-                                        transaction,
-                                        log,
-                                        entity_operations,
-                                    ).map_err(|e| format_err!("Failed to process event: {}", e))
-                            })
-                        }).and_then(move |entity_operations| {
+                    let instance_for_transact = instance.clone();
+                    instance
+                        .process_logs(&logger_for_process, block_for_process, logs, vec![])
+                        .map_err(|e| format_err!("Failed to process event: {}", e))
+                        .from_err::<CancelableError<Error>>()
+                        .and_then(move |entity_operations| {
                             let block = block_for_transact.clone();
                             let logger = logger_for_transact.clone();
 
+                            info!(logger, "Mapping heap usage after processing events";
+                                 "heap_size" => instance_for_transact.heap_size());
+
                             let block_ptr_now = EthereumBlockPointer::to_parent(&block);
                             let block_ptr_after = EthereumBlockPointer::from(&*block);
 
@@ -229,7 +266,7 @@ impl SubgraphInstanceManager {
                             )).map_err(|e| {
                                 format_err!("Error while processing block stream for a subgraph: {}", e)
                             }).from_err()
-                        })
+                        }).map(move |()| timer.observe_duration())
                 }).map_err(move |e| {
                     match e {
                         CancelableError::Cancel => {
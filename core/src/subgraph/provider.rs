@@ -1,11 +1,12 @@
 use futures::sync::mpsc::{channel, Receiver, Sender};
 use graph::data::subgraph::schema::{SubgraphEntity, SUBGRAPHS_ID};
 use graph::prelude::{SubgraphProvider as SubgraphProviderTrait, *};
+use graph::web3::types::H256;
 use std::collections::HashSet;
 use std::sync::Mutex;
 use std::time::{SystemTime, UNIX_EPOCH};
 
-pub struct SubgraphProvider<L, S> {
+pub struct SubgraphProvider<L, S, E> {
     logger: slog::Logger,
     event_stream: Option<Receiver<SubgraphProviderEvent>>,
     event_sink: Sender<SubgraphProviderEvent>,
@@ -13,14 +14,16 @@ pub struct SubgraphProvider<L, S> {
     schema_event_sink: Sender<SchemaEvent>,
     resolver: Arc<L>,
     subgraphs_running: Arc<Mutex<HashSet<SubgraphId>>>,
+    subgraphs_paused: Arc<Mutex<HashSet<SubgraphId>>>,
     store: Arc<S>,
+    ethereum: Arc<E>,
 }
 
-impl<L, S> SubgraphProvider<L, S>
+impl<L, S, E> SubgraphProvider<L, S, E>
 where
     L: LinkResolver,
 {
-    pub fn new(logger: slog::Logger, resolver: Arc<L>, store: Arc<S>) -> Self {
+    pub fn new(logger: slog::Logger, resolver: Arc<L>, store: Arc<S>, ethereum: Arc<E>) -> Self {
         let (schema_event_sink, schema_event_stream) = channel(100);
         let (event_sink, event_stream) = channel(100);
 
@@ -33,7 +36,9 @@ where
             schema_event_sink,
             resolver,
             subgraphs_running: Arc::new(Mutex::new(HashSet::new())),
+            subgraphs_paused: Arc::new(Mutex::new(HashSet::new())),
             store,
+            ethereum,
         };
 
         provider.send_builtin_schema(&include_str!("subgraphs.graphql"), SUBGRAPHS_ID.to_owned());
@@ -53,7 +58,10 @@ where
             .expect("failed to forward builtin schema")
     }
 
-    fn send_add_events(&self, subgraph: SubgraphManifest) -> impl Future<Item = (), Error = Error> {
+    fn send_add_events(
+        &self,
+        subgraph: SubgraphManifest,
+    ) -> Box<Future<Item = (), Error = Error> + Send> {
         let schema_addition = self
             .schema_event_sink
             .clone()
@@ -61,6 +69,12 @@ where
             .map_err(|e| panic!("failed to forward subgraph schema: {}", e))
             .map(|_| ());
 
+        // Subgraphs that were paused before the node restarted can be
+        // queried, but are not processed until they are resumed
+        if self.subgraphs_paused.lock().unwrap().contains(&subgraph.id) {
+            return Box::new(schema_addition);
+        }
+
         let subgraph_start = self
             .event_sink
             .clone()
@@ -68,7 +82,7 @@ where
             .map_err(|e| panic!("failed to forward subgraph: {}", e))
             .map(|_| ());
 
-        schema_addition.join(subgraph_start).map(|_| ())
+        Box::new(schema_addition.join(subgraph_start).map(|_| ()))
     }
 
     fn send_remove_events(
@@ -92,6 +106,76 @@ where
         schema_removal.join(subgraph_stop).map(|_| ())
     }
 
+    /// Reverts all blocks processed by a subgraph after `block_number`, in a
+    /// single transaction.
+    fn revert_to_block(
+        &self,
+        id: SubgraphId,
+        block_number: u64,
+        block_hash: Option<H256>,
+    ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send>
+    where
+        S: Store,
+        E: EthereumAdapter,
+    {
+        let block_ptr_from = match self.store.block_ptr(id.clone()) {
+            Ok(block_ptr) => block_ptr,
+            Err(e) => return Box::new(future::err(e.into())),
+        };
+        if block_number > block_ptr_from.number {
+            return Box::new(future::err(rewind_error(
+                &id,
+                format!(
+                    "block {} has not been processed yet, the subgraph is at block {}",
+                    block_number, block_ptr_from.number
+                ),
+            )));
+        }
+
+        let logger = self.logger.clone();
+        let store = self.store.clone();
+
+        // Blocks without events for the subgraph are skipped while indexing
+        // and don't end up in the chain store, so ask the Ethereum node
+        Box::new(
+            self.ethereum
+                .block_hash_by_block_number(&self.logger, block_number)
+                .from_err()
+                .and_then(move |hash| {
+                    let hash = hash.ok_or_else(|| {
+                        rewind_error(&id, format!("block {} was not found", block_number))
+                    })?;
+                    match block_hash {
+                        Some(block_hash) if block_hash != hash => {
+                            return Err(rewind_error(
+                                &id,
+                                format!(
+                                    "block {} does not have hash {:?}",
+                                    block_number, block_hash
+                                ),
+                            ))
+                        }
+                        _ => (),
+                    }
+
+                    info!(
+                        logger,
+                        "Rewind subgraph";
+                        "subgraph_id" => &id,
+                        "from_block_number" => block_ptr_from.number,
+                        "to_block_number" => block_number
+                    );
+
+                    let block_ptr_to = EthereumBlockPointer {
+                        hash,
+                        number: block_number,
+                    };
+                    store.revert_to_block(id, block_ptr_from, block_ptr_to)?;
+                    Ok(())
+                }),
+        )
+    }
+
     /// Clones but forcing receivers to `None`.
     fn clone(&self) -> Self {
         SubgraphProvider {
@@ -102,15 +186,18 @@ where
             schema_event_sink: self.schema_event_sink.clone(),
             resolver: self.resolver.clone(),
             subgraphs_running: self.subgraphs_running.clone(),
+            subgraphs_paused: self.subgraphs_paused.clone(),
             store: self.store.clone(),
+            ethereum: self.ethereum.clone(),
         }
     }
 }
 
-impl<L, S> SubgraphProviderTrait for SubgraphProvider<L, S>
+impl<L, S, E> SubgraphProviderTrait for SubgraphProvider<L, S, E>
 where
     L: LinkResolver,
     S: Store,
+    E: EthereumAdapter,
 {
     fn start(
         &self,
@@ -136,6 +223,25 @@ where
                         )));
                     }
 
+                    match self_clone.store.is_subgraph_paused(subgraph.id.clone()) {
+                        Ok(true) => {
+                            self_clone
+                                .subgraphs_paused
+                                .lock()
+                                .unwrap()
+                                .insert(subgraph.id.clone());
+                        }
+                        Ok(false) => (),
+                        Err(e) => {
+                            self_clone
+                                .subgraphs_running
+                                .lock()
+                                .unwrap()
+                                .remove(&subgraph.id);
+                            return Box::new(future::err(e.into()));
+                        }
+                    }
+
                     SubgraphEntity::new(
                         &subgraph,
                         SystemTime::now()
@@ -162,15 +268,165 @@ where
     ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static> {
         // If subgraph ID was in set
         if self.subgraphs_running.lock().unwrap().remove(&id) {
+            // A subgraph that is started again later starts out unpaused
+            if self.subgraphs_paused.lock().unwrap().remove(&id) {
+                if let Err(e) = self.store.set_subgraph_paused(id.clone(), false) {
+                    return Box::new(future::err(e.into()));
+                }
+            }
+
             // Shut down subgraph processing
             Box::new(self.send_remove_events(id))
         } else {
             Box::new(future::err(SubgraphProviderError::NotRunning(id)))
         }
     }
+
+    fn pause(
+        &self,
+        id: SubgraphId,
+    ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static> {
+        if !self.subgraphs_running.lock().unwrap().contains(&id) {
+            return Box::new(future::err(SubgraphProviderError::NotRunning(id)));
+        }
+        if !self.subgraphs_paused.lock().unwrap().insert(id.clone()) {
+            return Box::new(future::err(SubgraphProviderError::AlreadyPaused(id)));
+        }
+
+        // Remember the pause, so that the subgraph stays paused when the
+        // node restarts
+        if let Err(e) = self.store.set_subgraph_paused(id.clone(), true) {
+            self.subgraphs_paused.lock().unwrap().remove(&id);
+            return Box::new(future::err(e.into()));
+        }
+
+        // Shut down subgraph processing but keep the schema, so that the
+        // subgraph can still be queried
+        Box::new(
+            self.event_sink
+                .clone()
+                .send(SubgraphProviderEvent::SubgraphPause(id))
+                .map_err(|e| {
+                    SubgraphProviderError::Unknown(format_err!(
+                        "failed to forward subgraph pause event: {}",
+                        e
+                    ))
+                }).map(|_| ()),
+        )
+    }
+
+    fn resume(
+        &self,
+        id: SubgraphId,
+    ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static> {
+        if !self.subgraphs_paused.lock().unwrap().contains(&id) {
+            return Box::new(future::err(SubgraphProviderError::NotPaused(id)));
+        }
+
+        let self_clone = self.clone();
+        let link = format!("/ipfs/{}", id);
+
+        Box::new(
+            SubgraphManifest::resolve(Link { link }, self.resolver.clone())
+                .map_err(SubgraphProviderError::ResolveError)
+                .and_then(move |subgraph| -> Box<Future<Item = _, Error = _> + Send> {
+                    if let Err(e) = self_clone.store.set_subgraph_paused(id.clone(), false) {
+                        return Box::new(future::err(e.into()));
+                    }
+                    self_clone.subgraphs_paused.lock().unwrap().remove(&id);
+
+                    // The schema is still in place, only restart processing
+                    Box::new(
+                        self_clone
+                            .event_sink
+                            .clone()
+                            .send(SubgraphProviderEvent::SubgraphStart(subgraph))
+                            .map_err(|e| {
+                                SubgraphProviderError::Unknown(format_err!(
+                                    "failed to forward subgraph: {}",
+                                    e
+                                ))
+                            }).map(|_| ()),
+                    )
+                }),
+        )
+    }
+
+    fn reindex(
+        &self,
+        id: SubgraphId,
+    ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static> {
+        let self_clone = self.clone();
+        let store = self.store.clone();
+
+        // Reindexing a paused subgraph leaves it paused
+        let was_paused = self.subgraphs_paused.lock().unwrap().contains(&id);
+        let paused: Box<Future<Item = (), Error = SubgraphProviderError> + Send> = if was_paused {
+            Box::new(future::ok(()))
+        } else {
+            self.pause(id.clone())
+        };
+
+        Box::new(
+            paused
+                .and_then(move |()| -> Result<_, SubgraphProviderError> {
+                    store.remove_subgraph_data(id.clone())?;
+                    Ok(id)
+                }).and_then(move |id| -> Box<Future<Item = _, Error = _> + Send> {
+                    if was_paused {
+                        Box::new(future::ok(()))
+                    } else {
+                        self_clone.resume(id)
+                    }
+                }),
+        )
+    }
+
+    fn rewind(
+        &self,
+        id: SubgraphId,
+        block_number: u64,
+        block_hash: Option<H256>,
+    ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static> {
+        let self_clone = self.clone();
+
+        // Rewinding a paused subgraph leaves it paused
+        let was_paused = self.subgraphs_paused.lock().unwrap().contains(&id);
+        let paused: Box<Future<Item = (), Error = SubgraphProviderError> + Send> = if was_paused {
+            Box::new(future::ok(()))
+        } else {
+            self.pause(id.clone())
+        };
+
+        Box::new(paused.and_then(move |()| {
+            let reverted = self_clone.revert_to_block(id.clone(), block_number, block_hash);
+
+            // Reverting happens in one transaction, so the subgraph continues
+            // either from the target block or from where it was paused
+            reverted.then(move |result| {
+                let resumed: Box<Future<Item = (), Error = SubgraphProviderError> + Send> =
+                    if was_paused {
+                        Box::new(future::ok(()))
+                    } else {
+                        self_clone.resume(id)
+                    };
+                resumed.and_then(move |()| result)
+            })
+        }))
+    }
+
+    fn status(&self, id: &SubgraphId) -> SubgraphStatus {
+        if self.subgraphs_paused.lock().unwrap().contains(id) {
+            SubgraphStatus::Paused
+        } else if self.subgraphs_running.lock().unwrap().contains(id) {
+            SubgraphStatus::Running
+        } else {
+            SubgraphStatus::Stopped
+        }
+    }
 }
 
-impl<L, S> EventProducer<SubgraphProviderEvent> for SubgraphProvider<L, S> {
+impl<L, S, E> EventProducer<SubgraphProviderEvent> for SubgraphProvider<L, S, E> {
     fn take_event_stream(
         &mut self,
     ) -> Option<Box<Stream<Item = SubgraphProviderEvent, Error = ()> + Send>> {
@@ -180,10 +436,14 @@ impl<L, S> EventProducer<SubgraphProviderEvent> for SubgraphProvider<L, S> {
     }
 }
 
-impl<L, S> EventProducer<SchemaEvent> for SubgraphProvider<L, S> {
+impl<L, S, E> EventProducer<SchemaEvent> for SubgraphProvider<L, S, E> {
     fn take_event_stream(&mut self) -> Option<Box<Stream<Item = SchemaEvent, Error = ()> + Send>> {
         self.schema_event_stream
             .take()
             .map(|s| Box::new(s) as Box<Stream<Item = SchemaEvent, Error = ()> + Send>)
     }
 }
+
+fn rewind_error(id: &SubgraphId, message: String) -> SubgraphProviderError {
+    SubgraphProviderError::RewindError(format!("subgraph with ID {}: {}", id, message))
+}
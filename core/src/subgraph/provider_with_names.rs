@@ -1,14 +1,38 @@
-use std::collections::HashSet;
+use futures::sync::oneshot;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
 
 use graph::prelude::{
     SubgraphProvider as SubgraphProviderTrait,
     SubgraphProviderWithNames as SubgraphProviderWithNamesTrait, *,
 };
+use graph::tokio::timer::Interval;
+use graph::web3::types::H256;
 
+/// How often to check whether pending subgraph versions have caught up with
+/// the chain head.
+const PENDING_VERSIONS_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How many blocks a pending subgraph version may be behind the chain head
+/// for its subgraph name to switch over to it.
+const MAX_PENDING_BLOCKS_BEHIND: u64 = 1;
+
+/// Maps subgraph names to subgraph versions (IDs).
+///
+/// Each name has a current version, which queries against the name are served
+/// from, and optionally a pending version. Deploying a new version to a name
+/// that already has a current version makes it the pending version: it is
+/// indexed in the background and the name switches over to it once it has
+/// caught up with the chain head (see `poll_pending_versions`).
 pub struct SubgraphProviderWithNames<P, S> {
     logger: slog::Logger,
     provider: Arc<P>,
     store: Arc<S>,
+    /// Held while collecting garbage, so that only one collection runs at a
+    /// time.
+    gc_lock: Arc<Mutex<()>>,
 }
 
 impl<P, S> Clone for SubgraphProviderWithNames<P, S> {
@@ -17,6 +41,7 @@ impl<P, S> Clone for SubgraphProviderWithNames<P, S> {
             logger: self.logger.clone(),
             provider: self.provider.clone(),
             store: self.store.clone(),
+            gc_lock: self.gc_lock.clone(),
         }
     }
 }
@@ -36,6 +61,7 @@ where
             logger: logger.new(o!("component" => "SubgraphProviderWithNames")),
             provider,
             store,
+            gc_lock: Arc::new(Mutex::new(())),
         };
 
         // Deploy named subgraph found in store
@@ -45,20 +71,261 @@ where
     fn deploy_saved_subgraphs(&self) -> impl Future<Item = (), Error = Error> {
         let self_clone = self.clone();
 
-        future::result(self.store.read_all_subgraph_names()).and_then(
-            move |subgraph_names_and_ids| {
-                let self_clone = self_clone.clone();
+        future::result(self.referenced_subgraph_ids()).and_then(move |subgraph_ids| {
+            stream::iter_ok(subgraph_ids)
+                .for_each(move |id| self_clone.provider.start(id).from_err())
+        })
+    }
+
+    /// Returns the IDs of all subgraph versions that are the current or the
+    /// pending version of a subgraph name.
+    fn referenced_subgraph_ids(&self) -> Result<HashSet<SubgraphId>, Error> {
+        let current_ids = self
+            .store
+            .read_all_subgraph_names()?
+            .into_iter()
+            .filter_map(|(_name, id_opt)| id_opt);
+        let pending_ids = self
+            .store
+            .read_all_pending_subgraph_ids()?
+            .into_iter()
+            .map(|(_name, id)| id);
+        Ok(current_ids.chain(pending_ids).collect())
+    }
 
-                let subgraph_ids = subgraph_names_and_ids
-                    .into_iter()
-                    .filter_map(|(_name, id_opt)| id_opt)
-                    .collect::<HashSet<SubgraphId>>();
+    /// Changes subgraph names in the store, then starts the subgraphs that are
+    /// now used by a name and stops the subgraphs that no longer are.
+    ///
+    /// Note: there's a possible race condition if names are changed at the
+    /// same time. That's fine for now and will be fixed when this is redone
+    /// for the hosted service.
+    fn update_names<F>(
+        &self,
+        update: F,
+    ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static>
+    where
+        F: FnOnce(&S) -> Result<(), Error>,
+    {
+        let ids_before_and_after = self.referenced_subgraph_ids().and_then(|before| {
+            update(&*self.store)?;
+            Ok((before, self.referenced_subgraph_ids()?))
+        });
+        let (before, after) = match ids_before_and_after {
+            Ok(ids) => ids,
+            Err(e) => return Box::new(future::err(e.into())),
+        };
+
+        let stopped_ids = before.difference(&after).cloned().collect::<Vec<_>>();
+        let started_ids = after.difference(&before).cloned().collect::<Vec<_>>();
 
-                stream::iter_ok(subgraph_ids)
-                    .for_each(move |id| self_clone.provider.start(id).from_err())
-            },
+        let provider = self.provider.clone();
+        let provider_clone = self.provider.clone();
+        Box::new(
+            stream::iter_ok(stopped_ids)
+                .for_each(move |id| provider.stop(id))
+                .and_then(move |()| {
+                    stream::iter_ok(started_ids).for_each(move |id| provider_clone.start(id))
+                }),
         )
     }
+
+    /// Returns the IDs of the current and pending versions of a subgraph name.
+    fn subgraph_ids_by_name(&self, name: &str) -> Result<Vec<SubgraphId>, SubgraphProviderError> {
+        let current_id = self
+            .store
+            .read_subgraph_name(name.to_owned())?
+            .ok_or_else(|| SubgraphProviderError::NameNotFound(name.to_owned()))?;
+        let pending_id = self
+            .store
+            .read_all_pending_subgraph_ids()?
+            .into_iter()
+            .find(|(pending_name, _id)| pending_name == name)
+            .map(|(_name, id)| id);
+        Ok(current_id.into_iter().chain(pending_id).collect())
+    }
+
+    /// Applies `op` to the current and pending versions of a subgraph name,
+    /// one after the other.
+    fn for_each_version<F>(
+        &self,
+        name: String,
+        op: F,
+    ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static>
+    where
+        F: Fn(&P, SubgraphId) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send>
+            + Send
+            + 'static,
+    {
+        let ids = match self.subgraph_ids_by_name(&name) {
+            Ok(ids) => ids,
+            Err(e) => return Box::new(future::err(e)),
+        };
+
+        let provider = self.provider.clone();
+        Box::new(stream::iter_ok(ids).for_each(move |id| op(&*provider, id)))
+    }
+
+    fn version_status(&self, id: SubgraphId) -> SubgraphVersionStatus {
+        SubgraphVersionStatus {
+            status: self.provider.status(&id),
+            // The subgraph may not have been added to the store yet
+            latest_block_number: self.store.block_ptr(id.clone()).ok().map(|ptr| ptr.number),
+            id,
+        }
+    }
+
+    /// Removes the data of subgraph versions that are neither running nor
+    /// referred to by a subgraph name, see `collect_garbage`. Blocks until
+    /// all of them are removed.
+    fn collect_garbage_blocking(
+        &self,
+        dry_run: bool,
+    ) -> Result<Vec<UnusedSubgraphVersion>, SubgraphProviderError> {
+        let _gc_guard = self.gc_lock.lock().unwrap();
+
+        let referenced_ids = self.referenced_subgraph_ids()?;
+        let unused_ids = self
+            .store
+            .read_all_subgraph_ids()?
+            .into_iter()
+            .filter(|id| !referenced_ids.contains(id))
+            // Subgraphs are only stopped after names stop referring to them
+            .filter(|id| self.provider.status(id) == SubgraphStatus::Stopped);
+
+        if dry_run {
+            return Ok(unused_ids
+                .map(|id| UnusedSubgraphVersion {
+                    id,
+                    entity_count: None,
+                }).collect());
+        }
+
+        let mut removed_versions = vec![];
+        for id in unused_ids {
+            // The store checks the subgraph names again when removing the
+            // version, in case it has been deployed in the meantime
+            if let Some(entity_count) = self.store.remove_unused_subgraph(id.clone())? {
+                info!(
+                    self.logger,
+                    "Removed unused subgraph version";
+                    "subgraph_id" => &id,
+                    "entity_count" => entity_count
+                );
+                removed_versions.push(UnusedSubgraphVersion {
+                    id,
+                    entity_count: Some(entity_count),
+                });
+            }
+        }
+
+        Ok(removed_versions)
+    }
+
+    /// Switches a subgraph name over to its pending version `id`, unless the
+    /// pending version has changed in the meantime.
+    fn promote_pending_version(
+        &self,
+        name: String,
+        id: SubgraphId,
+    ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static> {
+        info!(
+            self.logger,
+            "Switch subgraph name over to pending version";
+            "subgraph_name" => &name,
+            "subgraph_id" => &id
+        );
+
+        self.update_names(move |store| store.promote_pending_subgraph_id(name, id).map(|_| ()))
+    }
+}
+
+impl<P, S> SubgraphProviderWithNames<P, S>
+where
+    P: SubgraphProviderTrait,
+    S: Store + ChainStore,
+{
+    /// Periodically removes the data of subgraph versions that are no longer
+    /// used, see `collect_garbage`, on a separate thread.
+    ///
+    /// If `dry_run` is set, the versions that would be removed are only logged.
+    pub fn collect_garbage_periodically(&self, interval: Duration, dry_run: bool) {
+        let self_clone = self.clone();
+
+        thread::spawn(move || loop {
+            thread::sleep(interval);
+
+            match self_clone.collect_garbage_blocking(dry_run) {
+                Ok(ref versions) if dry_run && !versions.is_empty() => info!(
+                    self_clone.logger,
+                    "Found unused subgraph versions to remove (dry run)";
+                    "subgraph_ids" => versions
+                        .iter()
+                        .map(|version| version.id.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                Ok(_) => (),
+                Err(e) => error!(
+                    self_clone.logger,
+                    "Failed to remove unused subgraph versions: {}", e
+                ),
+            };
+        });
+    }
+
+    /// Periodically switches subgraph names over to their pending versions
+    /// once these have caught up with the chain head.
+    pub fn poll_pending_versions(&self) -> impl Future<Item = (), Error = ()> + Send {
+        let self_clone = self.clone();
+        let interval_logger = self.logger.clone();
+
+        Interval::new_interval(PENDING_VERSIONS_POLL_INTERVAL)
+            .map_err(move |e| {
+                error!(
+                    interval_logger,
+                    "Error in pending subgraph versions interval: {}", e
+                );
+            }).for_each(move |_| {
+                let logger = self_clone.logger.clone();
+                let self_clone = self_clone.clone();
+
+                future::result(self_clone.caught_up_pending_versions())
+                    .from_err()
+                    .and_then(move |versions| {
+                        stream::iter_ok(versions).for_each(move |(name, id)| {
+                            self_clone.promote_pending_version(name, id)
+                        })
+                    }).or_else(move |e| {
+                        error!(
+                            logger,
+                            "Failed to switch subgraph names over to pending versions: {}", e
+                        );
+                        Ok(())
+                    })
+            })
+    }
+
+    /// Returns the subgraph names and pending versions for which the pending
+    /// version has caught up with the chain head.
+    fn caught_up_pending_versions(&self) -> Result<Vec<(String, SubgraphId)>, Error> {
+        let chain_head_ptr = match self.store.chain_head_ptr()? {
+            Some(ptr) => ptr,
+            None => return Ok(vec![]),
+        };
+
+        Ok(self
+            .store
+            .read_all_pending_subgraph_ids()?
+            .into_iter()
+            .filter(|(_name, id)| {
+                // The subgraph may not have been added to the store yet
+                self.store
+                    .block_ptr(id.clone())
+                    .map(|block_ptr| {
+                        block_ptr.number + MAX_PENDING_BLOCKS_BEHIND >= chain_head_ptr.number
+                    }).unwrap_or(false)
+            }).collect())
+    }
 }
 
 impl<P, S> SubgraphProviderWithNamesTrait for SubgraphProviderWithNames<P, S>
@@ -71,8 +338,6 @@ where
         name: String,
         id: SubgraphId,
     ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static> {
-        let self_clone = self.clone();
-
         // Check that the name contains only allowed characters.
         if !name
             .chars()
@@ -81,94 +346,124 @@ where
             return Box::new(future::err(SubgraphProviderError::InvalidName(name)));
         }
 
-        Box::new(
-            // Cleanly undeploy this name if it exists (removes old mapping)
-            self.remove(name.clone())
-                .then(|result| {
-                    match result {
-                        // Name not found is fine. No old mapping to remove.
-                        Err(SubgraphProviderError::NameNotFound(_)) => Ok(()),
-                        other => other,
-                    }
-                }).and_then(move |()| {
-                    future::result(self_clone.store.find_subgraph_names_by_id(id.clone()))
-                        .from_err()
-                        .and_then(move |existing_names| {
-                            // Add new mapping
-                            future::result(
-                                self_clone
-                                    .store
-                                    .write_subgraph_name(name.clone(), Some(id.clone())),
-                            ).from_err()
-                            .and_then(
-                                move |()| -> Box<Future<Item = _, Error = _> + Send> {
-                                    if existing_names.is_empty() {
-                                        // Start subgraph processing
-                                        Box::new(self_clone.provider.start(id))
-                                    } else {
-                                        // Subgraph is already started
-                                        Box::new(future::ok(()))
-                                    }
-                                },
-                            )
-                        })
-                }),
-        )
+        let current_id = match self.store.read_subgraph_name(name.clone()) {
+            Ok(id_opt_opt) => id_opt_opt.and_then(|id_opt| id_opt),
+            Err(e) => return Box::new(future::err(e.into())),
+        };
+
+        match current_id {
+            // The name already uses this version, drop any pending version
+            Some(ref current_id) if *current_id == id => {
+                self.update_names(move |store| store.write_pending_subgraph_id(name, None))
+            }
+            // Keep serving queries from the current version until the new
+            // version has caught up with the chain head
+            Some(_) => {
+                info!(
+                    self.logger,
+                    "Deploy pending subgraph version";
+                    "subgraph_name" => &name,
+                    "subgraph_id" => &id
+                );
+                self.update_names(move |store| store.write_pending_subgraph_id(name, Some(id)))
+            }
+            // There is no version to serve queries from yet, switch right away
+            None => self.update_names(move |store| {
+                store.write_subgraph_name(name.clone(), Some(id))?;
+                store.write_pending_subgraph_id(name, None)
+            }),
+        }
     }
 
     fn remove(
         &self,
         name: String,
     ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static> {
+        match self.store.read_subgraph_name(name.clone()) {
+            Ok(Some(_)) => self.update_names(move |store| store.delete_subgraph_name(name)),
+            Ok(None) => Box::new(future::err(SubgraphProviderError::NameNotFound(name))),
+            Err(e) => Box::new(future::err(e.into())),
+        }
+    }
+
+    fn list(&self) -> Result<Vec<SubgraphNameStatus>, SubgraphProviderError> {
+        let pending_ids = self
+            .store
+            .read_all_pending_subgraph_ids()?
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+
+        Ok(self
+            .store
+            .read_all_subgraph_names()?
+            .into_iter()
+            .map(|(name, current_id)| SubgraphNameStatus {
+                current_version: current_id.map(|id| self.version_status(id)),
+                pending_version: pending_ids
+                    .get(&name)
+                    .map(|id| self.version_status(id.clone())),
+                name,
+            }).collect())
+    }
+
+    fn pause(
+        &self,
+        name: String,
+    ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static> {
+        self.for_each_version(name, |provider, id| provider.pause(id))
+    }
+
+    fn resume(
+        &self,
+        name: String,
+    ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static> {
+        self.for_each_version(name, |provider, id| provider.resume(id))
+    }
+
+    fn reindex(
+        &self,
+        name: String,
+    ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static> {
+        self.for_each_version(name, |provider, id| provider.reindex(id))
+    }
+
+    fn collect_garbage(
+        &self,
+        dry_run: bool,
+    ) -> Box<
+        Future<Item = Vec<UnusedSubgraphVersion>, Error = SubgraphProviderError> + Send + 'static,
+    > {
         let self_clone = self.clone();
-        let store = self.store.clone();
-        let name_clone = name.clone();
+        let (sender, receiver) = oneshot::channel();
 
-        // Look up name mapping
-        Box::new(
-            future::result(store.read_subgraph_name(name.clone()))
-                .from_err()
-                .and_then(move |id_opt_opt: Option<Option<SubgraphId>>| {
-                    id_opt_opt
-                        .ok_or_else(|| SubgraphProviderError::NameNotFound(name_clone.clone()))
-                }).and_then(move |id_opt: Option<SubgraphId>| {
-                    let store = store.clone();
-
-                    // Delete this name->ID mapping
-                    future::result(store.delete_subgraph_name(name.clone()))
-                        .from_err()
-                        .and_then(move |()| -> Box<Future<Item = _, Error = _> + Send> {
-                            let store = store.clone();
-
-                            // If name mapping pointed to a non-null ID
-                            if let Some(id) = id_opt {
-                                Box::new(
-                                    future::result(store.find_subgraph_names_by_id(id.clone()))
-                                        .from_err()
-                                        .and_then(move |names| -> Box<Future<Item=_, Error=_> + Send> {
-                                            // If this subgraph ID is no longer pointed to by any
-                                            // subgraph names
-                                            if names.is_empty() {
-                                                // Shut down subgraph processing
-                                                // Note: there's a possible race condition if two
-                                                // names pointing to the same ID are removed at the
-                                                // same time.
-                                                // That's fine for now and will be fixed when this
-                                                // is redone for the hosted service.
-                                                Box::new(self_clone.provider.stop(id))
-                                            } else {
-                                                // Leave subgraph running
-                                                Box::new(future::ok(()))
-                                            }
-                                        })
-                                )
-                            } else {
-                                // Nothing to shut down
-                                Box::new(future::ok(()))
-                            }
-                        })
-                }),
-        )
+        // Removing subgraph versions can take a long time; don't block an
+        // executor thread with it
+        thread::spawn(move || {
+            let result = self_clone.collect_garbage_blocking(dry_run);
+            sender.send(result).ok();
+        });
+
+        Box::new(receiver.then(|result| match result {
+            Ok(result) => result,
+            Err(_) => Err(format_err!("subgraph garbage collection was canceled").into()),
+        }))
+    }
+
+    fn rewind(
+        &self,
+        name: String,
+        block_number: u64,
+        block_hash: Option<H256>,
+    ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static> {
+        match self.store.read_subgraph_name(name.clone()) {
+            Ok(Some(Some(id))) => self.provider.rewind(id, block_number, block_hash),
+            Ok(Some(None)) => Box::new(future::err(SubgraphProviderError::RewindError(format!(
+                "no subgraph version is deployed under name {} yet",
+                name
+            )))),
+            Ok(None) => Box::new(future::err(SubgraphProviderError::NameNotFound(name))),
+            Err(e) => Box::new(future::err(e.into())),
+        }
     }
 }
 
@@ -176,7 +471,305 @@ where
 mod tests {
     use super::super::SubgraphProvider;
     use super::*;
-    use graph_mock::MockStore;
+    use graph_mock::{MockEthereumAdapter, MockStore};
+    use std::sync::Mutex;
+
+    /// A subgraph provider that records which subgraphs it starts, stops,
+    /// pauses, resumes, reindexes and rewinds.
+    #[derive(Default)]
+    struct RecordingProvider {
+        started: Mutex<Vec<SubgraphId>>,
+        stopped: Mutex<Vec<SubgraphId>>,
+        paused: Mutex<Vec<SubgraphId>>,
+        resumed: Mutex<Vec<SubgraphId>>,
+        reindexed: Mutex<Vec<SubgraphId>>,
+        rewound: Mutex<Vec<(SubgraphId, u64)>>,
+    }
+
+    impl EventProducer<SubgraphProviderEvent> for RecordingProvider {
+        fn take_event_stream(
+            &mut self,
+        ) -> Option<Box<Stream<Item = SubgraphProviderEvent, Error = ()> + Send>> {
+            None
+        }
+    }
+
+    impl EventProducer<SchemaEvent> for RecordingProvider {
+        fn take_event_stream(
+            &mut self,
+        ) -> Option<Box<Stream<Item = SchemaEvent, Error = ()> + Send>> {
+            None
+        }
+    }
+
+    impl SubgraphProviderTrait for RecordingProvider {
+        fn start(
+            &self,
+            id: SubgraphId,
+        ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static> {
+            self.started.lock().unwrap().push(id);
+            Box::new(future::ok(()))
+        }
+
+        fn stop(
+            &self,
+            id: SubgraphId,
+        ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static> {
+            self.stopped.lock().unwrap().push(id);
+            Box::new(future::ok(()))
+        }
+
+        fn pause(
+            &self,
+            id: SubgraphId,
+        ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static> {
+            self.paused.lock().unwrap().push(id);
+            Box::new(future::ok(()))
+        }
+
+        fn resume(
+            &self,
+            id: SubgraphId,
+        ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static> {
+            self.resumed.lock().unwrap().push(id);
+            Box::new(future::ok(()))
+        }
+
+        fn reindex(
+            &self,
+            id: SubgraphId,
+        ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static> {
+            self.reindexed.lock().unwrap().push(id);
+            Box::new(future::ok(()))
+        }
+
+        fn rewind(
+            &self,
+            id: SubgraphId,
+            block_number: u64,
+            _: Option<H256>,
+        ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static> {
+            self.rewound.lock().unwrap().push((id, block_number));
+            Box::new(future::ok(()))
+        }
+
+        fn status(&self, id: &SubgraphId) -> SubgraphStatus {
+            let started = self.started.lock().unwrap().contains(id);
+            let stopped = self.stopped.lock().unwrap().contains(id);
+            if started && !stopped {
+                SubgraphStatus::Running
+            } else {
+                SubgraphStatus::Stopped
+            }
+        }
+    }
+
+    fn recording_name_provider() -> (
+        Arc<RecordingProvider>,
+        Arc<MockStore>,
+        SubgraphProviderWithNames<RecordingProvider, MockStore>,
+    ) {
+        let logger = slog::Logger::root(slog::Discard, o!());
+        let provider = Arc::new(RecordingProvider::default());
+        let store = Arc::new(MockStore::new());
+        let name_provider =
+            SubgraphProviderWithNames::init(logger, provider.clone(), store.clone())
+                .wait()
+                .unwrap();
+        (provider, store, name_provider)
+    }
+
+    fn ids(ids: &Mutex<Vec<SubgraphId>>) -> Vec<SubgraphId> {
+        let mut ids = ids.lock().unwrap().clone();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn deploys_new_versions_as_pending() {
+        let (provider, store, name_provider) = recording_name_provider();
+        let name = "subgraph".to_owned();
+
+        // A new name uses the deployed version right away
+        name_provider
+            .deploy(name.clone(), "v1".to_owned())
+            .wait()
+            .unwrap();
+        assert_eq!(
+            store.read_subgraph_name(name.clone()).unwrap(),
+            Some(Some("v1".to_owned()))
+        );
+
+        // Queries keep being served from the current version while the new
+        // version is pending
+        name_provider
+            .deploy(name.clone(), "v2".to_owned())
+            .wait()
+            .unwrap();
+        assert_eq!(
+            store.read_subgraph_name(name.clone()).unwrap(),
+            Some(Some("v1".to_owned()))
+        );
+        assert_eq!(
+            store.read_all_pending_subgraph_ids().unwrap(),
+            vec![(name.clone(), "v2".to_owned())]
+        );
+        assert_eq!(ids(&provider.started), vec!["v1", "v2"]);
+        assert!(ids(&provider.stopped).is_empty());
+
+        // Switching over stops the previous version
+        name_provider
+            .promote_pending_version(name.clone(), "v2".to_owned())
+            .wait()
+            .unwrap();
+        assert_eq!(
+            store.read_subgraph_name(name.clone()).unwrap(),
+            Some(Some("v2".to_owned()))
+        );
+        assert!(store.read_all_pending_subgraph_ids().unwrap().is_empty());
+        assert_eq!(ids(&provider.stopped), vec!["v1"]);
+    }
+
+    #[test]
+    fn replaces_and_removes_pending_versions() {
+        let (provider, store, name_provider) = recording_name_provider();
+        let name = "subgraph".to_owned();
+
+        name_provider
+            .deploy(name.clone(), "v1".to_owned())
+            .wait()
+            .unwrap();
+        name_provider
+            .deploy(name.clone(), "v2".to_owned())
+            .wait()
+            .unwrap();
+
+        // Deploying another version replaces the pending version
+        name_provider
+            .deploy(name.clone(), "v3".to_owned())
+            .wait()
+            .unwrap();
+        assert_eq!(
+            store.read_all_pending_subgraph_ids().unwrap(),
+            vec![(name.clone(), "v3".to_owned())]
+        );
+        assert_eq!(ids(&provider.stopped), vec!["v2"]);
+
+        // An outdated pending version is not switched over to
+        name_provider
+            .promote_pending_version(name.clone(), "v2".to_owned())
+            .wait()
+            .unwrap();
+        assert_eq!(
+            store.read_subgraph_name(name.clone()).unwrap(),
+            Some(Some("v1".to_owned()))
+        );
+
+        // Removing the name stops both the current and the pending version
+        name_provider.remove(name.clone()).wait().unwrap();
+        assert_eq!(store.read_subgraph_name(name).unwrap(), None);
+        assert_eq!(ids(&provider.stopped), vec!["v1", "v2", "v3"]);
+    }
+
+    #[test]
+    fn pauses_resumes_and_reindexes_all_versions_of_a_name() {
+        let (provider, _store, name_provider) = recording_name_provider();
+        let name = "subgraph".to_owned();
+
+        name_provider
+            .deploy(name.clone(), "v1".to_owned())
+            .wait()
+            .unwrap();
+        name_provider
+            .deploy(name.clone(), "v2".to_owned())
+            .wait()
+            .unwrap();
+
+        name_provider.pause(name.clone()).wait().unwrap();
+        assert_eq!(ids(&provider.paused), vec!["v1", "v2"]);
+
+        name_provider.resume(name.clone()).wait().unwrap();
+        assert_eq!(ids(&provider.resumed), vec!["v1", "v2"]);
+
+        name_provider.reindex(name.clone()).wait().unwrap();
+        assert_eq!(ids(&provider.reindexed), vec!["v1", "v2"]);
+
+        match name_provider.pause("unknown".to_owned()).wait() {
+            Err(SubgraphProviderError::NameNotFound(name)) => assert_eq!(name, "unknown"),
+            x => panic!("unexpected test result {:?}", x),
+        }
+    }
+
+    #[test]
+    fn rewinds_current_version_of_a_name() {
+        let (provider, _store, name_provider) = recording_name_provider();
+        let name = "subgraph".to_owned();
+
+        name_provider
+            .deploy(name.clone(), "v1".to_owned())
+            .wait()
+            .unwrap();
+        name_provider
+            .deploy(name.clone(), "v2".to_owned())
+            .wait()
+            .unwrap();
+
+        name_provider.rewind(name, 10, None).wait().unwrap();
+        assert_eq!(
+            *provider.rewound.lock().unwrap(),
+            vec![("v1".to_owned(), 10)]
+        );
+    }
+
+    #[test]
+    fn collects_unused_subgraph_versions() {
+        let (_provider, store, name_provider) = recording_name_provider();
+        let name = "subgraph".to_owned();
+        let block_ptr = EthereumBlockPointer {
+            hash: H256::zero(),
+            number: 0,
+        };
+
+        for id in &["v1", "v2", "v3"] {
+            store
+                .add_subgraph_if_missing(id.to_string(), block_ptr)
+                .unwrap();
+        }
+        name_provider
+            .deploy(name.clone(), "v1".to_owned())
+            .wait()
+            .unwrap();
+        name_provider
+            .deploy(name.clone(), "v2".to_owned())
+            .wait()
+            .unwrap();
+
+        // Only `v3` is neither running nor used by a name
+        let unused_ids = |versions: Vec<UnusedSubgraphVersion>| {
+            versions
+                .into_iter()
+                .map(|version| version.id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            unused_ids(name_provider.collect_garbage(true).wait().unwrap()),
+            vec!["v3"]
+        );
+        assert_eq!(
+            store.read_all_subgraph_ids().unwrap(),
+            vec!["v1", "v2", "v3"]
+        );
+
+        name_provider
+            .promote_pending_version(name, "v2".to_owned())
+            .wait()
+            .unwrap();
+        assert_eq!(
+            unused_ids(name_provider.collect_garbage(false).wait().unwrap()),
+            vec!["v1", "v3"]
+        );
+        assert_eq!(store.read_all_subgraph_ids().unwrap(), vec!["v2"]);
+    }
 
     #[test]
     fn rejects_name_bad_for_urls() {
@@ -191,8 +784,12 @@ mod tests {
         }
         let logger = slog::Logger::root(slog::Discard, o!());
         let store = Arc::new(MockStore::new());
-        let provider =
-            SubgraphProvider::new(logger.clone(), Arc::new(FakeLinkResolver), store.clone());
+        let provider = SubgraphProvider::new(
+            logger.clone(),
+            Arc::new(FakeLinkResolver),
+            store.clone(),
+            Arc::new(MockEthereumAdapter::default()),
+        );
         let name_provider = Arc::new(
             SubgraphProviderWithNames::init(logger, Arc::new(provider), store)
                 .wait()
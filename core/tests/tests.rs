@@ -18,8 +18,8 @@ use std::time::Instant;
 use graph::components::ethereum::*;
 use graph::prelude::*;
 use graph::web3::types::*;
-use graph_core::SubgraphInstanceManager;
-use graph_mock::{FakeStore, MockBlockStreamBuilder, MockStore};
+use graph_core::{RecentSubgraphLogs, SubgraphInstanceManager};
+use graph_mock::{FakeStore, MockBlockStreamBuilder, MockEthereumAdapter, MockStore};
 
 /// Adds subgraph located in `test/subgraphs/`, replacing "link to" placeholders
 /// in the subgraph manifest with links to files just added into a local IPFS
@@ -72,12 +72,15 @@ fn multiple_data_sources_per_subgraph() {
             true
         }
 
-        fn process_log(
+        fn heap_size(&self) -> usize {
+            0
+        }
+
+        fn process_logs(
             &self,
             _: &Logger,
             _: Arc<EthereumBlock>,
-            _: Arc<Transaction>,
-            _: Arc<Log>,
+            _: Vec<(Arc<Transaction>, Arc<Log>)>,
             _: Vec<EntityOperation>,
         ) -> Box<Future<Item = Vec<EntityOperation>, Error = Error> + Send> {
             unimplemented!();
@@ -138,6 +141,7 @@ fn multiple_data_sources_per_subgraph() {
                 host_builder.clone(),
                 block_stream_builder,
                 None,
+                Arc::new(RecentSubgraphLogs::new(0)),
             );
 
             // Load a subgraph with two data sources
@@ -169,6 +173,14 @@ fn multiple_data_sources_per_subgraph() {
                         HashSet::from_iter(vec!["ExampleDataSource", "ExampleDataSource2"]);
 
                     if data_source_names == expected_data_source_names {
+                        // Only the second data source has a start block
+                        for data_source in data_sources_received.iter() {
+                            let expected_start_block = match data_source.name.as_str() {
+                                "ExampleDataSource2" => 6000000,
+                                _ => 0,
+                            };
+                            assert_eq!(data_source.source.start_block, expected_start_block);
+                        }
                         break;
                     }
                     if Instant::now().duration_since(start_time) > max_wait {
@@ -206,6 +218,7 @@ fn subgraph_provider_events() {
         logger.clone(),
         Arc::new(IpfsClient::default()),
         Arc::new(MockStore::new()),
+        Arc::new(MockEthereumAdapter::default()),
     );
     let provider_events = provider.take_event_stream().unwrap();
     let schema_events = provider.take_event_stream().unwrap();
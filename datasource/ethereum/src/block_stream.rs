@@ -837,10 +837,24 @@ where
     type Stream = BlockStream<S, C, E>;
 
     fn from_subgraph(&self, manifest: &SubgraphManifest, logger: Logger) -> Self::Stream {
-        // Add entry to subgraphs table in Store
-        let genesis_block_ptr = self.chain_store.genesis_block_ptr().unwrap();
+        // Add entry to subgraphs table in Store. Subgraphs with a start block
+        // start out at the block right before it, so that the block stream
+        // never processes any earlier blocks
+        let start_block = start_block_from_subgraph(manifest);
+        let subgraph_start_ptr = if start_block > 0 {
+            let number = start_block - 1;
+            let hash = self
+                .eth_adapter
+                .block_hash_by_block_number(&logger, number)
+                .wait()
+                .unwrap()
+                .unwrap_or_else(|| panic!("Ethereum node could not find block {}", number));
+            EthereumBlockPointer { hash, number }
+        } else {
+            self.chain_store.genesis_block_ptr().unwrap()
+        };
         self.subgraph_store
-            .add_subgraph_if_missing(manifest.id.clone(), genesis_block_ptr)
+            .add_subgraph_if_missing(manifest.id.clone(), subgraph_start_ptr)
             .unwrap();
 
         // Listen for chain head block updates
@@ -871,6 +885,17 @@ where
     }
 }
 
+/// Returns the number of the first block any data source of the subgraph is
+/// interested in.
+fn start_block_from_subgraph(manifest: &SubgraphManifest) -> u64 {
+    manifest
+        .data_sources
+        .iter()
+        .map(|data_source| data_source.source.start_block)
+        .min()
+        .unwrap_or(0)
+}
+
 fn create_log_filter_from_subgraph(manifest: &SubgraphManifest) -> EthereumLogFilter {
     manifest
         .data_sources
@@ -3,6 +3,7 @@ use graph::serde_json::Value;
 use jsonrpc_core::types::Call;
 use std::env;
 
+use graph::components::metrics::ETHEREUM_RPC_CALLS;
 use graph::web3;
 use graph::web3::transports::{http, ipc, ws};
 use graph::web3::RequestId;
@@ -47,6 +48,22 @@ impl Transport {
     }
 }
 
+/// Returns the name of the method of a JSON-RPC call.
+fn method_name(request: &Call) -> String {
+    match request {
+        Call::MethodCall(call) => call.method.clone(),
+        Call::Notification(notification) => notification.method.clone(),
+        _ => String::from("invalid"),
+    }
+}
+
+/// Counts a finished JSON-RPC call by method and outcome.
+fn count_call(method: &str, success: bool) {
+    ETHEREUM_RPC_CALLS
+        .with_label_values(&[method, if success { "success" } else { "failure" }])
+        .inc();
+}
+
 impl web3::Transport for Transport {
     type Out = Box<Future<Item = Value, Error = web3::error::Error> + Send>;
 
@@ -59,11 +76,18 @@ impl web3::Transport for Transport {
     }
 
     fn send(&self, id: RequestId, request: Call) -> Self::Out {
-        match self {
+        let method = method_name(&request);
+
+        let response: Self::Out = match self {
             Transport::RPC(http) => Box::new(http.send(id, request)),
             Transport::IPC(ipc) => Box::new(ipc.send(id, request)),
             Transport::WS(ws) => Box::new(ws.send(id, request)),
-        }
+        };
+
+        Box::new(response.then(move |result| {
+            count_call(&method, result.is_ok());
+            result
+        }))
     }
 }
 
@@ -76,10 +100,33 @@ impl web3::BatchTransport for Transport {
     where
         T: IntoIterator<Item = (RequestId, Call)>,
     {
-        match self {
+        let requests: Vec<_> = requests.into_iter().collect();
+        let methods: Vec<_> = requests
+            .iter()
+            .map(|(_, request)| method_name(request))
+            .collect();
+
+        let responses: Self::Batch = match self {
             Transport::RPC(http) => Box::new(http.send_batch(requests)),
             Transport::IPC(ipc) => Box::new(ipc.send_batch(requests)),
             Transport::WS(ws) => Box::new(ws.send_batch(requests)),
-        }
+        };
+
+        // Count every call in the batch separately
+        Box::new(responses.then(move |result| {
+            match result {
+                Ok(ref results) => {
+                    for (method, result) in methods.iter().zip(results) {
+                        count_call(method, result.is_ok());
+                    }
+                }
+                Err(_) => {
+                    for method in methods.iter() {
+                        count_call(method, false);
+                    }
+                }
+            }
+            result
+        }))
     }
 }
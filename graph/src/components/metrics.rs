@@ -0,0 +1,62 @@
+//! All metrics are registered with the default Prometheus registry, from
+//! which they are exported by the `MetricsServer`.
+
+use prometheus::{Histogram, HistogramVec, IntCounter, IntCounterVec, IntGauge};
+
+lazy_static! {
+    /// Time spent processing a block, from extracting the events relevant to
+    /// a subgraph to transacting the resulting entity operations.
+    pub static ref BLOCK_PROCESSING_DURATION: HistogramVec = register_histogram_vec!(
+        "subgraph_block_processing_duration_seconds",
+        "Time spent processing a block for a subgraph",
+        &["subgraph"]
+    ).unwrap();
+
+    /// Time spent running a single mapping handler.
+    pub static ref HANDLER_EXECUTION_DURATION: HistogramVec = register_histogram_vec!(
+        "subgraph_handler_execution_duration_seconds",
+        "Time spent running a mapping handler",
+        &["subgraph", "handler"]
+    ).unwrap();
+
+    /// Number of requests sent to the Ethereum node, by method and by
+    /// outcome (`success` or `failure`).
+    pub static ref ETHEREUM_RPC_CALLS: IntCounterVec = register_int_counter_vec!(
+        "ethereum_rpc_calls_total",
+        "Number of Ethereum JSON-RPC calls",
+        &["method", "outcome"]
+    ).unwrap();
+
+    /// Time spent writing to or reverting entities in the store.
+    pub static ref STORE_TRANSACTION_DURATION: HistogramVec = register_histogram_vec!(
+        "store_transaction_duration_seconds",
+        "Time spent in store transactions",
+        &["operation"]
+    ).unwrap();
+
+    /// Time spent running GraphQL queries, including cached ones.
+    pub static ref GRAPHQL_QUERY_DURATION: Histogram = register_histogram!(
+        "graphql_query_duration_seconds",
+        "Time spent running GraphQL queries"
+    ).unwrap();
+
+    /// Number of GraphQL queries that returned errors.
+    pub static ref GRAPHQL_QUERY_ERRORS: IntCounter = register_int_counter!(
+        "graphql_query_errors_total",
+        "Number of GraphQL queries that failed"
+    ).unwrap();
+
+    /// Number of GraphQL subscriptions currently running over WebSockets.
+    pub static ref GRAPHQL_ACTIVE_SUBSCRIPTIONS: IntGauge = register_int_gauge!(
+        "graphql_active_subscriptions",
+        "Number of active GraphQL subscriptions"
+    ).unwrap();
+
+    /// Number of logs that could not be sent to Elasticsearch, by subgraph and
+    /// by reason (`buffer_full`, `rejected` or `invalid`).
+    pub static ref ELASTICSEARCH_DROPPED_LOGS: IntCounterVec = register_int_counter_vec!(
+        "elasticsearch_dropped_logs_total",
+        "Number of logs dropped instead of being sent to Elasticsearch",
+        &["subgraph", "reason"]
+    ).unwrap();
+}
@@ -52,6 +52,9 @@ pub mod store;
 
 pub mod link_resolver;
 
+/// Prometheus metrics recorded by the components.
+pub mod metrics;
+
 /// Plug the outputs of `output` of type `E` to the matching inputs in `input`.
 /// This is a lazy operation, nothing will be sent until you spawn the returned
 /// future. Returns `Some` in the first call and `None` on any further calls.
@@ -4,7 +4,7 @@ use std::sync::Arc;
 use prelude::Logger;
 
 /// Common trait for JSON-RPC admin server implementations.
-pub trait JsonRpcServer<P, S> {
+pub trait JsonRpcServer<P, S, L> {
     type Server;
 
     fn serve(
@@ -13,6 +13,7 @@ pub trait JsonRpcServer<P, S> {
         ws_port: u16,
         provider: Arc<P>,
         store: Arc<S>,
+        log_store: Arc<L>,
         logger: Logger,
     ) -> Result<Self::Server, io::Error>;
 }
@@ -0,0 +1,12 @@
+use futures::prelude::*;
+
+/// Common trait for servers that export the node's metrics.
+pub trait MetricsServer {
+    type ServeError;
+
+    /// Creates a new Tokio task that, when spawned, brings up the metrics server.
+    fn serve(
+        &mut self,
+        port: u16,
+    ) -> Result<Box<Future<Item = (), Error = ()> + Send>, Self::ServeError>;
+}
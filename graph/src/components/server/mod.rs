@@ -6,3 +6,6 @@ pub mod subscription;
 
 /// Component for the JSON-RPC admin API.
 pub mod admin;
+
+/// Component for exporting metrics over HTTP.
+pub mod metrics;
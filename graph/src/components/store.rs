@@ -1,6 +1,8 @@
 use failure::Error;
 use futures::Future;
 use futures::Stream;
+use std::collections::HashMap;
+use std::time::Instant;
 use web3::types::H256;
 
 use data::store::*;
@@ -38,6 +40,14 @@ pub enum EntityFilter {
     NotStartsWith(Attribute, Value),
     EndsWith(Attribute, Value),
     NotEndsWith(Attribute, Value),
+    ContainsNoCase(Attribute, Value),
+    StartsWithNoCase(Attribute, Value),
+    EndsWithNoCase(Attribute, Value),
+    /// Matches a case-insensitive POSIX regular expression.
+    Matches(Attribute, Value),
+    /// Full-text search over the given attributes, using a text search
+    /// configuration (e.g. `english`) and the text to search for.
+    FullTextMatch(Vec<Attribute>, String, String),
 }
 
 /// The order in which entities should be restored from a store.
@@ -77,6 +87,25 @@ pub struct EntityQuery {
 
     /// An optional range to limit the size of the result.
     pub range: Option<EntityRange>,
+
+    /// Point in time after which the query is aborted with a timeout.
+    pub deadline: Option<Instant>,
+}
+
+/// Aggregates over the entities that match a query.
+///
+/// `sum`, `min`, `max` and `avg` hold one value per aggregated attribute;
+/// the value is `Value::Null` if no entities match. Sums of `Int` attributes
+/// are `BigInt`s and averages are `Float`s for `Int` and `Float` attributes
+/// and `BigDecimal`s otherwise, so that they can't overflow or lose precision.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EntityAggregates {
+    /// The number of matching entities.
+    pub count: u64,
+    pub sum: HashMap<Attribute, Value>,
+    pub min: HashMap<Attribute, Value>,
+    pub max: HashMap<Attribute, Value>,
+    pub avg: HashMap<Attribute, Value>,
 }
 
 /// Operation types that lead to entity changes.
@@ -89,6 +118,10 @@ pub enum EntityChangeOperation {
     Updated,
     /// An existing entity was removed.
     Removed,
+    /// All entities of the subgraph were removed at once; `entity_type` and
+    /// `entity_id` of the change are empty.
+    #[serde(rename = "all_removed")]
+    AllRemoved,
 }
 
 /// Entity change events emitted by [Store](trait.Store.html) implementations.
@@ -236,15 +269,30 @@ pub type SubgraphEntityPair = (SubgraphId, String);
 
 /// Common trait for store implementations.
 pub trait Store: Send + Sync + 'static {
-    /// Set access token for the specified subgraph name.
-    fn authorize_subgraph_name(&self, name: String, new_access_token: String) -> Result<(), Error>;
+    /// Store a new admin API key.
+    fn add_api_key(&self, key: ApiKey) -> Result<(), Error>;
 
-    /// Check access token against the token in the Store for the specified subgraph name.
-    fn check_subgraph_name_access_token(
-        &self,
-        name: String,
-        untrusted_access_token: String,
-    ) -> Result<bool, Error>;
+    /// Look up an admin API key by its ID.
+    fn find_api_key(&self, id: String) -> Result<Option<ApiKey>, Error>;
+
+    /// Look up an admin API key by the hash of its token.
+    fn find_api_key_by_token_hash(&self, token_hash: String) -> Result<Option<ApiKey>, Error>;
+
+    /// Set the access token of a subgraph name, i.e. store an admin API key
+    /// for the token that grants `SUBGRAPH_NAME_ROLES` for the name, and
+    /// revoke the key of the name's previous token. Setting the current
+    /// token again changes nothing.
+    ///
+    /// Returns the ID of the key for the token.
+    fn authorize_subgraph_name(&self, name: String, token: String) -> Result<String, Error>;
+
+    /// Revoke the admin API key with the specified ID.
+    ///
+    /// Returns false if there is no unrevoked key with this ID.
+    fn revoke_api_key(&self, id: String) -> Result<bool, Error>;
+
+    /// Append an entry to the audit log of admin API calls.
+    fn add_admin_audit_entry(&self, entry: AdminAuditEntry) -> Result<(), Error>;
 
     /// List all subgraph names and their associated subgraph IDs.
     fn read_all_subgraph_names(&self) -> Result<Vec<(String, Option<SubgraphId>)>, Error>;
@@ -261,7 +309,22 @@ pub trait Store: Send + Sync + 'static {
     /// Find subgraph names associated with the specified subgraph ID
     fn find_subgraph_names_by_id(&self, id: SubgraphId) -> Result<Vec<String>, Error>;
 
-    /// Set the subgraph ID currently associated with specified subgraph name.
+    /// List all subgraph names that have a pending subgraph ID, together with that ID.
+    fn read_all_pending_subgraph_ids(&self) -> Result<Vec<(String, SubgraphId)>, Error>;
+
+    /// Set the pending subgraph ID of the specified subgraph name, i.e. the subgraph version
+    /// that the name switches to once it has caught up with the chain head.
+    ///
+    /// Creates the subgraph name if it does not exist.
+    fn write_pending_subgraph_id(&self, name: String, id: Option<SubgraphId>) -> Result<(), Error>;
+
+    /// Make the pending subgraph ID of the specified subgraph name its current subgraph ID, if
+    /// the pending subgraph ID is still `id`.
+    ///
+    /// Returns whether the subgraph name was switched over to `id`.
+    fn promote_pending_subgraph_id(&self, name: String, id: SubgraphId) -> Result<bool, Error>;
+
+    /// Delete the specified subgraph name, including its pending subgraph ID.
     fn delete_subgraph_name(&self, name: String) -> Result<(), Error>;
 
     /// Register a new subgraph ID in the store, and initialize the subgraph's block pointer to the
@@ -273,6 +336,41 @@ pub trait Store: Send + Sync + 'static {
         block_ptr: EthereumBlockPointer,
     ) -> Result<(), Error>;
 
+    /// List the IDs of all subgraphs that the store has data or metadata for.
+    fn read_all_subgraph_ids(&self) -> Result<Vec<SubgraphId>, Error>;
+
+    /// Count the entities of a subgraph.
+    fn count_entities(&self, subgraph_id: SubgraphId) -> Result<u64, Error>;
+
+    /// Delete everything that `remove_subgraph_data` deletes, as well as the subgraph's
+    /// metadata in the subgraph of subgraphs, unless a subgraph name refers to the subgraph.
+    ///
+    /// Subgraph names are checked in the same transaction that deletes the subgraph. Returns
+    /// the number of deleted entities, or `None` if the subgraph is still in use.
+    fn remove_unused_subgraph(&self, subgraph_id: SubgraphId) -> Result<Option<u64>, Error>;
+
+    /// Delete all entities of a subgraph, their history and the subgraph's block pointer.
+    ///
+    /// The next time the subgraph is started, its block pointer is initialized again with
+    /// `add_subgraph_if_missing`.
+    fn remove_subgraph_data(&self, subgraph_id: SubgraphId) -> Result<(), Error>;
+
+    /// Record whether a subgraph is paused, so that it stays paused across restarts.
+    fn set_subgraph_paused(&self, subgraph_id: SubgraphId, paused: bool) -> Result<(), Error>;
+
+    /// Check whether a subgraph was paused with `set_subgraph_paused`.
+    fn is_subgraph_paused(&self, subgraph_id: SubgraphId) -> Result<bool, Error>;
+
+    /// Creates the indexes needed to run the given full-text searches of a
+    /// subgraph efficiently. Indexes that already exist are left untouched.
+    ///
+    /// Indexes are built without locking out writes, which can take a while.
+    fn create_full_text_indexes(
+        &self,
+        subgraph_id: SubgraphId,
+        definitions: Vec<FullTextDefinition>,
+    ) -> Result<(), Error>;
+
     /// Get a pointer to the most recently processed block in the subgraph.
     fn block_ptr(&self, subgraph_id: SubgraphId) -> Result<EthereumBlockPointer, Error>;
 
@@ -284,6 +382,15 @@ pub trait Store: Send + Sync + 'static {
     // TODO need to validate block ptr
     fn find(&self, query: EntityQuery) -> Result<Vec<Entity>, QueryExecutionError>;
 
+    /// Counts the entities that match the store query and computes the sum,
+    /// minimum, maximum and average of the given numeric attributes over them.
+    /// The range and order of the query are ignored.
+    fn aggregate(
+        &self,
+        query: EntityQuery,
+        attributes: Vec<(Attribute, ValueType)>,
+    ) -> Result<EntityAggregates, QueryExecutionError>;
+
     /// Updates the block pointer.  Careful: this is only safe to use if it is known that no store
     /// changes are needed to go from `block_ptr_from` to `block_ptr_to`.
     ///
@@ -327,6 +434,20 @@ pub trait Store: Send + Sync + 'static {
         block_ptr_to: EthereumBlockPointer,
     ) -> Result<(), Error>;
 
+    /// Revert the entity changes from all blocks after `block_ptr_to` atomically in the store,
+    /// discarding their history, and update the subgraph block pointer from `block_ptr_from` to
+    /// `block_ptr_to`.
+    ///
+    /// `block_ptr_from` must match the current value of the subgraph block pointer.
+    /// `block_ptr_to` must point to an ancestor of `block_ptr_from`, which doesn't need to be in
+    /// the chain store.
+    fn revert_to_block(
+        &self,
+        subgraph_id: SubgraphId,
+        block_ptr_from: EthereumBlockPointer,
+        block_ptr_to: EthereumBlockPointer,
+    ) -> Result<(), Error>;
+
     /// Subscribe to entity changes for specific subgraphs and entities.
     ///
     /// Returns a stream of entity changes that match the input arguments.
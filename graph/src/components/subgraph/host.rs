@@ -10,13 +10,16 @@ pub trait RuntimeHost: Send + Sync + Debug {
     /// Returns true if the RuntimeHost has a handler for an Ethereum event.
     fn matches_log(&self, log: &Log) -> bool;
 
-    /// Process an Ethereum event and return a vector of entity operations.
-    fn process_log(
+    /// Returns the size of the mapping's heap in bytes.
+    fn heap_size(&self) -> usize;
+
+    /// Process a batch of Ethereum events from the same block, in order, and
+    /// return a vector of entity operations.
+    fn process_logs(
         &self,
         logger: &Logger,
         block: Arc<EthereumBlock>,
-        transaction: Arc<Transaction>,
-        log: Arc<Log>,
+        logs: Vec<(Arc<Transaction>, Arc<Log>)>,
         entity_operations: Vec<EntityOperation>,
     ) -> Box<Future<Item = Vec<EntityOperation>, Error = Error> + Send>;
 }
@@ -3,7 +3,7 @@ use futures::prelude::*;
 use std::sync::Arc;
 
 use prelude::*;
-use web3::types::{Log, Transaction};
+use web3::types::Log;
 
 /// Represents a loaded instance of a subgraph.
 pub trait SubgraphInstance<T>: Sized
@@ -20,13 +20,16 @@ where
     /// Returns true if the subgraph has a handler for an Ethereum event.
     fn matches_log(&self, log: &Log) -> bool;
 
-    /// Process an Ethereum event and return the resulting entity operations as a future.
-    fn process_log(
+    /// Returns the combined size of the heaps of all mappings in bytes.
+    fn heap_size(&self) -> usize;
+
+    /// Process the Ethereum events of a block and return the resulting entity
+    /// operations as a future.
+    fn process_logs(
         &self,
         logger: &Logger,
         block: Arc<EthereumBlock>,
-        transaction: Arc<Transaction>,
-        log: Log,
+        logs: Vec<Log>,
         entity_operations: Vec<EntityOperation>,
     ) -> Box<Future<Item = Vec<EntityOperation>, Error = Error> + Send>;
 }
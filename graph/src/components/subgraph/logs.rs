@@ -0,0 +1,77 @@
+use chrono::{DateTime, Utc};
+use serde::ser::Serializer;
+use slog::Level;
+
+use data::subgraph::SubgraphId;
+
+/// Serializes an slog log level using a serde Serializer.
+fn serialize_log_level<S>(level: &Level, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(match level {
+        Level::Critical => "critical",
+        Level::Error => "error",
+        Level::Warning => "warn",
+        Level::Info => "info",
+        Level::Debug => "debug",
+        Level::Trace => "trace",
+    })
+}
+
+/// A log message written by a subgraph.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubgraphLogEntry {
+    pub subgraph_id: SubgraphId,
+    pub timestamp: DateTime<Utc>,
+    #[serde(serialize_with = "serialize_log_level")]
+    pub level: Level,
+    /// The log message, followed by its key/value arguments.
+    pub text: String,
+}
+
+/// Criteria for the subgraph logs to return from a `SubgraphLogStore`.
+#[derive(Clone, Debug, Default)]
+pub struct SubgraphLogFilter {
+    /// Only return logs at this level or more severe.
+    pub level: Option<Level>,
+    /// Only return logs written at or after this time.
+    pub from: Option<DateTime<Utc>>,
+    /// Only return logs written at or before this time.
+    pub to: Option<DateTime<Utc>>,
+    /// Only return logs whose text contains this string, ignoring case.
+    pub text: Option<String>,
+    /// Return at most this many logs.
+    pub first: Option<usize>,
+}
+
+impl SubgraphLogFilter {
+    /// Returns true if the log entry matches the filter, ignoring `first`.
+    pub fn matches(&self, entry: &SubgraphLogEntry) -> bool {
+        self.level
+            .map_or(true, |level| entry.level.is_at_least(level))
+            && self.from.map_or(true, |from| entry.timestamp >= from)
+            && self.to.map_or(true, |to| entry.timestamp <= to)
+            && self.text.as_ref().map_or(true, |text| {
+                entry.text.to_lowercase().contains(&text.to_lowercase())
+            })
+    }
+}
+
+/// Common trait for components that keep subgraph logs around so that they
+/// can be queried, e.g. by subgraph developers through the admin API.
+pub trait SubgraphLogStore: Send + Sync + 'static {
+    /// Stores a log entry of a subgraph.
+    fn add_log(&self, entry: SubgraphLogEntry);
+
+    /// Returns the logs of a subgraph that match the filter, newest first.
+    fn find_logs(
+        &self,
+        subgraph_id: &SubgraphId,
+        filter: &SubgraphLogFilter,
+    ) -> Vec<SubgraphLogEntry>;
+
+    /// Removes all logs of a subgraph.
+    fn remove_logs(&self, subgraph_id: &SubgraphId);
+}
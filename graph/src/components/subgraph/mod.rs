@@ -1,6 +1,7 @@
 mod host;
 mod instance;
 mod instance_manager;
+mod logs;
 mod provider;
 mod registry;
 
@@ -9,7 +10,9 @@ pub use prelude::Entity;
 pub use self::host::{RuntimeHost, RuntimeHostBuilder};
 pub use self::instance::SubgraphInstance;
 pub use self::instance_manager::SubgraphInstanceManager;
+pub use self::logs::{SubgraphLogEntry, SubgraphLogFilter, SubgraphLogStore};
 pub use self::provider::{
-    SchemaEvent, SubgraphProvider, SubgraphProviderEvent, SubgraphProviderWithNames,
+    SchemaEvent, SubgraphNameStatus, SubgraphProvider, SubgraphProviderEvent,
+    SubgraphProviderWithNames, SubgraphStatus, SubgraphVersionStatus, UnusedSubgraphVersion,
 };
 pub use self::registry::SubgraphRegistry;
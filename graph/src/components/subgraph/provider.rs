@@ -1,4 +1,5 @@
 use prelude::*;
+use web3::types::H256;
 
 /// Events emitted by [SubgraphProvider](trait.SubgraphProvider.html) implementations.
 #[derive(Debug)]
@@ -7,6 +8,9 @@ pub enum SubgraphProviderEvent {
     SubgraphStart(SubgraphManifest),
     /// The subgraph with the given ID should stop processing.
     SubgraphStop(SubgraphId),
+    /// The subgraph with the given ID should stop processing for now, but
+    /// remains hosted.
+    SubgraphPause(SubgraphId),
 }
 
 /// Schema-only events emitted by a [SubgraphProvider](trait.SubgraphProvider.html).
@@ -18,6 +22,48 @@ pub enum SchemaEvent {
     SchemaRemoved(SubgraphId),
 }
 
+/// Whether a subgraph is being indexed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SubgraphStatus {
+    /// The subgraph is processing blocks.
+    Running,
+    /// The subgraph can be queried but does not process blocks.
+    Paused,
+    /// The subgraph is not hosted.
+    Stopped,
+}
+
+/// A subgraph version, whether it is being indexed and how far.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubgraphVersionStatus {
+    pub id: SubgraphId,
+    pub status: SubgraphStatus,
+    /// The number of the most recently processed block, if any.
+    pub latest_block_number: Option<u64>,
+}
+
+/// A subgraph name with its current and pending versions.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubgraphNameStatus {
+    pub name: String,
+    pub current_version: Option<SubgraphVersionStatus>,
+    pub pending_version: Option<SubgraphVersionStatus>,
+}
+
+/// A subgraph version that no subgraph name refers to anymore.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnusedSubgraphVersion {
+    pub id: SubgraphId,
+    /// The number of entities that were removed with the subgraph version;
+    /// not counted in dry runs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entity_count: Option<u64>,
+}
+
 /// Common trait for subgraph providers.
 pub trait SubgraphProvider:
     EventProducer<SubgraphProviderEvent> + EventProducer<SchemaEvent> + Send + Sync + 'static
@@ -31,6 +77,41 @@ pub trait SubgraphProvider:
         &self,
         id: SubgraphId,
     ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static>;
+
+    /// Stops processing blocks for a running subgraph, while keeping its data
+    /// and schema around so that it can still be queried.
+    fn pause(
+        &self,
+        id: SubgraphId,
+    ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static>;
+
+    /// Continues processing blocks for a paused subgraph.
+    fn resume(
+        &self,
+        id: SubgraphId,
+    ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static>;
+
+    /// Removes all entities of a running or paused subgraph and processes
+    /// its blocks again from the start block.
+    fn reindex(
+        &self,
+        id: SubgraphId,
+    ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static>;
+
+    /// Reverts all blocks processed by a running or paused subgraph after
+    /// block `block_number` at once, then continues processing from there.
+    ///
+    /// If `block_hash` is given, the subgraph is only rewound if that block
+    /// is the one at `block_number` in the Ethereum node's chain.
+    fn rewind(
+        &self,
+        id: SubgraphId,
+        block_number: u64,
+        block_hash: Option<H256>,
+    ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static>;
+
+    /// Returns whether the subgraph is running, paused or not hosted at all.
+    fn status(&self, id: &SubgraphId) -> SubgraphStatus;
 }
 
 /// Common trait for named subgraph providers.
@@ -45,4 +126,44 @@ pub trait SubgraphProviderWithNames: Send + Sync + 'static {
         &self,
         name: String,
     ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static>;
+
+    /// Lists all subgraph names with the status of their subgraph versions.
+    fn list(&self) -> Result<Vec<SubgraphNameStatus>, SubgraphProviderError>;
+
+    /// Pauses the current and pending versions of a subgraph name.
+    fn pause(
+        &self,
+        name: String,
+    ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static>;
+
+    /// Resumes the current and pending versions of a subgraph name.
+    fn resume(
+        &self,
+        name: String,
+    ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static>;
+
+    /// Reindexes the current and pending versions of a subgraph name.
+    fn reindex(
+        &self,
+        name: String,
+    ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static>;
+
+    /// Removes all data of the subgraph versions that are neither running nor
+    /// referred to by a subgraph name, and returns these versions.
+    ///
+    /// If `dry_run` is set, only returns the versions that would be removed.
+    fn collect_garbage(
+        &self,
+        dry_run: bool,
+    ) -> Box<
+        Future<Item = Vec<UnusedSubgraphVersion>, Error = SubgraphProviderError> + Send + 'static,
+    >;
+
+    /// Rewinds the current version of a subgraph name to an earlier block.
+    fn rewind(
+        &self,
+        name: String,
+        block_number: u64,
+        block_hash: Option<H256>,
+    ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static>;
 }
@@ -0,0 +1,207 @@
+use chrono::{DateTime, Utc};
+use hex;
+use rand::{self, Rng};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::str::FromStr;
+
+/// A role that grants access to a group of admin API methods.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AdminRole {
+    /// Deploy subgraphs to subgraph names.
+    Deploy,
+    /// Remove subgraph names.
+    Remove,
+    /// Create and revoke API keys.
+    Authorize,
+    /// Read the status and logs of subgraphs.
+    ReadStatus,
+}
+
+impl AdminRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AdminRole::Deploy => "deploy",
+            AdminRole::Remove => "remove",
+            AdminRole::Authorize => "authorize",
+            AdminRole::ReadStatus => "read-status",
+        }
+    }
+}
+
+impl fmt::Display for AdminRole {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for AdminRole {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "deploy" => Ok(AdminRole::Deploy),
+            "remove" => Ok(AdminRole::Remove),
+            "authorize" => Ok(AdminRole::Authorize),
+            "read-status" => Ok(AdminRole::ReadStatus),
+            _ => Err(format!("unknown admin role: {}", s)),
+        }
+    }
+}
+
+/// Roles granted by the access tokens of subgraph names, as set with the
+/// `subgraph_authorize` admin API method.
+pub const SUBGRAPH_NAME_ROLES: &[AdminRole] =
+    &[AdminRole::Deploy, AdminRole::Remove, AdminRole::ReadStatus];
+
+/// An API key for the admin API.
+///
+/// Only a hash of the secret token is kept; the token itself is handed out
+/// once, when the key is created.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ApiKey {
+    /// Public identifier of the key, e.g. for revoking it.
+    pub id: String,
+    /// Hash of the secret token, see `hash_api_token`.
+    pub token_hash: String,
+    /// The roles granted by the key.
+    pub roles: Vec<AdminRole>,
+    /// The subgraph name the key is limited to, or `None` for all names.
+    pub subgraph_name: Option<String>,
+    /// When the key expires, if ever.
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Whether the key has been revoked.
+    pub revoked: bool,
+}
+
+impl ApiKey {
+    /// Creates a new API key with a random token.
+    ///
+    /// Returns the key together with its token.
+    pub fn generate(
+        roles: Vec<AdminRole>,
+        subgraph_name: Option<String>,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> (Self, String) {
+        let token = random_hex(32);
+        let key = Self::with_token(&token, roles, subgraph_name, expires_at);
+        (key, token)
+    }
+
+    /// Creates a new API key for an existing token.
+    pub fn with_token(
+        token: &str,
+        roles: Vec<AdminRole>,
+        subgraph_name: Option<String>,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Self {
+        ApiKey {
+            id: random_hex(8),
+            token_hash: hash_api_token(token),
+            roles,
+            subgraph_name,
+            expires_at,
+            revoked: false,
+        }
+    }
+
+    /// Returns true if the key is neither revoked nor expired at `now`.
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        !self.revoked && self.expires_at.map_or(true, |expires_at| now < expires_at)
+    }
+
+    /// Returns true if the key grants `role` for the subgraph name, or for all
+    /// subgraph names if `subgraph_name` is `None`.
+    pub fn allows(&self, role: AdminRole, subgraph_name: Option<&str>) -> bool {
+        self.roles.contains(&role)
+            && match (&self.subgraph_name, subgraph_name) {
+                (None, _) => true,
+                (Some(scope), Some(name)) => scope == name,
+                (Some(_), None) => false,
+            }
+    }
+}
+
+/// An entry of the audit log of admin API calls.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AdminAuditEntry {
+    /// Who made the call: the ID of an API key, `master` or `anonymous`.
+    pub caller: String,
+    /// The admin API method that was called.
+    pub method: String,
+    /// The parameters of the call, without any secret tokens.
+    pub params: String,
+    /// `success` or the error the call failed with.
+    pub outcome: String,
+}
+
+/// Hashes a secret API token for storing it and looking it up.
+pub fn hash_api_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+fn random_hex(bytes: usize) -> String {
+    let mut rng = rand::thread_rng();
+    hex::encode((0..bytes).map(|_| rng.gen::<u8>()).collect::<Vec<u8>>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn api_key_tokens_are_hashed() {
+        let (key, token) = ApiKey::generate(vec![AdminRole::Deploy], None, None);
+        assert_eq!(token.len(), 64);
+        assert_ne!(key.token_hash, token);
+        assert_eq!(key.token_hash, hash_api_token(&token));
+    }
+
+    #[test]
+    fn api_key_roles_and_scope() {
+        let key = ApiKey::with_token(
+            "token",
+            vec![AdminRole::Deploy, AdminRole::ReadStatus],
+            Some("subgraph".to_owned()),
+            None,
+        );
+
+        assert!(key.allows(AdminRole::Deploy, Some("subgraph")));
+        assert!(key.allows(AdminRole::ReadStatus, Some("subgraph")));
+        assert!(!key.allows(AdminRole::Remove, Some("subgraph")));
+        assert!(!key.allows(AdminRole::Deploy, Some("other")));
+        assert!(!key.allows(AdminRole::Deploy, None));
+    }
+
+    #[test]
+    fn api_key_expiry_and_revocation() {
+        let now = Utc::now();
+        let mut key = ApiKey::with_token(
+            "token",
+            vec![AdminRole::Deploy],
+            None,
+            Some(now + Duration::hours(1)),
+        );
+
+        assert!(key.is_active(now));
+        assert!(!key.is_active(now + Duration::hours(2)));
+
+        key.revoked = true;
+        assert!(!key.is_active(now));
+    }
+
+    #[test]
+    fn admin_roles_round_trip() {
+        for role in &[
+            AdminRole::Deploy,
+            AdminRole::Remove,
+            AdminRole::Authorize,
+            AdminRole::ReadStatus,
+        ] {
+            assert_eq!(role.as_str().parse::<AdminRole>(), Ok(*role));
+        }
+        assert!("admin".parse::<AdminRole>().is_err());
+    }
+}
@@ -18,11 +18,18 @@ pub enum SchemaValidationError {
         _0
     )]
     EntityDirectivesMissing(Strings),
+    #[fail(
+        display = "@fulltext directive on type {} is invalid: {}",
+        _0,
+        _1
+    )]
+    InvalidFullTextDirective(String, String),
 }
 
 /// Validates whether a GraphQL schema is compatible with The Graph.
 pub(crate) fn validate_schema(schema: &Document) -> Result<(), SchemaValidationError> {
-    validate_schema_types(&schema)
+    validate_schema_types(&schema)?;
+    validate_full_text_directives(&schema)
 }
 
 /// Validates whether all object types in the schema are declared with an @entity directive.
@@ -44,6 +51,70 @@ fn validate_schema_types(schema: &Document) -> Result<(), SchemaValidationError>
     }
 }
 
+/// Validates that all @fulltext directives in the schema have a name and a
+/// language that can be used in a Postgres text search configuration, and
+/// that they only refer to existing string fields.
+fn validate_full_text_directives(schema: &Document) -> Result<(), SchemaValidationError> {
+    use self::SchemaValidationError::*;
+
+    for object_type in get_object_type_definitions(schema) {
+        let invalid = |reason: String| InvalidFullTextDirective(object_type.name.clone(), reason);
+
+        for directive in object_type
+            .directives
+            .iter()
+            .filter(|directive| directive.name == "fulltext")
+        {
+            match get_argument(directive, "name") {
+                Some(Value::String(_)) => (),
+                _ => return Err(invalid(String::from("missing search name"))),
+            }
+
+            match get_argument(directive, "language") {
+                None => (),
+                Some(Value::String(ref language))
+                    if !language.is_empty()
+                        && language.chars().all(|c| c.is_ascii_lowercase() || c == '_') => {}
+                Some(value) => return Err(invalid(format!("invalid language {}", value))),
+            }
+
+            let fields = match get_argument(directive, "fields") {
+                Some(Value::List(ref fields)) if !fields.is_empty() => fields,
+                _ => return Err(invalid(String::from("missing list of fields"))),
+            };
+
+            for field in fields {
+                let field_type = match field {
+                    Value::String(ref name) => object_type
+                        .fields
+                        .iter()
+                        .find(|field| &field.name == name)
+                        .map(|field| &field.field_type),
+                    _ => None,
+                };
+
+                match field_type {
+                    Some(Type::NamedType(ref name)) if name == "String" => (),
+                    Some(Type::NonNullType(ref inner))
+                        if **inner == Type::NamedType(String::from("String")) => {}
+                    _ => return Err(invalid(format!("{} is not a String field", field))),
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Looks up the value of a directive argument, if it is provided.
+fn get_argument<'a>(directive: &'a Directive, name: &str) -> Option<&'a Value> {
+    directive
+        .arguments
+        .iter()
+        .find(|(argument_name, _)| argument_name == name)
+        .map(|(_, value)| value)
+}
+
 /// Returns all object type definitions in the schema.
 pub fn get_object_type_definitions(schema: &Document) -> Vec<&ObjectType> {
     schema
@@ -1,6 +1,9 @@
 /// Data types for dealing with subgraphs.
 pub mod subgraph;
 
+/// Data types for authorizing and auditing admin API calls.
+pub mod admin;
+
 /// Data types for dealing with GraphQL queries.
 pub mod query;
 
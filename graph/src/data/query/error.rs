@@ -1,3 +1,4 @@
+use bigdecimal;
 use graphql_parser::{query as q, Pos};
 use hex::FromHexError;
 use num_bigint;
@@ -37,6 +38,9 @@ pub enum QueryExecutionError {
     ValueParseError(String, String),
     AttributeTypeError(String, String),
     EntityParseError(String),
+    TooDeep(u8),
+    TooComplex(u64, u64),
+    Timeout,
 }
 
 impl Error for QueryExecutionError {
@@ -130,6 +134,16 @@ impl fmt::Display for QueryExecutionError {
             QueryExecutionError::EntityParseError(s) => {
                 write!(f, "Broken entity found in store: {}", s)
             }
+            QueryExecutionError::TooDeep(max_depth) => {
+                write!(f, "Query has a depth that exceeds the limit of {}", max_depth)
+            }
+            QueryExecutionError::TooComplex(complexity, max_complexity) => write!(
+                f,
+                "Query potentially returns {} entities or more and thereby exceeds \
+                 the limit of {} entities",
+                complexity, max_complexity
+            ),
+            QueryExecutionError::Timeout => write!(f, "Query timed out"),
         }
     }
 }
@@ -152,6 +166,12 @@ impl From<num_bigint::ParseBigIntError> for QueryExecutionError {
     }
 }
 
+impl From<bigdecimal::ParseBigDecimalError> for QueryExecutionError {
+    fn from(e: bigdecimal::ParseBigDecimalError) -> Self {
+        QueryExecutionError::ValueParseError("BigDecimal".to_string(), format!("{}", e))
+    }
+}
+
 /// Error caused while processing a [Query](struct.Query.html) request.
 #[derive(Debug)]
 pub enum QueryError {
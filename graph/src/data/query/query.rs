@@ -1,19 +1,53 @@
 use graphql_parser::query as q;
+use serde::de::{Deserialize, Deserializer};
+use serde_json;
 use std::cmp::PartialEq;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::i32;
 use std::ops::{Deref, DerefMut};
 
 use data::schema::Schema;
 
-#[derive(Deserialize)]
-#[serde(untagged, remote = "q::Value")]
-enum GraphQLValue {
-    String(String),
+/// Variable value for a GraphQL query.
+#[derive(Clone, Debug)]
+pub struct QueryVariableValue(q::Value);
+
+impl QueryVariableValue {
+    /// Converts a JSON value into a GraphQL value. Numbers that fit into
+    /// a GraphQL `Int` become integers, all others become floats.
+    fn from_json(value: serde_json::Value) -> q::Value {
+        match value {
+            serde_json::Value::Null => q::Value::Null,
+            serde_json::Value::Bool(b) => q::Value::Boolean(b),
+            serde_json::Value::Number(n) => match n.as_i64() {
+                Some(i) if i >= i32::MIN as i64 && i <= i32::MAX as i64 => {
+                    q::Value::Int(q::Number::from(i as i32))
+                }
+                _ => q::Value::Float(n.as_f64().unwrap_or(0.0)),
+            },
+            serde_json::Value::String(s) => q::Value::String(s),
+            serde_json::Value::Array(values) => {
+                q::Value::List(values.into_iter().map(Self::from_json).collect())
+            }
+            serde_json::Value::Object(object) => q::Value::Object(
+                object
+                    .into_iter()
+                    .map(|(name, value)| (name, Self::from_json(value)))
+                    .collect::<BTreeMap<_, _>>(),
+            ),
+        }
+    }
 }
 
-/// Variable value for a GraphQL query.
-#[derive(Clone, Debug, Deserialize)]
-pub struct QueryVariableValue(#[serde(with = "GraphQLValue")] q::Value);
+impl<'de> Deserialize<'de> for QueryVariableValue {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        serde_json::Value::deserialize(deserializer)
+            .map(|value| QueryVariableValue(Self::from_json(value)))
+    }
+}
 
 impl Deref for QueryVariableValue {
     type Target = q::Value;
@@ -77,4 +111,6 @@ pub struct Query {
     pub schema: Schema,
     pub document: q::Document,
     pub variables: Option<QueryVariables>,
+    /// Name of the operation to execute if the document defines more than one.
+    pub operation_name: Option<String>,
 }
@@ -4,6 +4,52 @@ use failure::Error;
 use graphql_parser;
 use graphql_parser::{schema, Pos};
 
+/// A full-text search over one or more string fields of an entity type,
+/// declared with a `@fulltext(name: ..., language: ..., fields: [...])`
+/// directive on the type.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FullTextDefinition {
+    /// Name of the search field in the root `Query` type.
+    pub name: String,
+    /// Entity type to search.
+    pub entity_type: String,
+    /// Postgres text search configuration to use, e.g. `english`.
+    pub language: String,
+    /// Fields whose values are searched.
+    pub fields: Vec<String>,
+}
+
+impl FullTextDefinition {
+    /// Parses a `@fulltext` directive of an entity type.
+    ///
+    /// Assumes the directive has been validated.
+    pub fn from_directive(entity_type: &str, directive: &schema::Directive) -> Self {
+        let argument = |name: &str| {
+            directive
+                .arguments
+                .iter()
+                .find(|(argument_name, _)| argument_name == name)
+                .map(|(_, value)| value)
+        };
+        let string = |value: &schema::Value| match value {
+            schema::Value::String(s) => Some(s.clone()),
+            _ => None,
+        };
+
+        FullTextDefinition {
+            name: argument("name").and_then(string).unwrap_or_default(),
+            entity_type: entity_type.to_owned(),
+            language: argument("language")
+                .and_then(string)
+                .unwrap_or_else(|| String::from("english")),
+            fields: match argument("fields") {
+                Some(schema::Value::List(fields)) => fields.iter().filter_map(string).collect(),
+                _ => vec![],
+            },
+        }
+    }
+}
+
 /// A validated and preprocessed GraphQL schema for a subgraph.
 #[derive(Clone, Debug, PartialEq)]
 pub struct Schema {
@@ -23,6 +69,25 @@ impl Schema {
         Ok(schema)
     }
 
+    /// Returns the full-text searches declared in the schema.
+    pub fn full_text_definitions(&self) -> Vec<FullTextDefinition> {
+        self.document
+            .definitions
+            .iter()
+            .filter_map(|definition| match definition {
+                schema::Definition::TypeDefinition(schema::TypeDefinition::Object(t)) => Some(t),
+                _ => None,
+            }).flat_map(|object_type| {
+                object_type
+                    .directives
+                    .iter()
+                    .filter(|directive| directive.name == "fulltext")
+                    .map(move |directive| {
+                        FullTextDefinition::from_directive(&object_type.name, directive)
+                    })
+            }).collect()
+    }
+
     // Adds a @subgraphId(id: ...) directive to object/interface/enum types in the schema.
     fn add_subgraph_id_directives(&mut self, id: SubgraphId) {
         for definition in self.document.definitions.iter_mut() {
@@ -18,11 +18,13 @@ pub type Attribute = String;
 pub const ID: &str = "ID";
 pub const BYTES_SCALAR: &str = "Bytes";
 pub const BIG_INT_SCALAR: &str = "BigInt";
+pub const BIG_DECIMAL_SCALAR: &str = "BigDecimal";
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum ValueType {
     Boolean,
     BigInt,
+    BigDecimal,
     Bytes,
     Float,
     ID,
@@ -37,6 +39,7 @@ impl FromStr for ValueType {
         match s {
             "Boolean" => Ok(ValueType::Boolean),
             "BigInt" => Ok(ValueType::BigInt),
+            "BigDecimal" => Ok(ValueType::BigDecimal),
             "Bytes" => Ok(ValueType::Bytes),
             "Float" => Ok(ValueType::Float),
             "ID" => Ok(ValueType::ID),
@@ -59,6 +62,7 @@ pub enum Value {
     Null,
     Bytes(scalar::Bytes),
     BigInt(scalar::BigInt),
+    BigDecimal(scalar::BigDecimal),
 }
 
 impl Value {
@@ -91,6 +95,7 @@ impl Value {
                 match n.as_str() {
                     BYTES_SCALAR => Value::Bytes(scalar::Bytes::from_str(e)?),
                     BIG_INT_SCALAR => Value::BigInt(scalar::BigInt::from_str(e)?),
+                    BIG_DECIMAL_SCALAR => Value::BigDecimal(scalar::BigDecimal::from_str(e)?),
                     _ => Value::String(e.clone()),
                 }
             }
@@ -100,6 +105,7 @@ impl Value {
                 match n.as_str() {
                     BYTES_SCALAR => Value::Bytes(scalar::Bytes::from_str(s)?),
                     BIG_INT_SCALAR => Value::BigInt(scalar::BigInt::from_str(s)?),
+                    BIG_DECIMAL_SCALAR => Value::BigDecimal(scalar::BigDecimal::from_str(s)?),
                     _ => Value::String(s.clone()),
                 }
             }
@@ -139,6 +145,7 @@ impl fmt::Display for Value {
                     .collect(),
                 Value::Bytes(ref bytes) => bytes.to_string(),
                 Value::BigInt(ref number) => number.to_string(),
+                Value::BigDecimal(ref number) => number.to_string(),
             }
         )
     }
@@ -157,6 +164,7 @@ impl From<Value> for query::Value {
             }
             Value::Bytes(bytes) => query::Value::String(bytes.to_string()),
             Value::BigInt(number) => query::Value::String(number.to_string()),
+            Value::BigDecimal(number) => query::Value::String(number.to_string()),
         }
     }
 }
@@ -191,6 +199,12 @@ impl From<u64> for Value {
     }
 }
 
+impl From<scalar::BigDecimal> for Value {
+    fn from(value: scalar::BigDecimal) -> Value {
+        Value::BigDecimal(value)
+    }
+}
+
 impl From<Vec<Value>> for Value {
     fn from(list: Vec<Value>) -> Value {
         Value::List(list)
@@ -303,3 +317,56 @@ fn value_bigint() {
     );
     assert_eq!(query::Value::from(from_query), graphql_value);
 }
+
+#[test]
+fn value_bigdecimal() {
+    let graphql_value = query::Value::String("12345.6789000".to_owned());
+    let ty = query::Type::NamedType(BIG_DECIMAL_SCALAR.to_owned());
+    let from_query = Value::from_query_value(&graphql_value, &ty).unwrap();
+    assert_eq!(
+        from_query,
+        Value::BigDecimal(scalar::BigDecimal::new(123456789.into(), -4))
+    );
+
+    // Values are normalized, so trailing zeros are dropped
+    assert_eq!(
+        query::Value::from(from_query),
+        query::Value::String("12345.6789".to_owned())
+    );
+}
+
+#[test]
+fn value_bigdecimal_rejects_large_exponents() {
+    let ty = query::Type::NamedType(BIG_DECIMAL_SCALAR.to_owned());
+    for s in &["1e6145", "1e-6145"] {
+        let graphql_value = query::Value::String(s.to_string());
+        assert!(Value::from_query_value(&graphql_value, &ty).is_err());
+    }
+
+    let graphql_value = query::Value::String("1e6144".to_owned());
+    assert!(Value::from_query_value(&graphql_value, &ty).is_ok());
+}
+
+#[test]
+fn bigdecimal_arithmetic() {
+    let x = scalar::BigDecimal::from_str("10.25").unwrap();
+    let y = scalar::BigDecimal::from_str("0.75").unwrap();
+    assert_eq!((x.clone() + y.clone()).to_string(), "11");
+    assert_eq!((x.clone() - y.clone()).to_string(), "9.5");
+    assert_eq!((x.clone() * y.clone()).to_string(), "7.6875");
+    assert_eq!(
+        scalar::BigDecimal::from(1)
+            .checked_div(scalar::BigDecimal::from(8))
+            .unwrap()
+            .to_string(),
+        "0.125"
+    );
+    assert!(x.clone().checked_div(scalar::BigDecimal::from(0)).is_err());
+    assert_eq!(
+        scalar::BigDecimal::from_str("1200")
+            .unwrap()
+            .as_digits_and_exp(),
+        (12.into(), 2)
+    );
+    assert!(y < x);
+}
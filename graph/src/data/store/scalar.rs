@@ -1,3 +1,5 @@
+use bigdecimal;
+use failure;
 use hex;
 use num_bigint;
 use serde::{self, Deserialize, Serialize};
@@ -9,6 +11,8 @@ use std::str::FromStr;
 
 pub use num_bigint::Sign as BigIntSign;
 
+pub use bigdecimal::ParseBigDecimalError;
+
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct BigInt(num_bigint::BigInt);
 
@@ -86,6 +90,12 @@ impl From<i32> for BigInt {
     }
 }
 
+impl From<i64> for BigInt {
+    fn from(i: i64) -> BigInt {
+        BigInt(i.into())
+    }
+}
+
 impl From<u64> for BigInt {
     fn from(i: u64) -> BigInt {
         BigInt(i.into())
@@ -172,6 +182,130 @@ impl Rem for BigInt {
     }
 }
 
+/// `BigDecimal`s parsed from strings, e.g. from queries or mappings, must
+/// have an exponent between `-MAX_BIG_DECIMAL_EXP` and `MAX_BIG_DECIMAL_EXP`,
+/// like IEEE 754 decimal128 numbers. Otherwise printing or adding them could
+/// take up unbounded time and memory.
+pub const MAX_BIG_DECIMAL_EXP: i64 = 6144;
+
+/// An arbitrary precision decimal number that's serialized as a decimal
+/// string. Values are kept normalized, i.e. without trailing zeros in their
+/// digits, so that equal numbers always have the same representation.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct BigDecimal(bigdecimal::BigDecimal);
+
+impl BigDecimal {
+    /// Creates the number `digits * 10^exp`.
+    pub fn new(digits: BigInt, exp: i64) -> Self {
+        BigDecimal(bigdecimal::BigDecimal::new(digits.0, -exp)).normalized()
+    }
+
+    /// Returns `(digits, exp)` such that the number is `digits * 10^exp`.
+    pub fn as_digits_and_exp(&self) -> (BigInt, i64) {
+        let (digits, scale) = self.0.as_bigint_and_exponent();
+        (BigInt(digits), -scale)
+    }
+
+    /// Divides the number by `other`; fails if `other` is zero.
+    pub fn checked_div(self, other: BigDecimal) -> Result<BigDecimal, failure::Error> {
+        if other == BigDecimal::from(0) {
+            return Err(failure::err_msg(format!(
+                "Attempted to divide BigDecimal `{}` by zero",
+                self
+            )));
+        }
+
+        Ok(BigDecimal(self.0 / other.0).normalized())
+    }
+
+    fn normalized(self) -> Self {
+        let (mut digits, mut scale) = self.0.into_bigint_and_exponent();
+        if digits == num_bigint::BigInt::from(0) {
+            return BigDecimal(bigdecimal::BigDecimal::new(digits, 0));
+        }
+
+        let ten = num_bigint::BigInt::from(10);
+        while (&digits % &ten) == num_bigint::BigInt::from(0) {
+            digits = digits / &ten;
+            scale -= 1;
+        }
+        BigDecimal(bigdecimal::BigDecimal::new(digits, scale))
+    }
+}
+
+impl Display for BigDecimal {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        self.0.fmt(f)
+    }
+}
+
+impl From<i32> for BigDecimal {
+    fn from(i: i32) -> BigDecimal {
+        BigDecimal(i64::from(i).into()).normalized()
+    }
+}
+
+impl From<BigInt> for BigDecimal {
+    fn from(n: BigInt) -> BigDecimal {
+        BigDecimal::new(n, 0)
+    }
+}
+
+impl FromStr for BigDecimal {
+    type Err = ParseBigDecimalError;
+
+    fn from_str(s: &str) -> Result<BigDecimal, Self::Err> {
+        let n = BigDecimal(bigdecimal::BigDecimal::from_str(s)?).normalized();
+        let (_, exp) = n.as_digits_and_exp();
+        if exp.abs() > MAX_BIG_DECIMAL_EXP {
+            return Err(ParseBigDecimalError::Other(format!(
+                "exponent {} is outside the range -{} to {}",
+                exp, MAX_BIG_DECIMAL_EXP, MAX_BIG_DECIMAL_EXP
+            )));
+        }
+        Ok(n)
+    }
+}
+
+impl Serialize for BigDecimal {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_string().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for BigDecimal {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        let decimal_string = <String>::deserialize(deserializer)?;
+        BigDecimal::from_str(&decimal_string).map_err(D::Error::custom)
+    }
+}
+
+impl Add for BigDecimal {
+    type Output = BigDecimal;
+
+    fn add(self, other: BigDecimal) -> BigDecimal {
+        BigDecimal(self.0.add(other.0)).normalized()
+    }
+}
+
+impl Sub for BigDecimal {
+    type Output = BigDecimal;
+
+    fn sub(self, other: BigDecimal) -> BigDecimal {
+        BigDecimal(self.0.sub(other.0)).normalized()
+    }
+}
+
+impl Mul for BigDecimal {
+    type Output = BigDecimal;
+
+    fn mul(self, other: BigDecimal) -> BigDecimal {
+        BigDecimal(self.0.mul(other.0)).normalized()
+    }
+}
+
 /// A byte array that's serialized as a hex string prefixed by `0x`.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Bytes(Box<[u8]>);
@@ -47,6 +47,12 @@ pub enum SubgraphProviderError {
     AlreadyRunning(SubgraphId),
     #[fail(display = "subgraph with ID {} is not running", _0)]
     NotRunning(SubgraphId),
+    #[fail(display = "subgraph with ID {} is already paused", _0)]
+    AlreadyPaused(SubgraphId),
+    #[fail(display = "subgraph with ID {} is not paused", _0)]
+    NotPaused(SubgraphId),
+    #[fail(display = "failed to rewind subgraph: {}", _0)]
+    RewindError(String),
     /// Occurs when a subgraph's GraphQL schema is invalid.
     #[fail(display = "GraphQL schema error: {}", _0)]
     SchemaValidationError(failure::Error),
@@ -103,10 +109,15 @@ impl SchemaData {
 }
 
 #[derive(Clone, Debug, Hash, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Source {
     #[serde(deserialize_with = "deserialize_address")]
     pub address: Address,
     pub abi: String,
+    /// The number of the first block to process for this source, e.g. the
+    /// block the contract was deployed in. Defaults to the genesis block.
+    #[serde(default)]
+    pub start_block: u64,
 }
 
 #[derive(Clone, Debug, Hash, Eq, PartialEq, Deserialize)]
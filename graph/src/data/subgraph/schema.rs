@@ -165,6 +165,7 @@ impl<'a> From<&'a super::DataSource> for EthereumContractDataSource {
 struct EthereumContractSource {
     address: super::Address,
     abi: String,
+    start_block: u64,
 }
 
 impl EthereumContractSource {
@@ -174,6 +175,7 @@ impl EthereumContractSource {
         entity.insert("id".to_owned(), id.clone().into());
         entity.insert("address".to_owned(), self.address.into());
         entity.insert("abi".to_owned(), self.abi.into());
+        entity.insert("startBlock".to_owned(), self.start_block.into());
 
         store.apply_set_operation(
             EntityOperation::Set {
@@ -195,6 +197,7 @@ impl From<super::Source> for EthereumContractSource {
         Self {
             address: source.address,
             abi: source.abi,
+            start_block: source.start_block,
         }
     }
 }
@@ -1,4 +1,6 @@
 extern crate backtrace;
+extern crate bigdecimal;
+pub extern crate chrono;
 pub extern crate ethabi;
 extern crate futures;
 extern crate graphql_parser;
@@ -8,15 +10,22 @@ extern crate serde;
 #[macro_use]
 extern crate serde_derive;
 extern crate serde_yaml;
+extern crate sha2;
 #[macro_use]
 pub extern crate slog;
 #[macro_use]
 extern crate failure;
 extern crate ipfs_api;
+#[macro_use]
+extern crate lazy_static;
 extern crate parity_wasm;
+extern crate rand;
+#[macro_use]
+pub extern crate prometheus;
 pub extern crate serde_json;
 pub extern crate slog_async;
 extern crate slog_envlogger;
+extern crate slog_json;
 extern crate slog_term;
 extern crate tiny_keccak;
 pub extern crate tokio;
@@ -64,25 +73,29 @@ pub mod prelude {
     pub use components::graphql::{GraphQlRunner, QueryResultFuture, SubscriptionResultFuture};
     pub use components::link_resolver::LinkResolver;
     pub use components::server::admin::JsonRpcServer;
+    pub use components::server::metrics::MetricsServer;
     pub use components::server::query::GraphQLServer;
     pub use components::server::subscription::SubscriptionServer;
     pub use components::store::{
-        ChainStore, EntityChange, EntityChangeOperation, EntityChangeStream, EntityFilter,
-        EntityKey, EntityOperation, EntityOrder, EntityQuery, EntityRange, Store,
+        ChainStore, EntityAggregates, EntityChange, EntityChangeOperation, EntityChangeStream,
+        EntityFilter, EntityKey, EntityOperation, EntityOrder, EntityQuery, EntityRange, Store,
         SubgraphEntityPair,
     };
     pub use components::subgraph::{
         RuntimeHost, RuntimeHostBuilder, SchemaEvent, SubgraphInstance, SubgraphInstanceManager,
+        SubgraphLogEntry, SubgraphLogFilter, SubgraphLogStore, SubgraphNameStatus,
         SubgraphProvider, SubgraphProviderEvent, SubgraphProviderWithNames, SubgraphRegistry,
+        SubgraphStatus, SubgraphVersionStatus, UnusedSubgraphVersion,
     };
     pub use components::{EventConsumer, EventProducer};
 
+    pub use data::admin::{hash_api_token, AdminAuditEntry, AdminRole, ApiKey};
     pub use data::graphql::SerializableValue;
     pub use data::query::{
         Query, QueryError, QueryExecutionError, QueryResult, QueryVariableValue, QueryVariables,
     };
-    pub use data::schema::Schema;
-    pub use data::store::scalar::{BigInt, BigIntSign};
+    pub use data::schema::{FullTextDefinition, Schema};
+    pub use data::store::scalar::{BigDecimal, BigInt, BigIntSign};
     pub use data::store::{Attribute, Entity, Value, ValueType};
     pub use data::subgraph::{
         DataSource, Link, MappingABI, MappingEventHandler, SubgraphId, SubgraphManifest,
@@ -95,5 +108,6 @@ pub mod prelude {
         CancelGuard, CancelHandle, CancelableError, FutureExtension, SharedCancelGuard,
         StreamExtension,
     };
+    pub use util::env::parse_env_var;
     pub use util::futures::retry;
 }
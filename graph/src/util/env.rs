@@ -0,0 +1,61 @@
+use failure::Error;
+use std::env;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// Parses the value of the environment variable `name`, if it is set.
+///
+/// Fails with an error that names the variable if the value is not valid
+/// unicode or cannot be parsed.
+pub fn parse_env_var<T>(name: &str) -> Result<Option<T>, Error>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    let value = match env::var_os(name) {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+    let value = value.into_string().map_err(|value| {
+        format_err!(
+            "environment variable {} is not valid unicode: {:?}",
+            name,
+            value
+        )
+    })?;
+    value.parse().map(Some).map_err(|e| {
+        format_err!(
+            "invalid value for environment variable {}: {:?}: {}",
+            name,
+            value,
+            e
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_set_variables() {
+        env::set_var("GRAPH_TEST_PARSE_ENV_VAR_SET", "42");
+        let value: Option<u64> = parse_env_var("GRAPH_TEST_PARSE_ENV_VAR_SET").unwrap();
+        assert_eq!(value, Some(42));
+    }
+
+    #[test]
+    fn ignores_unset_variables() {
+        env::remove_var("GRAPH_TEST_PARSE_ENV_VAR_UNSET");
+        let value: Option<u64> = parse_env_var("GRAPH_TEST_PARSE_ENV_VAR_UNSET").unwrap();
+        assert_eq!(value, None);
+    }
+
+    #[test]
+    fn names_the_variable_in_parse_errors() {
+        env::set_var("GRAPH_TEST_PARSE_ENV_VAR_INVALID", "ten");
+        let error = parse_env_var::<u64>("GRAPH_TEST_PARSE_ENV_VAR_INVALID").unwrap_err();
+        let message = error.to_string();
+        assert!(message.contains("GRAPH_TEST_PARSE_ENV_VAR_INVALID"));
+    }
+}
@@ -2,14 +2,39 @@ use backtrace::Backtrace;
 use slog;
 use slog_async;
 use slog_envlogger;
+use slog_json;
 use slog_term;
-use std::{env, panic};
+use std::{env, io, panic};
 
-use slog::{Drain, FilterLevel};
+use slog::{Drain, FilterLevel, Never};
 
-pub fn logger(show_debug: bool) -> slog::Logger {
-    let decorator = slog_term::TermDecorator::new().build();
-    let drain = slog_term::CompactFormat::new(decorator).build().fuse();
+/// Format of the logs written to the terminal.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LogFormat {
+    /// Compact, human-readable text.
+    Text,
+    /// One JSON object per line, with all key/value pairs as fields.
+    Json,
+}
+
+/// Creates a drain that writes logs to the terminal in the given format.
+fn terminal_drain(format: LogFormat) -> Box<Drain<Ok = (), Err = Never> + Send> {
+    match format {
+        LogFormat::Text => {
+            let decorator = slog_term::TermDecorator::new().build();
+            Box::new(slog_term::CompactFormat::new(decorator).build().fuse())
+        }
+        LogFormat::Json => Box::new(
+            slog_json::Json::new(io::stderr())
+                .add_default_keys()
+                .build()
+                .fuse(),
+        ),
+    }
+}
+
+pub fn logger(show_debug: bool, format: LogFormat) -> slog::Logger {
+    let drain = terminal_drain(format);
     let drain = slog_envlogger::LogBuilder::new(drain)
         .filter(
             None,
@@ -28,9 +53,8 @@ pub fn logger(show_debug: bool) -> slog::Logger {
     slog::Logger::root(drain, o!())
 }
 
-pub fn guarded_logger() -> (slog::Logger, slog_async::AsyncGuard) {
-    let decorator = slog_term::TermDecorator::new().build();
-    let drain = slog_term::CompactFormat::new(decorator).build().fuse();
+pub fn guarded_logger(format: LogFormat) -> (slog::Logger, slog_async::AsyncGuard) {
+    let drain = terminal_drain(format);
     let (drain, guard) = slog_async::Async::new(drain).build_with_guard();
     (slog::Logger::root(drain.fuse(), o!()), guard)
 }
@@ -1,6 +1,9 @@
 /// Utilities for working with futures.
 pub mod futures;
 
+/// Utilities for reading configuration from environment variables.
+pub mod env;
+
 /// Logging facilities based on `slog`.
 pub mod log;
 
@@ -3,6 +3,7 @@ use graphql_parser::schema as s;
 use indexmap::IndexMap;
 use std::cmp;
 use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::Instant;
 
 use graph::prelude::*;
 
@@ -33,6 +34,10 @@ where
     pub fields: Vec<&'a q::Field>,
     /// Whether or not we're executing an introspection query
     pub introspecting: bool,
+    /// Point in time after which the execution of the query is aborted.
+    pub deadline: Option<Instant>,
+    /// Values of the variables of the operation being executed.
+    pub variable_values: Arc<HashMap<q::Name, q::Value>>,
 }
 
 impl<'a, R1, R2> ExecutionContext<'a, R1, R2>
@@ -46,6 +51,40 @@ where
         ctx.fields.push(field);
         ctx
     }
+
+    /// Replaces a variable with its value; other values are returned as they are.
+    /// Returns `None` for variables without a value.
+    pub fn resolve_variable<'b>(&'b self, value: &'b q::Value) -> Option<&'b q::Value> {
+        match value {
+            q::Value::Variable(name) => self.variable_values.get(name),
+            _ => Some(value),
+        }
+    }
+}
+
+/// Collects the values of the variables an operation defines, falling back
+/// to their defaults for variables that the query does not provide.
+pub fn variable_values(
+    operation: &q::OperationDefinition,
+    variables: &Option<QueryVariables>,
+) -> HashMap<q::Name, q::Value> {
+    let definitions = match operation {
+        q::OperationDefinition::Query(query) => &query.variable_definitions,
+        q::OperationDefinition::Subscription(subscription) => &subscription.variable_definitions,
+        q::OperationDefinition::Mutation(mutation) => &mutation.variable_definitions,
+        q::OperationDefinition::SelectionSet(_) => return HashMap::new(),
+    };
+
+    definitions
+        .iter()
+        .filter_map(|definition| {
+            variables
+                .as_ref()
+                .and_then(|variables| variables.get(&definition.name))
+                .map(|value| (*value).clone())
+                .or_else(|| definition.default_value.clone())
+                .map(|value| (definition.name.clone(), value))
+        }).collect()
 }
 
 /// Executes the root selection set of a query.
@@ -68,6 +107,157 @@ where
     execute_selection_set(ctx, selection_set, query_type, initial_value)
 }
 
+/// Checks the root selection set of a query against depth and complexity
+/// limits, without resolving any fields.
+///
+/// The complexity of a query estimates the number of entities it may
+/// return: every list field multiplies the complexity of its selection set
+/// by its `first` argument, every aggregate field by the number of entities
+/// it is assumed to aggregate, and the complexities of sibling fields add up.
+pub fn check_root_selection_set<'a, R1, R2>(
+    ctx: ExecutionContext<'a, R1, R2>,
+    selection_set: &'a q::SelectionSet,
+    max_depth: u8,
+    max_complexity: Option<u64>,
+) -> Result<(), Vec<QueryExecutionError>>
+where
+    R1: Resolver,
+    R2: Resolver,
+{
+    // Obtain the root Query type and fail if there isn't one
+    let query_type = match sast::get_root_query_type(&ctx.schema.document) {
+        Some(t) => t,
+        None => return Err(vec![QueryExecutionError::NoRootQueryObjectType]),
+    };
+
+    check_selection_set(ctx, selection_set, query_type, max_depth, max_complexity)
+}
+
+/// Checks a selection set of the given object type against depth and
+/// complexity limits, e.g. the root selection set of a subscription.
+pub fn check_selection_set<'a, R1, R2>(
+    ctx: ExecutionContext<'a, R1, R2>,
+    selection_set: &'a q::SelectionSet,
+    object_type: &'a s::ObjectType,
+    max_depth: u8,
+    max_complexity: Option<u64>,
+) -> Result<(), Vec<QueryExecutionError>>
+where
+    R1: Resolver,
+    R2: Resolver,
+{
+    let complexity = selection_set_complexity(ctx, selection_set, object_type, max_depth, 0)?;
+
+    match max_complexity {
+        Some(max_complexity) if complexity > max_complexity => {
+            Err(vec![QueryExecutionError::TooComplex(
+                complexity,
+                max_complexity,
+            )])
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Number of entities assumed for list fields without a `first` argument.
+const DEFAULT_FIRST: u64 = 100;
+
+/// Number of entities assumed for list fields whose `first` argument is
+/// not an integer: the most that `first: Int` allows.
+const MAX_FIRST: u64 = i32::max_value() as u64;
+
+/// Number of entities assumed for aggregate fields, which read all entities
+/// matching their filter even though they only return a single object.
+const AGGREGATE_ENTITIES: u64 = 1000;
+
+/// Estimates the complexity of a selection set; fails if it is nested
+/// deeper than `max_depth`.
+fn selection_set_complexity<'a, R1, R2>(
+    ctx: ExecutionContext<'a, R1, R2>,
+    selection_set: &'a q::SelectionSet,
+    object_type: &'a s::ObjectType,
+    max_depth: u8,
+    depth: u8,
+) -> Result<u64, QueryExecutionError>
+where
+    R1: Resolver,
+    R2: Resolver,
+{
+    if depth >= max_depth {
+        return Err(QueryExecutionError::TooDeep(max_depth));
+    }
+
+    let grouped_field_set = collect_fields(ctx.clone(), object_type, selection_set, None);
+
+    let mut complexity: u64 = 0;
+    for (_, fields) in grouped_field_set {
+        let (field_definition, introspecting) =
+            match get_field_type(ctx.clone(), object_type, &fields[0].name) {
+                Some(field) => field,
+                None => continue,
+            };
+
+        let mut ctx = ctx.clone();
+        ctx.introspecting = introspecting;
+
+        // Only object types have selection sets of their own
+        let inner_complexity = match sast::get_type_definition_from_field_type(
+            if ctx.introspecting {
+                ctx.introspection_schema
+            } else {
+                &ctx.schema.document
+            },
+            field_definition,
+        ) {
+            Some(s::TypeDefinition::Object(inner_type)) => selection_set_complexity(
+                ctx.clone(),
+                &merge_selection_sets(fields.clone()),
+                inner_type,
+                max_depth,
+                depth + 1,
+            )?,
+            _ => 1,
+        };
+
+        // Introspection results are bounded by the size of the schema,
+        // so only list and aggregate fields of entities multiply the complexity
+        let multiplier = if ctx.introspecting {
+            1
+        } else if sast::get_aggregate_entity_type(&ctx.schema.document, field_definition).is_some()
+        {
+            AGGREGATE_ENTITIES
+        } else if is_list_type(&field_definition.field_type) {
+            // Variables are charged at the value they are given
+            fields[0]
+                .arguments
+                .iter()
+                .find(|(name, _)| name == "first")
+                .and_then(|(_, value)| ctx.resolve_variable(value))
+                .map_or(DEFAULT_FIRST, |value| match value {
+                    q::Value::Int(n) => n
+                        .as_i64()
+                        .map_or(MAX_FIRST, |n| cmp::min(cmp::max(n, 0) as u64, MAX_FIRST)),
+                    _ => MAX_FIRST,
+                })
+        } else {
+            1
+        };
+
+        complexity = complexity.saturating_add(multiplier.saturating_mul(inner_complexity));
+    }
+
+    Ok(complexity)
+}
+
+/// Returns whether a field type is a list type, possibly wrapped in a non-null type.
+fn is_list_type(field_type: &s::Type) -> bool {
+    match field_type {
+        s::Type::NonNullType(inner_type) => is_list_type(inner_type),
+        s::Type::ListType(_) => true,
+        s::Type::NamedType(_) => false,
+    }
+}
+
 /// Executes a selection set, requiring the result to be of the given object type.
 ///
 /// Allows passing in a parent value during recursive processing of objects and their fields.
@@ -89,6 +279,14 @@ where
 
     // Process all field groups in order
     for (response_key, fields) in grouped_field_set {
+        // Stop executing the query once it has used up its time budget
+        if let Some(deadline) = ctx.deadline {
+            if Instant::now() > deadline {
+                errors.push(QueryExecutionError::Timeout);
+                break;
+            }
+        }
+
         // If the field exists on the object, execute it and add its result to the result map
         if let Some((ref field, introspecting)) =
             get_field_type(ctx.clone(), object_type, &fields[0].name)
@@ -367,6 +565,12 @@ where
                 t,
                 argument_values,
             )
+        } else if let Some(entity_type) =
+            sast::get_aggregate_entity_type(&ctx.schema.document, field_definition)
+        {
+            // Aggregate fields resolve to aggregates over entities of
+            // another type than the field type
+            ctx.resolver.resolve_aggregate(entity_type, argument_values)
         } else {
             ctx.resolver.resolve_object(
                 object_value,
@@ -545,6 +749,21 @@ where
         return match resolved_value {
             // Complete list values individually
             q::Value::List(values) => {
+                // Resolve the relationships of all objects in the list at once
+                // rather than one object at a time
+                if let Some(s::TypeDefinition::Object(object_type)) =
+                    sast::get_type_definition_from_type(&ctx.schema.document, inner_type)
+                {
+                    if !ctx.introspecting {
+                        prefetch_selection_set(
+                            ctx.clone(),
+                            &values,
+                            object_type,
+                            &merge_selection_sets(fields.clone()),
+                        )?;
+                    }
+                }
+
                 let mut out = Vec::with_capacity(values.len());
                 for value in values.into_iter() {
                     out.push(complete_value(
@@ -628,6 +847,59 @@ where
     }
 }
 
+/// Resolves the relationship fields in a selection set for all the given
+/// objects at once, and then recursively for all objects these fields
+/// resolve to. This way, the number of queries against the resolver is
+/// bounded by the size of the selection set rather than by the number of
+/// objects; resolving the fields of the individual objects later on uses
+/// the prefetched results.
+fn prefetch_selection_set<'a, R1, R2>(
+    ctx: ExecutionContext<'a, R1, R2>,
+    objects: &[q::Value],
+    object_type: &'a s::ObjectType,
+    selection_set: &'a q::SelectionSet,
+) -> Result<(), Vec<QueryExecutionError>>
+where
+    R1: Resolver,
+    R2: Resolver,
+{
+    if objects.is_empty() {
+        return Ok(());
+    }
+
+    let grouped_field_set = collect_fields(ctx.clone(), object_type, selection_set, None);
+
+    for (_, fields) in grouped_field_set {
+        let field_definition = match sast::get_field_type(object_type, &fields[0].name) {
+            Some(field_definition) => field_definition,
+            None => continue,
+        };
+
+        // Only fields that refer to other objects need to be prefetched
+        let inner_type =
+            match sast::get_type_definition_from_field_type(&ctx.schema.document, field_definition)
+            {
+                Some(s::TypeDefinition::Object(inner_type)) => inner_type,
+                _ => continue,
+            };
+
+        let argument_values = coerce_argument_values(ctx.clone(), object_type, fields[0])?;
+        let inner_objects = ctx
+            .resolver
+            .prefetch_objects(objects, field_definition, inner_type, &argument_values)
+            .map_err(|e| vec![e])?;
+
+        prefetch_selection_set(
+            ctx.clone(),
+            &inner_objects,
+            inner_type,
+            &merge_selection_sets(fields),
+        )?;
+    }
+
+    Ok(())
+}
+
 /// Resolves an abstract type (interface, union) into an object type based on the given value.
 fn resolve_abstract_type<'a, R1, R2>(
     ctx: ExecutionContext<'a, R1, R2>,
@@ -698,10 +970,11 @@ where
 
     if let Some(argument_definitions) = sast::get_argument_definitions(object_type, &field.name) {
         for argument_def in argument_definitions.iter() {
-            match qast::get_argument_value(&field.arguments, &argument_def.name) {
-                // We don't support variables yet
-                Some(q::Value::Variable(_)) => unimplemented!(),
+            // Arguments given as variables take the value of the variable
+            let value = qast::get_argument_value(&field.arguments, &argument_def.name)
+                .and_then(|value| ctx.resolve_variable(value));
 
+            match value {
                 // There is no value, either use the default or fail
                 None => {
                     if let Some(ref default_value) = argument_def.default_value {
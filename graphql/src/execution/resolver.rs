@@ -26,6 +26,37 @@ pub trait Resolver: Clone + Send + Sync {
         arguments: &HashMap<&q::Name, q::Value>,
     ) -> Result<q::Value, QueryExecutionError>;
 
+    /// Resolves the entities referenced by a field of many parent objects at
+    /// once and remembers them, so that subsequent calls to `resolve_objects`
+    /// and `resolve_object` for any of these parents don't have to resolve
+    /// them one by one. Returns all objects that the field resolves to.
+    ///
+    /// The default implementation doesn't prefetch anything.
+    fn prefetch_objects(
+        &self,
+        _parents: &[q::Value],
+        _field_definition: &s::Field,
+        _object_type: &s::ObjectType,
+        _arguments: &HashMap<&q::Name, q::Value>,
+    ) -> Result<Vec<q::Value>, QueryExecutionError> {
+        Ok(vec![])
+    }
+
+    /// Resolves aggregates (counts, sums etc.) over the entities of the given
+    /// type that match the arguments, in the form of an object with one field
+    /// per aggregate.
+    ///
+    /// The default implementation doesn't support aggregates.
+    fn resolve_aggregate(
+        &self,
+        _entity_type: &s::ObjectType,
+        _arguments: &HashMap<&q::Name, q::Value>,
+    ) -> Result<q::Value, QueryExecutionError> {
+        Err(QueryExecutionError::NotSupported(String::from(
+            "Resolving aggregates is not supported by this resolver",
+        )))
+    }
+
     /// Resolves an enum value for a given enum type.
     fn resolve_enum_value(&self, enum_type: &s::EnumType, value: Option<&q::Value>) -> q::Value {
         value
@@ -1,4 +1,5 @@
 use graphql_parser::query as q;
+use std::time::Instant;
 
 use graph::prelude::*;
 
@@ -18,6 +19,12 @@ where
     pub logger: slog::Logger,
     /// The resolver to use.
     pub resolver: R,
+    /// Time at which the query times out.
+    pub deadline: Option<Instant>,
+    /// Maximum nesting depth of the query.
+    pub max_depth: u8,
+    /// Maximum complexity of the query, as estimated from its `first` arguments.
+    pub max_complexity: Option<u64>,
 }
 
 /// Executes a query and returns a result.
@@ -27,8 +34,12 @@ where
 {
     info!(options.logger, "Execute query");
 
-    // Obtain the only operation of the query (fail if there is none or more than one)
-    let operation = match qast::get_operation(&query.document, None) {
+    // Obtain the operation to execute (fail if there is none or if the name
+    // is missing or doesn't match when there is more than one)
+    let operation = match qast::get_operation(
+        &query.document,
+        query.operation_name.as_ref().map(|name| name.as_str()),
+    ) {
         Ok(op) => op,
         Err(e) => return QueryResult::from(e),
     };
@@ -47,25 +58,36 @@ where
         introspecting: false,
         document: &query.document,
         fields: vec![],
+        deadline: options.deadline,
+        variable_values: Arc::new(variable_values(operation, &query.variables)),
     };
 
-    let result = match *operation {
-        // Execute top-level `query { ... }` expressions
+    let selection_set = match *operation {
+        // Top-level `query { ... }` expressions
         q::OperationDefinition::Query(q::Query {
             ref selection_set, ..
-        }) => execute_root_selection_set(ctx, selection_set, &None),
+        }) => selection_set,
 
-        // Execute top-level `{ ... }` expressions
-        q::OperationDefinition::SelectionSet(ref selection_set) => {
-            execute_root_selection_set(ctx, selection_set, &None)
-        }
+        // Top-level `{ ... }` expressions
+        q::OperationDefinition::SelectionSet(ref selection_set) => selection_set,
 
         // Everything else (e.g. mutations) is unsupported
-        _ => Err(vec![QueryExecutionError::NotSupported(
-            "Only queries are supported".to_string(),
-        )]),
+        _ => {
+            return QueryResult::from(QueryExecutionError::NotSupported(
+                "Only queries are supported".to_string(),
+            ))
+        }
     };
 
+    // Reject queries that are too deep or too complex before resolving
+    // any of their fields
+    let result = check_root_selection_set(
+        ctx.clone(),
+        selection_set,
+        options.max_depth,
+        options.max_complexity,
+    ).and_then(|_| execute_root_selection_set(ctx, selection_set, &None));
+
     match result {
         Ok(value) => QueryResult::new(Some(value)),
         Err(e) => QueryResult::from(e),
@@ -3,6 +3,8 @@ use graphql_parser::Pos;
 use inflector::Inflector;
 use std::iter::IntoIterator;
 
+use graph::prelude::FullTextDefinition;
+
 use schema::ast;
 
 #[derive(Fail, Debug)]
@@ -30,6 +32,7 @@ pub fn api_schema(input_schema: &Document) -> Result<Document, APISchemaError> {
     add_order_direction_enum(&mut schema);
     add_types_for_object_types(&mut schema, &object_types)?;
     add_types_for_interface_types(&mut schema, &interface_types)?;
+    add_aggregate_types(&mut schema, &object_types)?;
     add_query_type(&mut schema, &object_types, &interface_types)?;
     add_subscription_type(&mut schema, &object_types, &interface_types)?;
     add_typename_fields(&mut schema)?;
@@ -39,7 +42,17 @@ pub fn api_schema(input_schema: &Document) -> Result<Document, APISchemaError> {
 
 /// Adds built-in GraphQL scalar types (`Int`, `String` etc.) to the schema.
 fn add_builtin_scalar_types(schema: &mut Document) -> Result<(), APISchemaError> {
-    for name in ["Boolean", "ID", "Int", "Float", "String", "Bytes", "BigInt"].into_iter() {
+    for name in [
+        "Boolean",
+        "ID",
+        "Int",
+        "Float",
+        "String",
+        "Bytes",
+        "BigInt",
+        "BigDecimal",
+    ].into_iter()
+    {
         match ast::get_named_type(schema, &name.to_string()) {
             None => {
                 let typedef = TypeDefinition::Scalar(ScalarType {
@@ -205,6 +218,7 @@ fn field_scalar_filter_input_values(
 ) -> Vec<InputValue> {
     match field_type.name.as_ref() {
         "BigInt" => vec!["", "not", "gt", "lt", "gte", "lte", "in", "not_in"],
+        "BigDecimal" => vec!["", "not", "gt", "lt", "gte", "lte", "in", "not_in"],
         "Boolean" => vec!["", "not", "in", "not_in"],
         "Bytes" => vec!["", "not", "in", "not_in", "contains", "not_contains"],
         "Float" => vec!["", "not", "gt", "lt", "gte", "lte", "in", "not_in"],
@@ -226,6 +240,10 @@ fn field_scalar_filter_input_values(
             "not_starts_with",
             "ends_with",
             "not_ends_with",
+            "contains_nocase",
+            "starts_with_nocase",
+            "ends_with_nocase",
+            "matches",
         ],
         _ => vec!["", "not"],
     }.into_iter()
@@ -286,6 +304,128 @@ fn input_value(name: &Name, suffix: &'static str, value_type: Type) -> InputValu
     }
 }
 
+/// Returns the numeric, non-list fields of an object type along with the
+/// names of their scalar types.
+fn numeric_fields(object_type: &ObjectType) -> Vec<(&Field, &str)> {
+    object_type
+        .fields
+        .iter()
+        .filter_map(|field| {
+            let type_name = match field.field_type {
+                Type::NonNullType(ref t) => match **t {
+                    Type::NamedType(ref name) => name,
+                    _ => return None,
+                },
+                Type::NamedType(ref name) => name,
+                Type::ListType(_) => return None,
+            };
+            match type_name.as_str() {
+                "Int" | "Float" | "BigInt" | "BigDecimal" => Some((field, type_name.as_str())),
+                _ => None,
+            }
+        }).collect()
+}
+
+/// Generates a nullable field with the given name and type for an aggregate type.
+fn aggregate_field(name: &str, type_name: &str) -> Field {
+    Field {
+        position: Pos::default(),
+        description: None,
+        name: name.to_owned(),
+        arguments: vec![],
+        field_type: Type::NamedType(type_name.to_owned()),
+        directives: vec![],
+    }
+}
+
+/// Adds an object type with the given name and fields to the schema.
+fn add_object_type(
+    schema: &mut Document,
+    type_name: String,
+    fields: Vec<Field>,
+) -> Result<(), APISchemaError> {
+    if ast::get_named_type(schema, &type_name).is_some() {
+        return Err(APISchemaError::TypeExists(type_name));
+    }
+
+    let typedef = TypeDefinition::Object(ObjectType {
+        position: Pos::default(),
+        description: None,
+        name: type_name,
+        implements_interfaces: vec![],
+        directives: vec![],
+        fields,
+    });
+    let def = Definition::TypeDefinition(typedef);
+    schema.definitions.push(def);
+    Ok(())
+}
+
+/// Adds `*_aggregate` object types for the given object types to the schema.
+///
+/// Types without numeric fields can only be counted; for all other types,
+/// `*_sum`, `*_minMax` and `*_avg` types are added as well. Sums of `Int`
+/// fields are `BigInt`s and averages of `Int` and `Float` fields are
+/// `Float`s, all other aggregates have the type of the aggregated field.
+fn add_aggregate_types(
+    schema: &mut Document,
+    object_types: &[&ObjectType],
+) -> Result<(), APISchemaError> {
+    for object_type in object_types {
+        let name = &object_type.name;
+        let numeric_fields = numeric_fields(object_type);
+
+        let mut fields = vec![Field {
+            position: Pos::default(),
+            description: None,
+            name: "count".to_owned(),
+            arguments: vec![],
+            field_type: Type::NonNullType(Box::new(Type::NamedType("Int".to_owned()))),
+            directives: vec![],
+        }];
+
+        if !numeric_fields.is_empty() {
+            let aggregate_fields = |aggregate_type: fn(&str) -> &str| {
+                numeric_fields
+                    .iter()
+                    .map(|(field, type_name)| {
+                        aggregate_field(&field.name, aggregate_type(type_name))
+                    }).collect::<Vec<_>>()
+            };
+
+            add_object_type(
+                schema,
+                format!("{}_sum", name),
+                aggregate_fields(|type_name| match type_name {
+                    "Int" => "BigInt",
+                    _ => type_name,
+                }),
+            )?;
+            add_object_type(
+                schema,
+                format!("{}_minMax", name),
+                aggregate_fields(|type_name| type_name),
+            )?;
+            add_object_type(
+                schema,
+                format!("{}_avg", name),
+                aggregate_fields(|type_name| match type_name {
+                    "Int" | "Float" => "Float",
+                    _ => "BigDecimal",
+                }),
+            )?;
+
+            fields.push(aggregate_field("sum", &format!("{}_sum", name)));
+            fields.push(aggregate_field("min", &format!("{}_minMax", name)));
+            fields.push(aggregate_field("max", &format!("{}_minMax", name)));
+            fields.push(aggregate_field("avg", &format!("{}_avg", name)));
+        }
+
+        add_object_type(schema, format!("{}_aggregate", name), fields)?;
+    }
+    Ok(())
+}
+
 /// Adds a root `Query` object type to the schema.
 fn add_query_type(
     schema: &mut Document,
@@ -309,6 +449,8 @@ fn add_query_type(
             .map(|t| &t.name)
             .chain(interface_types.iter().map(|t| &t.name))
             .flat_map(|name| query_fields_for_type(schema, name))
+            .chain(object_types.iter().flat_map(|t| full_text_query_fields(t)))
+            .chain(object_types.iter().map(|t| aggregate_query_field(t)))
             .collect(),
     });
     let def = Definition::TypeDefinition(typedef);
@@ -398,6 +540,67 @@ fn query_fields_for_type(_schema: &Document, type_name: &Name) -> Vec<Field> {
     ]
 }
 
+/// Generates `Query` fields for the full-text searches declared on the given
+/// type with `@fulltext` directives. The directive is kept on the generated
+/// field so that resolvers can identify it as a search field.
+fn full_text_query_fields(object_type: &ObjectType) -> Vec<Field> {
+    object_type
+        .directives
+        .iter()
+        .filter(|directive| directive.name == "fulltext")
+        .map(|directive| Field {
+            position: Pos::default(),
+            description: None,
+            name: FullTextDefinition::from_directive(&object_type.name, directive).name,
+            arguments: vec![
+                input_value(
+                    &"text".to_string(),
+                    "",
+                    Type::NonNullType(Box::new(Type::NamedType("String".to_string()))),
+                ),
+                input_value(&"skip".to_string(), "", Type::NamedType("Int".to_string())),
+                input_value(&"first".to_string(), "", Type::NamedType("Int".to_string())),
+                input_value(
+                    &"where".to_string(),
+                    "",
+                    Type::NamedType(format!("{}_filter", object_type.name)),
+                ),
+            ],
+            field_type: Type::NonNullType(Box::new(Type::ListType(Box::new(Type::NonNullType(
+                Box::new(Type::NamedType(object_type.name.to_owned())),
+            ))))),
+            directives: vec![directive.clone()],
+        }).collect()
+}
+
+/// Generates the `Query` field that aggregates entities of the given type
+/// (e.g. `usersAggregate`). The field is marked with an `@aggregate`
+/// directive so that resolvers can identify the entity type to aggregate.
+fn aggregate_query_field(object_type: &ObjectType) -> Field {
+    Field {
+        position: Pos::default(),
+        description: None,
+        name: format!("{}Aggregate", object_type.name.to_plural().to_camel_case()),
+        arguments: vec![input_value(
+            &"where".to_string(),
+            "",
+            Type::NamedType(format!("{}_filter", object_type.name)),
+        )],
+        field_type: Type::NonNullType(Box::new(Type::NamedType(format!(
+            "{}_aggregate",
+            object_type.name
+        )))),
+        directives: vec![Directive {
+            position: Pos::default(),
+            name: "aggregate".to_owned(),
+            arguments: vec![(
+                "entity".to_owned(),
+                Value::String(object_type.name.to_owned()),
+            )],
+        }],
+    }
+}
+
 fn add_typename_fields(schema: &mut Document) -> Result<(), APISchemaError> {
     for object_type in ast::get_object_type_definitions_mut(schema) {
         add_typename_field_to_object_type(object_type)?;
@@ -564,7 +767,11 @@ mod tests {
                 "name_starts_with",
                 "name_not_starts_with",
                 "name_ends_with",
-                "name_not_ends_with"
+                "name_not_ends_with",
+                "name_contains_nocase",
+                "name_starts_with_nocase",
+                "name_ends_with_nocase",
+                "name_matches"
             ]
                 .iter()
                 .map(|name| name.to_string())
@@ -752,4 +959,135 @@ mod tests {
                 assert!(interface_type.fields.iter().any(is_typename_field))
             });
     }
+
+    #[test]
+    fn api_schema_contains_full_text_search_fields() {
+        let input_schema = parse_schema(
+            "
+            type Band @fulltext(name: \"bandSearch\", fields: [\"name\", \"bio\"]) {
+                id: ID!
+                name: String!
+                bio: String
+            }
+            ",
+        ).expect("Failed to parse input schema");
+        let schema = api_schema(&input_schema).expect("Failed to derive API schema");
+
+        let query_type = match ast::get_named_type(&schema, &"Query".to_string()) {
+            Some(TypeDefinition::Object(t)) => t,
+            _ => panic!("Root Query type is missing in API schema"),
+        };
+        let search_field = ast::get_field_type(query_type, &"bandSearch".to_string())
+            .expect("bandSearch field is missing in the Query type");
+
+        assert_eq!(
+            search_field
+                .arguments
+                .iter()
+                .map(|argument| argument.name.to_owned())
+                .collect::<Vec<String>>(),
+            ["text", "skip", "first", "where"]
+        );
+        assert_eq!(
+            search_field.field_type,
+            Type::NonNullType(Box::new(Type::ListType(Box::new(Type::NonNullType(
+                Box::new(Type::NamedType("Band".to_string())),
+            )))))
+        );
+        assert_eq!(search_field.directives[0].name, "fulltext");
+    }
+
+    #[test]
+    fn api_schema_contains_aggregate_fields() {
+        let input_schema = parse_schema(
+            "
+            type User {
+                id: ID!
+                name: String!
+                age: Int!
+                weight: Float
+                balance: BigDecimal
+                scores: [Int!]!
+            }
+
+            type Tag {
+                id: ID!
+                label: String!
+            }
+            ",
+        ).expect("Failed to parse input schema");
+        let schema = api_schema(&input_schema).expect("Failed to derive API schema");
+
+        let object_type = |name: &str| match ast::get_named_type(&schema, &name.to_string()) {
+            Some(TypeDefinition::Object(t)) => t,
+            _ => panic!("{} type is missing in API schema", name),
+        };
+        fn type_name(t: &Type) -> String {
+            match t {
+                Type::NamedType(name) => name.to_owned(),
+                Type::ListType(t) => format!("[{}]", type_name(t)),
+                Type::NonNullType(t) => format!("{}!", type_name(t)),
+            }
+        }
+        let field_types = |name: &str| {
+            object_type(name)
+                .fields
+                .iter()
+                .filter(|field| field.name != "__typename")
+                .map(|field| (field.name.to_owned(), type_name(&field.field_type)))
+                .collect::<Vec<(String, String)>>()
+        };
+        let pairs = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(name, t)| (name.to_string(), t.to_string()))
+                .collect::<Vec<(String, String)>>()
+        };
+
+        assert_eq!(
+            field_types("User_aggregate"),
+            pairs(&[
+                ("count", "Int!"),
+                ("sum", "User_sum"),
+                ("min", "User_minMax"),
+                ("max", "User_minMax"),
+                ("avg", "User_avg"),
+            ])
+        );
+        assert_eq!(
+            field_types("User_sum"),
+            pairs(&[
+                ("age", "BigInt"),
+                ("weight", "Float"),
+                ("balance", "BigDecimal"),
+            ])
+        );
+        assert_eq!(
+            field_types("User_minMax"),
+            pairs(&[
+                ("age", "Int"),
+                ("weight", "Float"),
+                ("balance", "BigDecimal"),
+            ])
+        );
+        assert_eq!(
+            field_types("User_avg"),
+            pairs(&[
+                ("age", "Float"),
+                ("weight", "Float"),
+                ("balance", "BigDecimal"),
+            ])
+        );
+
+        // Types without numeric fields can only be counted
+        assert_eq!(field_types("Tag_aggregate"), pairs(&[("count", "Int!")]));
+        assert!(ast::get_named_type(&schema, &"Tag_sum".to_string()).is_none());
+
+        let aggregate_field =
+            ast::get_field_type(object_type("Query"), &"usersAggregate".to_string())
+                .expect("usersAggregate field is missing in the Query type");
+        assert_eq!(aggregate_field.arguments[0].name, "where");
+        assert_eq!(type_name(&aggregate_field.field_type), "User_aggregate!");
+        assert_eq!(aggregate_field.directives[0].name, "aggregate");
+    }
 }
@@ -18,12 +18,22 @@ pub(crate) enum FilterOp {
     NotStartsWith,
     EndsWith,
     NotEndsWith,
+    ContainsNoCase,
+    StartsWithNoCase,
+    EndsWithNoCase,
+    Matches,
     Equal,
 }
 
 /// Split a "name_eq" style name into an attribute ("name") and a filter op (`Equal`).
 pub(crate) fn parse_field_as_filter(key: &Name) -> (Name, FilterOp) {
     let (suffix, op) = match key {
+        k if k.ends_with("_contains_nocase") => ("_contains_nocase", FilterOp::ContainsNoCase),
+        k if k.ends_with("_starts_with_nocase") => {
+            ("_starts_with_nocase", FilterOp::StartsWithNoCase)
+        }
+        k if k.ends_with("_ends_with_nocase") => ("_ends_with_nocase", FilterOp::EndsWithNoCase),
+        k if k.ends_with("_matches") => ("_matches", FilterOp::Matches),
         k if k.ends_with("_not") => ("_not", FilterOp::Not),
         k if k.ends_with("_gt") => ("_gt", FilterOp::GreaterThan),
         k if k.ends_with("_lt") => ("_lt", FilterOp::LessThan),
@@ -222,6 +232,30 @@ pub fn get_type_definition_from_type<'a>(
     }
 }
 
+/// Returns the entity type aggregated by a field with an
+/// `@aggregate(entity: "...")` directive, if the field has one.
+pub fn get_aggregate_entity_type<'a>(
+    schema: &'a Document,
+    field_definition: &Field,
+) -> Option<&'a ObjectType> {
+    field_definition
+        .directives
+        .iter()
+        .find(|directive| directive.name == "aggregate")
+        .and_then(|directive| {
+            directive
+                .arguments
+                .iter()
+                .find(|(name, _)| name == "entity")
+        }).and_then(|(_, value)| match value {
+            Value::String(name) => get_named_type(schema, name),
+            _ => None,
+        }).and_then(|typedef| match typedef {
+            TypeDefinition::Object(t) => Some(t),
+            _ => None,
+        })
+}
+
 /// Looks up a directive in a object type, if it is provided.
 pub fn get_object_type_directive(object_type: &ObjectType, name: Name) -> Option<&Directive> {
     object_type
@@ -16,6 +16,7 @@ pub fn build_query(
         filter: build_filter(entity, arguments)?,
         order_by: build_order_by(entity, arguments)?,
         order_direction: build_order_direction(arguments)?,
+        deadline: None,
     })
 }
 
@@ -120,6 +121,10 @@ fn build_filter_from_object(
                     NotStartsWith => EntityFilter::NotStartsWith(field_name, store_value),
                     EndsWith => EntityFilter::EndsWith(field_name, store_value),
                     NotEndsWith => EntityFilter::NotEndsWith(field_name, store_value),
+                    ContainsNoCase => EntityFilter::ContainsNoCase(field_name, store_value),
+                    StartsWithNoCase => EntityFilter::StartsWithNoCase(field_name, store_value),
+                    EndsWithNoCase => EntityFilter::EndsWithNoCase(field_name, store_value),
+                    Matches => EntityFilter::Matches(field_name, store_value),
                     Equal => EntityFilter::Equal(field_name, store_value),
                 })
             }).collect::<Result<Vec<EntityFilter>, QueryExecutionError>>()?
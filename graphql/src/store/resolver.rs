@@ -1,8 +1,10 @@
 use graphql_parser::{query as q, schema as s};
-use std::collections::HashMap;
+use std::cmp;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::ops::Deref;
 use std::result;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 use graph::components::store::*;
 use graph::prelude::*;
@@ -12,20 +14,84 @@ use query::ast as qast;
 use schema::ast as sast;
 use store::query::{collect_entities_from_query_field, parse_subgraph_id};
 
+/// Identifies the entities a relationship field resolves to, independent of
+/// the type of the parent object the field belongs to.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum PrefetchKey {
+    /// Entities that refer to the parent with the given ID in `field`.
+    Derived {
+        entity_type: String,
+        field: String,
+        parent_id: String,
+        arguments: BTreeMap<q::Name, ArgumentValue>,
+    },
+    /// Entities with the given IDs.
+    References {
+        entity_type: String,
+        ids: Vec<String>,
+        arguments: BTreeMap<q::Name, ArgumentValue>,
+    },
+}
+
+/// An argument value of a field that, unlike `q::Value`, can be hashed.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum ArgumentValue {
+    Variable(q::Name),
+    Int(Option<i64>),
+    /// The bits of the float, so that equal floats compare equal.
+    Float(u64),
+    String(String),
+    Boolean(bool),
+    Null,
+    Enum(q::Name),
+    List(Vec<ArgumentValue>),
+    Object(BTreeMap<q::Name, ArgumentValue>),
+}
+
+impl<'a> From<&'a q::Value> for ArgumentValue {
+    fn from(value: &q::Value) -> Self {
+        match value {
+            q::Value::Variable(name) => ArgumentValue::Variable(name.clone()),
+            q::Value::Int(n) => ArgumentValue::Int(n.as_i64()),
+            q::Value::Float(f) => ArgumentValue::Float(f.to_bits()),
+            q::Value::String(s) => ArgumentValue::String(s.clone()),
+            q::Value::Boolean(b) => ArgumentValue::Boolean(*b),
+            q::Value::Null => ArgumentValue::Null,
+            q::Value::Enum(name) => ArgumentValue::Enum(name.clone()),
+            q::Value::List(values) => {
+                ArgumentValue::List(values.iter().map(ArgumentValue::from).collect())
+            }
+            q::Value::Object(object) => ArgumentValue::Object(
+                object
+                    .iter()
+                    .map(|(name, value)| (name.clone(), ArgumentValue::from(value)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
 /// A resolver that fetches entities from a `Store`.
 pub struct StoreResolver<S> {
     logger: Logger,
     store: Arc<S>,
+    /// Point in time after which store queries are aborted.
+    deadline: Option<Instant>,
+    /// Entities fetched ahead of time for relationship fields of many parents.
+    prefetched: Mutex<HashMap<PrefetchKey, Vec<q::Value>>>,
 }
 
 impl<S> Clone for StoreResolver<S>
 where
     S: Store,
 {
+    /// Clones the resolver; prefetched entities are not shared with the clone.
     fn clone(&self) -> Self {
         StoreResolver {
             logger: self.logger.clone(),
             store: self.store.clone(),
+            deadline: self.deadline,
+            prefetched: Mutex::new(HashMap::new()),
         }
     }
 }
@@ -38,9 +104,29 @@ where
         StoreResolver {
             logger: logger.new(o!("component" => "StoreResolver")),
             store,
+            deadline: None,
+            prefetched: Mutex::new(HashMap::new()),
         }
     }
 
+    /// Aborts the store queries of the resolver once the deadline has passed.
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Builds a store query from GraphQL arguments that is bounded by the
+    /// deadline of the resolver.
+    fn build_query(
+        &self,
+        entity: &s::ObjectType,
+        arguments: &HashMap<&q::Name, q::Value>,
+    ) -> Result<EntityQuery, QueryExecutionError> {
+        let mut query = build_query(entity, arguments)?;
+        query.deadline = self.deadline;
+        Ok(query)
+    }
+
     /// If the field has a `@derivedFrom(field: "foo")` directive, obtain the
     /// name of the field (e.g. `"foo"`)
     fn get_derived_from_directive(field_definition: &s::Field) -> Option<&s::Directive> {
@@ -50,6 +136,50 @@ where
             .find(|directive| directive.name == s::Name::from("derivedFrom"))
     }
 
+    /// If the field has a `@derivedFrom(field: "foo")` directive, obtain the
+    /// definition of the field it is derived from in the object type
+    fn get_derived_from_field<'a>(
+        field_definition: &s::Field,
+        object_type: &'a s::ObjectType,
+    ) -> Option<&'a s::Field> {
+        Self::get_derived_from_directive(field_definition)
+            .and_then(|directive| {
+                qast::get_argument_value(&directive.arguments, &q::Name::from("field"))
+            }).and_then(|value| match value {
+                q::Value::String(s) => Some(s),
+                _ => None,
+            }).and_then(|derived_from_field_name| {
+                sast::get_field_type(object_type, derived_from_field_name)
+            })
+    }
+
+    /// Returns true if the field type is a list type, possibly wrapped in a
+    /// non-null type.
+    fn is_list_type(field_type: &s::Type) -> bool {
+        match field_type {
+            s::Type::ListType(_) => true,
+            s::Type::NonNullType(ref inner) => match inner.deref() {
+                s::Type::ListType(_) => true,
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// Adds a filter to the top-level `And` filter of a query, creating one
+    /// if necessary.
+    fn add_filter(query: &mut EntityQuery, filter: EntityFilter) {
+        let top_level_filter = query.filter.get_or_insert(EntityFilter::And(vec![]));
+        *top_level_filter = match top_level_filter {
+            EntityFilter::And(ref mut filters) => {
+                let mut filters = filters.clone();
+                filters.push(filter);
+                EntityFilter::And(filters)
+            }
+            _ => top_level_filter.clone(),
+        };
+    }
+
     /// Adds a filter for matching entities that correspond to a derived field.
     ///
     /// Returns true if the field is a derived field (i.e., if it is defined with
@@ -60,15 +190,7 @@ where
         field_definition: &s::Field,
         object_type: &s::ObjectType,
     ) -> bool {
-        let derived_from_field = Self::get_derived_from_directive(field_definition)
-            .and_then(|directive| {
-                qast::get_argument_value(&directive.arguments, &q::Name::from("field"))
-            }).and_then(|value| match value {
-                q::Value::String(s) => Some(s),
-                _ => None,
-            }).and_then(|derived_from_field_name| {
-                sast::get_field_type(object_type, derived_from_field_name)
-            });
+        let derived_from_field = Self::get_derived_from_field(field_definition, object_type);
 
         if let Some(derived_from_field) = derived_from_field {
             // This field is derived from a field in the object type that we're trying
@@ -97,26 +219,14 @@ where
             // Depending on whether the field we're deriving from has a list or a
             // single value type, we either create a `Contains` or `Equal`
             // filter argument
-            let filter = match derived_from_field.field_type {
-                s::Type::ListType(_) => EntityFilter::Contains(field_name, parent_id),
-                s::Type::NonNullType(ref inner) => match inner.deref() {
-                    s::Type::ListType(_) => EntityFilter::Contains(field_name, parent_id),
-                    _ => EntityFilter::Equal(field_name, parent_id),
-                },
-                _ => EntityFilter::Equal(field_name, parent_id),
+            let filter = if Self::is_list_type(&derived_from_field.field_type) {
+                EntityFilter::Contains(field_name, parent_id)
+            } else {
+                EntityFilter::Equal(field_name, parent_id)
             };
 
-            // Add the `Contains`/`Equal` filter to the top-level `And` filter, creating one
-            // if necessary
-            let top_level_filter = query.filter.get_or_insert(EntityFilter::And(vec![]));
-            *top_level_filter = match top_level_filter {
-                EntityFilter::And(ref mut filters) => {
-                    let mut filters = filters.clone();
-                    filters.push(filter);
-                    EntityFilter::And(filters)
-                }
-                _ => top_level_filter.clone(),
-            };
+            // Add the `Contains`/`Equal` filter to the top-level `And` filter
+            Self::add_filter(query, filter);
 
             true
         } else {
@@ -156,16 +266,32 @@ where
                     )
                 });
 
-            // Add the `Or` filter to the top-level `And` filter, creating one if necessary
-            let top_level_filter = query.filter.get_or_insert(EntityFilter::And(vec![]));
-            *top_level_filter = match top_level_filter {
-                EntityFilter::And(ref mut filters) => {
-                    let mut filters = filters.clone();
-                    filters.push(filter);
-                    EntityFilter::And(filters)
-                }
-                _ => top_level_filter.clone(),
-            };
+            // Add the `Or` filter to the top-level `And` filter
+            Self::add_filter(query, filter);
+        }
+    }
+
+    /// Adds a filter for matching entities against the search text of a
+    /// full-text search field (i.e., a field with a `@fulltext` directive).
+    fn add_filter_for_full_text_search(
+        query: &mut EntityQuery,
+        field_definition: &s::Field,
+        object_type: &s::ObjectType,
+        arguments: &HashMap<&q::Name, q::Value>,
+    ) {
+        let directive = field_definition
+            .directives
+            .iter()
+            .find(|directive| directive.name == "fulltext");
+
+        if let (Some(directive), Some(q::Value::String(text))) =
+            (directive, arguments.get(&"text".to_string()))
+        {
+            let definition = FullTextDefinition::from_directive(&object_type.name, directive);
+            Self::add_filter(
+                query,
+                EntityFilter::FullTextMatch(definition.fields, definition.language, text.clone()),
+            );
         }
     }
 
@@ -182,6 +308,142 @@ where
                 _ => true,
             }).unwrap_or(true)
     }
+
+    /// Returns the ID of a parent object.
+    fn parent_id(parent: &q::Value) -> Option<&String> {
+        match parent {
+            q::Value::Object(object) => match object.get("id") {
+                Some(q::Value::String(id)) => Some(id),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns the IDs referenced by a parent object in the given field.
+    fn referenced_ids(parent: &q::Value, field: &q::Name) -> Option<Vec<String>> {
+        match parent {
+            q::Value::Object(object) => match object.get(field) {
+                Some(q::Value::String(id)) => Some(vec![id.clone()]),
+                Some(q::Value::List(ids)) => Some(
+                    ids.iter()
+                        .filter_map(|id| match id {
+                            q::Value::String(s) => Some(s.clone()),
+                            _ => None,
+                        }).collect(),
+                ),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Identifies the entities that a field of a parent object resolves to.
+    fn prefetch_key(
+        parent: &q::Value,
+        field_definition: &s::Field,
+        object_type: &s::ObjectType,
+        arguments: &HashMap<&q::Name, q::Value>,
+    ) -> Option<PrefetchKey> {
+        // Arguments are ordered to make the key independent of the order
+        // in which they appear in the query
+        let arguments: BTreeMap<q::Name, ArgumentValue> = arguments
+            .iter()
+            .map(|(name, value)| ((*name).clone(), ArgumentValue::from(value)))
+            .collect();
+
+        match Self::get_derived_from_field(field_definition, object_type) {
+            Some(derived_from_field) => Self::parent_id(parent).map(|id| PrefetchKey::Derived {
+                entity_type: object_type.name.clone(),
+                field: derived_from_field.name.clone(),
+                parent_id: id.clone(),
+                arguments,
+            }),
+            None => Self::referenced_ids(parent, &field_definition.name).map(|ids| {
+                PrefetchKey::References {
+                    entity_type: object_type.name.clone(),
+                    ids,
+                    arguments,
+                }
+            }),
+        }
+    }
+
+    /// Builds a filter that matches the entities of all the given parents.
+    fn prefetch_filter(
+        field_definition: &s::Field,
+        object_type: &s::ObjectType,
+        keys: &[&PrefetchKey],
+    ) -> EntityFilter {
+        match Self::get_derived_from_field(field_definition, object_type) {
+            Some(derived_from_field) => {
+                let parent_ids = keys
+                    .iter()
+                    .filter_map(|key| match key {
+                        PrefetchKey::Derived { parent_id, .. } => {
+                            Some(Value::from(parent_id.as_str()))
+                        }
+                        _ => None,
+                    }).collect::<Vec<_>>();
+
+                if Self::is_list_type(&derived_from_field.field_type) {
+                    EntityFilter::Or(
+                        parent_ids
+                            .into_iter()
+                            .map(|id| EntityFilter::Contains(derived_from_field.name.clone(), id))
+                            .collect(),
+                    )
+                } else {
+                    EntityFilter::In(derived_from_field.name.clone(), parent_ids)
+                }
+            }
+            None => {
+                let ids = keys
+                    .iter()
+                    .filter_map(|key| match key {
+                        PrefetchKey::References { ids, .. } => Some(ids),
+                        _ => None,
+                    }).flat_map(|ids| ids.iter())
+                    .collect::<HashSet<_>>();
+
+                EntityFilter::In(
+                    String::from("id"),
+                    ids.into_iter().map(|id| Value::from(id.as_str())).collect(),
+                )
+            }
+        }
+    }
+
+    /// Returns whether an entity is one of those a prefetch key identifies.
+    fn belongs_to(entity: &Entity, key: &PrefetchKey) -> bool {
+        match key {
+            PrefetchKey::Derived {
+                field, parent_id, ..
+            } => match entity.get(field) {
+                Some(Value::String(id)) => id == parent_id,
+                Some(Value::List(ids)) => ids.contains(&Value::from(parent_id.as_str())),
+                _ => false,
+            },
+            PrefetchKey::References { ids, .. } => match entity.get("id") {
+                Some(Value::String(id)) => ids.contains(id),
+                _ => false,
+            },
+        }
+    }
+
+    /// Returns the entities prefetched for a field of a parent object, if any.
+    fn get_prefetched(
+        &self,
+        parent: &Option<q::Value>,
+        field_definition: &s::Field,
+        object_type: &s::ObjectType,
+        arguments: &HashMap<&q::Name, q::Value>,
+    ) -> Option<Vec<q::Value>> {
+        parent
+            .as_ref()
+            .and_then(|parent| Self::prefetch_key(parent, field_definition, object_type, arguments))
+            .and_then(|key| self.prefetched.lock().unwrap().get(&key).cloned())
+    }
 }
 
 impl<S> Resolver for StoreResolver<S>
@@ -196,7 +458,16 @@ where
         object_type: &s::ObjectType,
         arguments: &HashMap<&q::Name, q::Value>,
     ) -> Result<q::Value, QueryExecutionError> {
-        let mut query = build_query(&object_type, arguments)?;
+        // Use the entities prefetched for the parent, if there are any
+        if let Some(values) = self.get_prefetched(parent, field_definition, object_type, arguments)
+        {
+            return Ok(q::Value::List(values));
+        }
+
+        let mut query = self.build_query(&object_type, arguments)?;
+
+        // Add matching filter for full-text search fields
+        Self::add_filter_for_full_text_search(&mut query, field_definition, object_type, arguments);
 
         // Add matching filter for derived fields
         let is_derived =
@@ -252,8 +523,17 @@ where
                 })?.map_or(q::Value::Null, |entity| entity.into()));
         }
 
+        // Use the entity prefetched for the parent, if there is one
+        if let Some(values) = self.get_prefetched(parent, field_definition, object_type, arguments)
+        {
+            return Ok(values.into_iter().next().unwrap_or(q::Value::Null));
+        }
+
         match parent {
             Some(q::Value::Object(parent_object)) => match parent_object.get(field) {
+                // Objects embedded in the parent (e.g. the sums of an
+                // aggregate) are returned as they are
+                Some(value @ q::Value::Object(_)) => Ok(value.clone()),
                 Some(q::Value::String(id)) => Ok(self
                     .store
                     .get(EntityKey {
@@ -266,7 +546,7 @@ where
                 _ => Ok(q::Value::Null),
             },
             _ => {
-                let mut query = build_query(&object_type, arguments)?;
+                let mut query = self.build_query(&object_type, arguments)?;
 
                 // Add matching filter for derived fields
                 Self::add_filter_for_derived_field(
@@ -289,6 +569,153 @@ where
         }
     }
 
+    fn resolve_aggregate(
+        &self,
+        entity_type: &s::ObjectType,
+        arguments: &HashMap<&q::Name, q::Value>,
+    ) -> Result<q::Value, QueryExecutionError> {
+        let query = self.build_query(entity_type, arguments)?;
+
+        // Sums, minimums, maximums and averages are computed for all
+        // numeric fields that are not lists
+        let attributes = entity_type
+            .fields
+            .iter()
+            .filter_map(|field| {
+                sast::get_field_value_type(&field.field_type)
+                    .ok()
+                    .and_then(|value_type| match value_type {
+                        ValueType::Int
+                        | ValueType::Float
+                        | ValueType::BigInt
+                        | ValueType::BigDecimal => Some((field.name.clone(), value_type)),
+                        _ => None,
+                    })
+            }).collect();
+
+        let aggregates = self.store.aggregate(query, attributes)?;
+
+        let object = |values: HashMap<Attribute, Value>| {
+            q::Value::Object(
+                values
+                    .into_iter()
+                    .map(|(attribute, value)| (attribute, value.into()))
+                    .collect(),
+            )
+        };
+
+        // Counts beyond the range of `Int` are reported as its maximum
+        let count = cmp::min(aggregates.count, i32::max_value() as u64) as i32;
+
+        let mut result = BTreeMap::new();
+        result.insert("count".to_owned(), q::Value::Int(q::Number::from(count)));
+        result.insert("sum".to_owned(), object(aggregates.sum));
+        result.insert("min".to_owned(), object(aggregates.min));
+        result.insert("max".to_owned(), object(aggregates.max));
+        result.insert("avg".to_owned(), object(aggregates.avg));
+        Ok(q::Value::Object(result))
+    }
+
+    fn prefetch_objects(
+        &self,
+        parents: &[q::Value],
+        field_definition: &s::Field,
+        object_type: &s::ObjectType,
+        arguments: &HashMap<&q::Name, q::Value>,
+    ) -> Result<Vec<q::Value>, QueryExecutionError> {
+        let mut query = self.build_query(&object_type, arguments)?;
+
+        // Pagination applies to the entities of each parent individually,
+        // not to the entities of all parents together
+        let range = query.range.take();
+
+        let keys: Vec<PrefetchKey> = parents
+            .iter()
+            .filter_map(|parent| {
+                Self::prefetch_key(parent, field_definition, object_type, arguments)
+            }).collect();
+
+        // Only fetch entities for parents that we haven't seen before
+        let missing: Vec<&PrefetchKey> = {
+            let prefetched = self.prefetched.lock().unwrap();
+            keys.iter()
+                .filter(|key| !prefetched.contains_key(*key))
+                .collect()
+        };
+
+        if !missing.is_empty() {
+            // Match the entities of all parents with a single query, which
+            // needs at most `skip + first` entities per parent
+            let per_parent = range.as_ref().map(|range| range.skip + range.first);
+            let limit = per_parent.map(|n| n.saturating_mul(missing.len()));
+
+            let mut batch_query = query.clone();
+            Self::add_filter(
+                &mut batch_query,
+                Self::prefetch_filter(field_definition, object_type, &missing),
+            );
+
+            // Distribute the entities among the parents they belong to,
+            // preserving the order in which the store returned them
+            let mut matching: HashMap<&PrefetchKey, Vec<q::Value>> =
+                missing.iter().map(|key| (*key, vec![])).collect();
+
+            // A few parents with many entities can fill a page on their
+            // own; keep paging through the batched query until every parent
+            // has all the entities it needs or the store runs out of them
+            let mut skip = 0;
+            loop {
+                let mut page_query = batch_query.clone();
+                page_query.range = limit.map(|first| EntityRange { first, skip });
+
+                let entities = self.store.find(page_query)?;
+                let page_size = entities.len();
+
+                for entity in entities {
+                    for key in missing.iter() {
+                        if Self::belongs_to(&entity, key) {
+                            matching.get_mut(key).unwrap().push(entity.clone().into());
+                        }
+                    }
+                }
+
+                match (limit, per_parent) {
+                    (Some(limit), Some(per_parent))
+                        if page_size >= limit
+                            && matching.values().any(|values| values.len() < per_parent) =>
+                    {
+                        skip += limit
+                    }
+                    _ => break,
+                }
+            }
+
+            let mut prefetched = self.prefetched.lock().unwrap();
+            for (key, values) in matching {
+                let values: Vec<q::Value> = match &range {
+                    Some(range) => values
+                        .into_iter()
+                        .skip(range.skip)
+                        .take(range.first)
+                        .collect(),
+                    None => values,
+                };
+                prefetched.insert(key.clone(), values);
+            }
+        }
+
+        // Return every entity resolved for the parents once
+        let prefetched = self.prefetched.lock().unwrap();
+        let mut seen = HashSet::new();
+        Ok(keys
+            .iter()
+            .filter_map(|key| prefetched.get(key))
+            .flat_map(|values| values.iter())
+            .filter(|value| Self::parent_id(value).map_or(true, |id| seen.insert(id.clone())))
+            .cloned()
+            .collect())
+    }
+
     fn resolve_field_stream<'a, 'b>(
         &self,
         schema: &'a s::Document,
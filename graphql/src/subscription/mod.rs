@@ -19,6 +19,10 @@ where
     pub logger: slog::Logger,
     /// The resolver to use.
     pub resolver: R,
+    /// Maximum nesting depth of the subscription.
+    pub max_depth: u8,
+    /// Maximum complexity of the subscription, as estimated from its `first` arguments.
+    pub max_complexity: Option<u64>,
 }
 
 pub fn execute_subscription<R>(
@@ -30,8 +34,16 @@ where
 {
     info!(options.logger, "Execute subscription");
 
-    // Obtain the only operation of the subscription (fail if there is none or more than one)
-    let operation = qast::get_operation(&subscription.query.document, None)?;
+    // Obtain the operation to execute (fail if there is none or if the name
+    // is missing or doesn't match when there is more than one)
+    let operation = qast::get_operation(
+        &subscription.query.document,
+        subscription
+            .query
+            .operation_name
+            .as_ref()
+            .map(|name| name.as_str()),
+    )?;
 
     // Create an introspection type store and resolver
     let introspection_schema = introspection_schema();
@@ -48,11 +60,25 @@ where
         introspecting: false,
         document: &subscription.query.document,
         fields: vec![],
+        deadline: None,
+        variable_values: Arc::new(variable_values(operation, &subscription.query.variables)),
     };
 
     match *operation {
         // Execute top-level `subscription { ... }` expressions
         q::OperationDefinition::Subscription(ref subscription) => {
+            // Reject subscriptions that are too deep or too complex, since
+            // they are executed again for every event
+            let subscription_type = sast::get_root_subscription_type(&ctx.schema.document)
+                .ok_or(QueryExecutionError::NoRootSubscriptionObjectType)?;
+            check_selection_set(
+                ctx.clone(),
+                &subscription.selection_set,
+                subscription_type,
+                options.max_depth,
+                options.max_complexity,
+            )?;
+
             let source_stream = create_source_event_stream(&ctx, subscription)?;
             let response_stream = map_source_to_response_stream(&ctx, subscription, source_stream)?;
             Ok(response_stream)
@@ -126,6 +152,7 @@ where
     let resolver = ctx.resolver.clone();
     let schema = ctx.schema.clone();
     let document = ctx.document.clone();
+    let variable_values = ctx.variable_values.clone();
     let subscription = subscription.to_owned();
 
     Ok(Box::new(source_stream.map(move |event| {
@@ -134,6 +161,7 @@ where
             resolver.clone(),
             schema.clone(),
             document.clone(),
+            variable_values.clone(),
             subscription.clone(),
             event,
         )
@@ -145,6 +173,7 @@ fn execute_subscription_event<R1>(
     resolver: Arc<R1>,
     schema: Schema,
     document: q::Document,
+    variable_values: Arc<HashMap<q::Name, q::Value>>,
     subscription: q::Subscription,
     event: EntityChange,
 ) -> QueryResult
@@ -160,13 +189,17 @@ where
     // Create a fresh execution context
     let ctx = ExecutionContext {
         logger: logger,
-        resolver: resolver,
+        // Use a fresh resolver for every event, so that objects prefetched
+        // for a previous event are not reused
+        resolver: Arc::new(resolver.as_ref().clone()),
         schema: &schema,
         introspection_resolver: Arc::new(introspection_resolver),
         introspection_schema: &introspection_schema,
         introspecting: false,
         document: &document,
         fields: vec![],
+        deadline: None,
+        variable_values,
     };
 
     // We have established that this exists earlier in the subscription execution
@@ -34,6 +34,7 @@ impl MaybeCoercible<ScalarType> for Value {
             ("ID", v @ Value::String(_)) => Some(v.clone()),
             ("Bytes", v @ Value::String(_)) => Some(v.clone()),
             ("BigInt", v @ Value::String(_)) => Some(v.clone()),
+            ("BigDecimal", v @ Value::String(_)) => Some(v.clone()),
             _ => None,
         }
     }
@@ -299,6 +300,17 @@ mod tests {
         );
     }
 
+    #[test]
+    fn coerce_big_decimal_scalar() {
+        let big_decimal_type = TypeDefinition::Scalar(ScalarType::new("BigDecimal".to_string()));
+
+        // We can coerce from Value::String -> TypeDefinition::Scalar(BigDecimal)
+        assert_eq!(
+            Value::String("-12.345".to_string()).coerce(&big_decimal_type),
+            Some(Value::String("-12.345".to_string()))
+        );
+    }
+
     #[test]
     fn coerce_bytes_scalar() {
         let bytes_type = TypeDefinition::Scalar(ScalarType::new("Bytes".to_string()));
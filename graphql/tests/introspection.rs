@@ -483,6 +483,7 @@ fn introspection_query(schema: Schema, query: &str) -> QueryResult {
         schema: schema,
         document: graphql_parser::parse_query(query).unwrap(),
         variables: None,
+        operation_name: None,
     };
 
     // Execute it
@@ -491,6 +492,9 @@ fn introspection_query(schema: Schema, query: &str) -> QueryResult {
         QueryExecutionOptions {
             logger: slog::Logger::root(slog::Discard, o!()),
             resolver: MockResolver,
+            deadline: None,
+            max_depth: u8::max_value(),
+            max_complexity: None,
         },
     )
 }
@@ -8,8 +8,11 @@ extern crate graph_core;
 extern crate graph_graphql;
 
 use graphql_parser::query as q;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 
 use graph::prelude::*;
+use graph::serde_json;
 use graph_graphql::prelude::*;
 
 fn test_schema() -> Schema {
@@ -23,7 +26,7 @@ fn test_schema() -> Schema {
                 writtenSongs: [Song]! @derivedFrom(field: \"writtenBy\")
             }
 
-            type Band @entity {
+            type Band @entity @fulltext(name: \"bandSearch\", fields: [\"name\"]) {
                 id: ID!
                 name: String!
                 members: [Musician!]! @derivedFrom(field: \"bands\")
@@ -49,6 +52,8 @@ fn test_schema() -> Schema {
 #[derive(Clone)]
 struct TestStore {
     entities: Vec<Entity>,
+    get_calls: Arc<AtomicUsize>,
+    find_calls: Arc<AtomicUsize>,
 }
 
 impl TestStore {
@@ -124,16 +129,34 @@ impl TestStore {
                     ("writtenBy", Value::from("m3")),
                 ]),
             ],
+            get_calls: Arc::new(AtomicUsize::new(0)),
+            find_calls: Arc::new(AtomicUsize::new(0)),
         }
     }
 }
 
 impl Store for TestStore {
-    fn authorize_subgraph_name(&self, _: String, _: String) -> Result<(), Error> {
+    fn add_api_key(&self, _: ApiKey) -> Result<(), Error> {
         unimplemented!()
     }
 
-    fn check_subgraph_name_access_token(&self, _: String, _: String) -> Result<bool, Error> {
+    fn find_api_key(&self, _: String) -> Result<Option<ApiKey>, Error> {
+        unimplemented!()
+    }
+
+    fn find_api_key_by_token_hash(&self, _: String) -> Result<Option<ApiKey>, Error> {
+        unimplemented!()
+    }
+
+    fn authorize_subgraph_name(&self, _: String, _: String) -> Result<String, Error> {
+        unimplemented!()
+    }
+
+    fn revoke_api_key(&self, _: String) -> Result<bool, Error> {
+        unimplemented!()
+    }
+
+    fn add_admin_audit_entry(&self, _: AdminAuditEntry) -> Result<(), Error> {
         unimplemented!()
     }
 
@@ -153,6 +176,18 @@ impl Store for TestStore {
         unimplemented!()
     }
 
+    fn read_all_pending_subgraph_ids(&self) -> Result<Vec<(String, SubgraphId)>, Error> {
+        unimplemented!()
+    }
+
+    fn write_pending_subgraph_id(&self, _: String, _: Option<SubgraphId>) -> Result<(), Error> {
+        unimplemented!()
+    }
+
+    fn promote_pending_subgraph_id(&self, _: String, _: SubgraphId) -> Result<bool, Error> {
+        unimplemented!()
+    }
+
     fn delete_subgraph_name(&self, _: String) -> Result<(), Error> {
         unimplemented!()
     }
@@ -161,6 +196,30 @@ impl Store for TestStore {
         unimplemented!()
     }
 
+    fn read_all_subgraph_ids(&self) -> Result<Vec<SubgraphId>, Error> {
+        unimplemented!()
+    }
+
+    fn count_entities(&self, _: SubgraphId) -> Result<u64, Error> {
+        unimplemented!()
+    }
+
+    fn remove_unused_subgraph(&self, _: SubgraphId) -> Result<Option<u64>, Error> {
+        unimplemented!()
+    }
+
+    fn remove_subgraph_data(&self, _: SubgraphId) -> Result<(), Error> {
+        unimplemented!()
+    }
+
+    fn set_subgraph_paused(&self, _: SubgraphId, _: bool) -> Result<(), Error> {
+        unimplemented!()
+    }
+
+    fn is_subgraph_paused(&self, _: SubgraphId) -> Result<bool, Error> {
+        unimplemented!()
+    }
+
     fn block_ptr(&self, _: SubgraphId) -> Result<EthereumBlockPointer, Error> {
         unimplemented!()
     }
@@ -196,12 +255,30 @@ impl Store for TestStore {
     ) -> Result<(), Error> {
         unimplemented!()
     }
+    fn revert_to_block(
+        &self,
+        _: SubgraphId,
+        _: EthereumBlockPointer,
+        _: EthereumBlockPointer,
+    ) -> Result<(), Error> {
+        unimplemented!()
+    }
+
+    fn create_full_text_indexes(
+        &self,
+        _: SubgraphId,
+        _: Vec<FullTextDefinition>,
+    ) -> Result<(), Error> {
+        unimplemented!()
+    }
 
     fn subscribe(&self, _: Vec<SubgraphEntityPair>) -> EntityChangeStream {
         unimplemented!()
     }
 
     fn get(&self, key: EntityKey) -> Result<Option<Entity>, QueryExecutionError> {
+        self.get_calls.fetch_add(1, Ordering::SeqCst);
+
         self.entities
             .iter()
             .find(|entity| {
@@ -216,6 +293,8 @@ impl Store for TestStore {
     }
 
     fn find(&self, query: EntityQuery) -> Result<Vec<Entity>, QueryExecutionError> {
+        self.find_calls.fetch_add(1, Ordering::SeqCst);
+
         let entity_name = Value::String(query.entity_type.clone());
 
         let entities = self
@@ -227,7 +306,10 @@ impl Store for TestStore {
             //
             // - And(Contains(...))
             // - And(Equal(...))
+            // - And(In(...))
             // - And(Or([Equal(...), ...]))
+            // - And(Or([Contains(...), ...]))
+            // - And(FullTextMatch(...)), matching any word case-insensitively
             .filter(|entity| {
                 query
                     .filter
@@ -241,36 +323,124 @@ impl Store for TestStore {
                             Some(Value::List(values)) => values.contains(v),
                             _ => false,
                         },
+                        EntityFilter::FullTextMatch(attributes, _, text) => {
+                            attributes
+                                .iter()
+                                .any(|attribute| match entity.get(attribute) {
+                                    Some(Value::String(s)) => text.split_whitespace().any(|word| {
+                                        s.to_lowercase().contains(&word.to_lowercase())
+                                    }),
+                                    _ => false,
+                                })
+                        }
+                        EntityFilter::In(k, values) => match entity.get(k) {
+                            Some(v) => values.contains(v),
+                            None => false,
+                        },
                         EntityFilter::Or(filters) => filters.iter().any(|filter| match filter {
                             EntityFilter::Equal(k, v) => entity.get(k) == Some(&v),
+                            EntityFilter::Contains(k, v) => match entity.get(k) {
+                                Some(Value::List(values)) => values.contains(v),
+                                _ => false,
+                            },
                             _ => unimplemented!(),
                         }),
                         _ => unimplemented!(),
                     }).unwrap_or(true)
             }).map(|entity| entity.clone())
-            .collect();
+            .collect::<Vec<_>>();
 
-        Ok(entities)
+        Ok(match query.range {
+            Some(range) => entities
+                .into_iter()
+                .skip(range.skip)
+                .take(range.first)
+                .collect(),
+            None => entities,
+        })
+    }
+
+    fn aggregate(
+        &self,
+        query: EntityQuery,
+        _: Vec<(Attribute, ValueType)>,
+    ) -> Result<EntityAggregates, QueryExecutionError> {
+        // The test schema has no numeric fields, so there's only the count
+        self.find(query).map(|entities| EntityAggregates {
+            count: entities.len() as u64,
+            ..EntityAggregates::default()
+        })
     }
 }
 
 fn execute_query_document(query: q::Document) -> QueryResult {
+    execute_query_document_with_operation_name(query, None)
+}
+
+fn execute_query_document_with_operation_name(
+    query: q::Document,
+    operation_name: Option<&str>,
+) -> QueryResult {
+    let query = Query {
+        schema: test_schema(),
+        document: query,
+        variables: None,
+        operation_name: operation_name.map(|name| name.to_owned()),
+    };
+
+    execute_query_with_limits(&query, None, u8::max_value(), None)
+}
+
+fn execute_query_document_with_limits(
+    query: q::Document,
+    deadline: Option<Instant>,
+    max_depth: u8,
+    max_complexity: Option<u64>,
+) -> QueryResult {
     let query = Query {
         schema: test_schema(),
         document: query,
         variables: None,
+        operation_name: None,
     };
 
+    execute_query_with_limits(&query, deadline, max_depth, max_complexity)
+}
+
+fn execute_query_with_limits(
+    query: &Query,
+    deadline: Option<Instant>,
+    max_depth: u8,
+    max_complexity: Option<u64>,
+) -> QueryResult {
+    execute_query_with_store(
+        query,
+        Arc::new(TestStore::new()),
+        deadline,
+        max_depth,
+        max_complexity,
+    )
+}
+
+fn execute_query_with_store(
+    query: &Query,
+    store: Arc<TestStore>,
+    deadline: Option<Instant>,
+    max_depth: u8,
+    max_complexity: Option<u64>,
+) -> QueryResult {
     let logger = Logger::root(slog::Discard, o!());
-    let store = Arc::new(TestStore::new());
     let store_resolver = StoreResolver::new(&logger, store);
 
     let options = QueryExecutionOptions {
         logger: logger,
         resolver: store_resolver,
+        deadline,
+        max_depth,
+        max_complexity,
     };
 
-    execute_query(&query, options)
+    execute_query(query, options)
 }
 
 #[test]
@@ -512,3 +682,415 @@ fn can_query_many_to_many_relationship() {
         )]))
     );
 }
+
+#[test]
+fn resolves_relationships_with_one_store_query_per_field() {
+    let store = Arc::new(TestStore::new());
+    let query = Query {
+        schema: test_schema(),
+        document: graphql_parser::parse_query(
+            "
+            query {
+                musicians {
+                    name
+                    mainBand { name }
+                    bands {
+                        name
+                        members { name }
+                    }
+                    writtenSongs {
+                        title
+                        writtenBy { name }
+                    }
+                }
+            }
+            ",
+        ).expect("Invalid test query"),
+        variables: None,
+        operation_name: None,
+    };
+
+    let result = execute_query_with_store(&query, store.clone(), None, u8::max_value(), None);
+
+    assert!(
+        result.errors.is_none(),
+        format!("Unexpected errors return for query: {:#?}", result.errors)
+    );
+
+    // One query for the musicians and one for each of `mainBand`, `bands`,
+    // `members`, `writtenSongs` and `writtenBy`, no matter how many
+    // musicians, bands and songs there are
+    assert_eq!(store.find_calls.load(Ordering::SeqCst), 6);
+    assert_eq!(store.get_calls.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn paginates_batched_relationships_per_parent() {
+    let store = Arc::new(TestStore::new());
+    let query = Query {
+        schema: test_schema(),
+        document: graphql_parser::parse_query(
+            "
+            query {
+                musicians(first: 3) {
+                    name
+                    writtenSongs(first: 1) { title }
+                }
+            }
+            ",
+        ).expect("Invalid test query"),
+        variables: None,
+        operation_name: None,
+    };
+
+    let result = execute_query_with_store(&query, store.clone(), None, u8::max_value(), None);
+
+    // The songs of John fill up the first page of the batched query, which
+    // allows one song per musician, so the song of Tom is on the second page
+    assert_eq!(
+        result.data,
+        Some(object_value(vec![(
+            "musicians",
+            q::Value::List(vec![
+                object_value(vec![
+                    ("name", q::Value::String(String::from("John"))),
+                    (
+                        "writtenSongs",
+                        q::Value::List(vec![object_value(vec![(
+                            "title",
+                            q::Value::String(String::from("Cheesy Tune")),
+                        )])]),
+                    ),
+                ]),
+                object_value(vec![
+                    ("name", q::Value::String(String::from("Lisa"))),
+                    (
+                        "writtenSongs",
+                        q::Value::List(vec![object_value(vec![(
+                            "title",
+                            q::Value::String(String::from("Rock Tune")),
+                        )])]),
+                    ),
+                ]),
+                object_value(vec![
+                    ("name", q::Value::String(String::from("Tom"))),
+                    (
+                        "writtenSongs",
+                        q::Value::List(vec![object_value(vec![(
+                            "title",
+                            q::Value::String(String::from("Folk Tune")),
+                        )])]),
+                    ),
+                ]),
+            ]),
+        )]))
+    );
+    assert_eq!(store.find_calls.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn can_query_full_text_search_fields() {
+    let result = execute_query_document(
+        graphql_parser::parse_query(
+            "
+            query {
+                bandSearch(text: \"amateurs\") {
+                    name
+                }
+            }
+            ",
+        ).expect("Invalid test query"),
+    );
+
+    assert!(
+        result.errors.is_none(),
+        format!("Unexpected errors return for query: {:#?}", result.errors)
+    );
+
+    assert_eq!(
+        result.data,
+        Some(object_value(vec![(
+            "bandSearch",
+            q::Value::List(vec![object_value(vec![(
+                "name",
+                q::Value::String(String::from("The Amateurs")),
+            )])]),
+        )]))
+    );
+}
+
+#[test]
+fn can_query_aggregates() {
+    let result = execute_query_document(
+        graphql_parser::parse_query(
+            "
+            query {
+                all: musiciansAggregate {
+                    count
+                }
+                lisas: musiciansAggregate(where: { name: \"Lisa\" }) {
+                    count
+                }
+            }
+            ",
+        ).expect("Invalid test query"),
+    );
+
+    assert!(
+        result.errors.is_none(),
+        format!("Unexpected errors return for query: {:#?}", result.errors)
+    );
+
+    assert_eq!(
+        result.data,
+        Some(object_value(vec![
+            (
+                "all",
+                object_value(vec![("count", q::Value::Int(q::Number::from(4)))]),
+            ),
+            (
+                "lisas",
+                object_value(vec![("count", q::Value::Int(q::Number::from(1)))]),
+            ),
+        ]))
+    );
+}
+
+#[test]
+fn can_select_operation_by_name() {
+    let document = graphql_parser::parse_query(
+        "
+        query musicianNames {
+            musicians {
+                name
+            }
+        }
+
+        query bandNames {
+            bands {
+                name
+            }
+        }
+        ",
+    ).expect("Invalid test query");
+
+    let result = execute_query_document_with_operation_name(document.clone(), Some("bandNames"));
+
+    assert!(
+        result.errors.is_none(),
+        format!("Unexpected errors return for query: {:#?}", result.errors)
+    );
+
+    assert_eq!(
+        result.data,
+        Some(object_value(vec![(
+            "bands",
+            q::Value::List(vec![
+                object_value(vec![(
+                    "name",
+                    q::Value::String(String::from("The Musicians")),
+                )]),
+                object_value(vec![(
+                    "name",
+                    q::Value::String(String::from("The Amateurs")),
+                )]),
+            ]),
+        )]))
+    );
+
+    // Without an operation name the query is ambiguous
+    let result = execute_query_document(document.clone());
+    match result.errors.as_ref().map(|errors| &errors[..]) {
+        Some([QueryError::ExecutionError(QueryExecutionError::OperationNameRequired)]) => (),
+        errors => panic!("Unexpected errors: {:#?}", errors),
+    }
+
+    // Unknown operation names are rejected
+    let result = execute_query_document_with_operation_name(document, Some("songTitles"));
+    match result.errors.as_ref().map(|errors| &errors[..]) {
+        Some([QueryError::ExecutionError(QueryExecutionError::OperationNotFound(name))]) => {
+            assert_eq!(name, "songTitles")
+        }
+        errors => panic!("Unexpected errors: {:#?}", errors),
+    }
+}
+
+#[test]
+fn rejects_queries_that_are_too_deep() {
+    let document = graphql_parser::parse_query(
+        "
+        query {
+            musicians {
+                bands {
+                    members {
+                        bands {
+                            name
+                        }
+                    }
+                }
+            }
+        }
+        ",
+    ).expect("Invalid test query");
+
+    let result = execute_query_document_with_limits(document.clone(), None, 4, None);
+    match result.errors.as_ref().map(|errors| &errors[..]) {
+        Some([QueryError::ExecutionError(QueryExecutionError::TooDeep(4))]) => (),
+        errors => panic!("Unexpected errors: {:#?}", errors),
+    }
+
+    let result = execute_query_document_with_limits(document, None, 5, None);
+    assert!(
+        result.errors.is_none(),
+        format!("Unexpected errors return for query: {:#?}", result.errors)
+    );
+}
+
+#[test]
+fn rejects_queries_that_are_too_complex() {
+    let document = graphql_parser::parse_query(
+        "
+        query {
+            musicians(first: 10) {
+                name
+                bands(first: 5) {
+                    name
+                }
+            }
+        }
+        ",
+    ).expect("Invalid test query");
+
+    // Each musician contributes its name and up to five bands
+    let result = execute_query_document_with_limits(document.clone(), None, 10, Some(59));
+    match result.errors.as_ref().map(|errors| &errors[..]) {
+        Some([QueryError::ExecutionError(QueryExecutionError::TooComplex(60, 59))]) => (),
+        errors => panic!("Unexpected errors: {:#?}", errors),
+    }
+
+    let result = execute_query_document_with_limits(document, None, 10, Some(60));
+    assert!(
+        result.errors.is_none(),
+        format!("Unexpected errors return for query: {:#?}", result.errors)
+    );
+}
+
+#[test]
+fn charges_first_arguments_given_as_variables_at_their_value() {
+    let query = Query {
+        schema: test_schema(),
+        document: graphql_parser::parse_query(
+            "
+            query musicians($first: Int) {
+                musicians(first: $first) {
+                    name
+                }
+            }
+            ",
+        ).expect("Invalid test query"),
+        variables: Some(
+            serde_json::from_str::<QueryVariables>("{\"first\": 50}").expect("Invalid variables"),
+        ),
+        operation_name: None,
+    };
+
+    let result = execute_query_with_limits(&query, None, 10, Some(10));
+    match result.errors.as_ref().map(|errors| &errors[..]) {
+        Some([QueryError::ExecutionError(QueryExecutionError::TooComplex(50, 10))]) => (),
+        errors => panic!("Unexpected errors: {:#?}", errors),
+    }
+
+    let result = execute_query_with_limits(&query, None, 10, Some(50));
+    assert!(
+        result.errors.is_none(),
+        format!("Unexpected errors return for query: {:#?}", result.errors)
+    );
+}
+
+#[test]
+fn charges_aggregate_fields_for_the_entities_they_read() {
+    let document = graphql_parser::parse_query(
+        "
+        query {
+            musiciansAggregate {
+                count
+            }
+        }
+        ",
+    ).expect("Invalid test query");
+
+    let result = execute_query_document_with_limits(document, None, 10, Some(100));
+    match result.errors.as_ref().map(|errors| &errors[..]) {
+        Some([QueryError::ExecutionError(QueryExecutionError::TooComplex(1000, 100))]) => (),
+        errors => panic!("Unexpected errors: {:#?}", errors),
+    }
+}
+
+#[test]
+fn rejects_subscriptions_that_are_too_deep_or_too_complex() {
+    let subscription = Subscription {
+        query: Query {
+            schema: test_schema(),
+            document: graphql_parser::parse_query(
+                "
+                subscription {
+                    musicians(first: 10) {
+                        bands(first: 10) {
+                            members {
+                                name
+                            }
+                        }
+                    }
+                }
+                ",
+            ).expect("Invalid test subscription"),
+            variables: None,
+            operation_name: None,
+        },
+    };
+
+    let execute = |max_depth: u8, max_complexity: Option<u64>| {
+        let logger = Logger::root(slog::Discard, o!());
+        let options = SubscriptionExecutionOptions {
+            logger: logger.clone(),
+            resolver: StoreResolver::new(&logger, Arc::new(TestStore::new())),
+            max_depth,
+            max_complexity,
+        };
+        match execute_subscription(&subscription, options) {
+            Ok(_) => panic!("Subscription should have been rejected"),
+            Err(SubscriptionError::GraphQLError(errors)) => errors,
+        }
+    };
+
+    match &execute(3, None)[..] {
+        [QueryExecutionError::TooDeep(3)] => (),
+        errors => panic!("Unexpected errors: {:#?}", errors),
+    }
+
+    // Ten musicians with ten bands each, with the default of 100 members each
+    match &execute(10, Some(1000))[..] {
+        [QueryExecutionError::TooComplex(10000, 1000)] => (),
+        errors => panic!("Unexpected errors: {:#?}", errors),
+    }
+}
+
+#[test]
+fn aborts_queries_that_time_out() {
+    let document = graphql_parser::parse_query(
+        "
+        query {
+            musicians {
+                name
+            }
+        }
+        ",
+    ).expect("Invalid test query");
+
+    let deadline = Instant::now() - Duration::from_secs(1);
+    let result = execute_query_document_with_limits(document, Some(deadline), 10, None);
+    match result.errors.as_ref().map(|errors| &errors[..]) {
+        Some([QueryError::ExecutionError(QueryExecutionError::Timeout)]) => (),
+        errors => panic!("Unexpected errors: {:#?}", errors),
+    }
+}
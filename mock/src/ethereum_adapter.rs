@@ -0,0 +1,59 @@
+use graph::components::ethereum::{EthereumContractCall, EthereumContractCallError};
+use graph::ethabi::Token;
+use graph::prelude::*;
+use graph::web3::types::H256;
+
+/// A mock `EthereumAdapter` for components that need one but don't use it.
+#[derive(Default)]
+pub struct MockEthereumAdapter {}
+
+impl EthereumAdapter for MockEthereumAdapter {
+    fn net_identifiers(
+        &self,
+        _: &Logger,
+    ) -> Box<Future<Item = EthereumNetworkIdentifier, Error = Error> + Send> {
+        unimplemented!();
+    }
+
+    fn block_by_hash(
+        &self,
+        _: &Logger,
+        _: H256,
+    ) -> Box<Future<Item = Option<EthereumBlock>, Error = Error> + Send> {
+        unimplemented!();
+    }
+
+    fn block_hash_by_block_number(
+        &self,
+        _: &Logger,
+        _: u64,
+    ) -> Box<Future<Item = Option<H256>, Error = Error> + Send> {
+        unimplemented!();
+    }
+
+    fn is_on_main_chain(
+        &self,
+        _: &Logger,
+        _: EthereumBlockPointer,
+    ) -> Box<Future<Item = bool, Error = Error> + Send> {
+        unimplemented!();
+    }
+
+    fn find_first_blocks_with_logs(
+        &self,
+        _: &Logger,
+        _: u64,
+        _: u64,
+        _: EthereumLogFilter,
+    ) -> Box<Future<Item = Vec<EthereumBlockPointer>, Error = Error> + Send> {
+        unimplemented!();
+    }
+
+    fn contract_call(
+        &self,
+        _: &Logger,
+        _: EthereumContractCall,
+    ) -> Box<Future<Item = Vec<Token>, Error = EthereumContractCallError> + Send> {
+        unimplemented!();
+    }
+}
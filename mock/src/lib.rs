@@ -5,12 +5,14 @@ extern crate graph_graphql;
 extern crate graphql_parser;
 
 mod block_stream;
+mod ethereum_adapter;
 mod graphql;
 mod server;
 mod store;
 mod subgraph;
 
 pub use self::block_stream::{MockBlockStream, MockBlockStreamBuilder};
+pub use self::ethereum_adapter::MockEthereumAdapter;
 pub use self::graphql::MockGraphQlRunner;
 pub use self::server::MockGraphQLServer;
 pub use self::store::{FakeStore, MockStore};
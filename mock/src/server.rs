@@ -107,6 +107,7 @@ where
                     schema: schema.clone().unwrap(),
                     document: graphql_parser::parse_query("{ allUsers { name }}").unwrap(),
                     variables: None,
+                    operation_name: None,
                 }
             }).collect::<Vec<Query>>();
 
@@ -1,8 +1,9 @@
 use failure::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Mutex;
 
 use graph::components::store::*;
+use graph::data::admin::SUBGRAPH_NAME_ROLES;
 use graph::prelude::*;
 use graph::web3::types::H256;
 
@@ -25,6 +26,13 @@ impl EventProducer<ChainHeadUpdate> for MockChainHeadUpdateListener {
 pub struct MockStore {
     entities: Vec<Entity>,
     subgraph_names: Mutex<HashMap<String, Option<SubgraphId>>>,
+    pending_subgraph_ids: Mutex<HashMap<String, SubgraphId>>,
+    subgraph_ids: Mutex<HashSet<SubgraphId>>,
+    paused_subgraph_ids: Mutex<HashSet<SubgraphId>>,
+    /// Admin API keys, and whether they hold the access token of their
+    /// subgraph name.
+    api_keys: Mutex<Vec<(ApiKey, bool)>>,
+    audit_log: Mutex<Vec<AdminAuditEntry>>,
 }
 
 impl MockStore {
@@ -42,8 +50,18 @@ impl MockStore {
         MockStore {
             entities,
             subgraph_names: Mutex::new(HashMap::new()),
+            pending_subgraph_ids: Mutex::new(HashMap::new()),
+            subgraph_ids: Mutex::new(HashSet::new()),
+            paused_subgraph_ids: Mutex::new(HashSet::new()),
+            api_keys: Mutex::new(vec![]),
+            audit_log: Mutex::new(vec![]),
         }
     }
+
+    /// Returns the entries of the audit log of admin API calls.
+    pub fn audit_log(&self) -> Vec<AdminAuditEntry> {
+        self.audit_log.lock().unwrap().clone()
+    }
 }
 
 impl Store for MockStore {
@@ -68,12 +86,72 @@ impl Store for MockStore {
         Ok(self.entities.clone())
     }
 
-    fn authorize_subgraph_name(&self, _: String, _: String) -> Result<(), Error> {
+    fn aggregate(
+        &self,
+        _: EntityQuery,
+        _: Vec<(Attribute, ValueType)>,
+    ) -> Result<EntityAggregates, QueryExecutionError> {
         unimplemented!();
     }
 
-    fn check_subgraph_name_access_token(&self, _: String, _: String) -> Result<bool, Error> {
-        unimplemented!();
+    fn add_api_key(&self, key: ApiKey) -> Result<(), Error> {
+        self.api_keys.lock().unwrap().push((key, false));
+        Ok(())
+    }
+
+    fn find_api_key(&self, id: String) -> Result<Option<ApiKey>, Error> {
+        let api_keys = self.api_keys.lock().unwrap();
+        Ok(api_keys
+            .iter()
+            .find(|(key, _)| key.id == id)
+            .map(|(key, _)| key.clone()))
+    }
+
+    fn find_api_key_by_token_hash(&self, token_hash: String) -> Result<Option<ApiKey>, Error> {
+        let api_keys = self.api_keys.lock().unwrap();
+        Ok(api_keys
+            .iter()
+            .find(|(key, _)| key.token_hash == token_hash)
+            .map(|(key, _)| key.clone()))
+    }
+
+    fn authorize_subgraph_name(&self, name: String, token: String) -> Result<String, Error> {
+        let mut api_keys = self.api_keys.lock().unwrap();
+        let token_hash = hash_api_token(&token);
+
+        let mut current_key_id = None;
+        for (key, is_subgraph_name_token) in api_keys.iter_mut() {
+            let is_current = *is_subgraph_name_token && !key.revoked;
+            if is_current && key.subgraph_name.as_ref() == Some(&name) {
+                if key.token_hash == token_hash {
+                    current_key_id = Some(key.id.clone());
+                } else {
+                    key.revoked = true;
+                }
+            }
+        }
+        if let Some(current_key_id) = current_key_id {
+            return Ok(current_key_id);
+        }
+
+        let key = ApiKey::with_token(&token, SUBGRAPH_NAME_ROLES.to_vec(), Some(name), None);
+        let key_id = key.id.clone();
+        api_keys.push((key, true));
+        Ok(key_id)
+    }
+
+    fn revoke_api_key(&self, id: String) -> Result<bool, Error> {
+        let mut api_keys = self.api_keys.lock().unwrap();
+        Ok(api_keys
+            .iter_mut()
+            .find(|(key, _)| key.id == id && !key.revoked)
+            .map(|(key, _)| key.revoked = true)
+            .is_some())
+    }
+
+    fn add_admin_audit_entry(&self, entry: AdminAuditEntry) -> Result<(), Error> {
+        self.audit_log.lock().unwrap().push(entry);
+        Ok(())
     }
 
     fn read_all_subgraph_names(&self) -> Result<Vec<(String, Option<SubgraphId>)>, Error> {
@@ -104,13 +182,114 @@ impl Store for MockStore {
             .collect())
     }
 
+    fn read_all_pending_subgraph_ids(&self) -> Result<Vec<(String, SubgraphId)>, Error> {
+        let pending_subgraph_ids = self.pending_subgraph_ids.lock().unwrap();
+        Ok(pending_subgraph_ids
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect())
+    }
+
+    fn write_pending_subgraph_id(
+        &self,
+        name: String,
+        id_opt: Option<SubgraphId>,
+    ) -> Result<(), Error> {
+        let mut subgraph_names = self.subgraph_names.lock().unwrap();
+        let mut pending_subgraph_ids = self.pending_subgraph_ids.lock().unwrap();
+        subgraph_names.entry(name.clone()).or_insert(None);
+        match id_opt {
+            Some(id) => pending_subgraph_ids.insert(name, id),
+            None => pending_subgraph_ids.remove(&name),
+        };
+        Ok(())
+    }
+
+    fn promote_pending_subgraph_id(&self, name: String, id: SubgraphId) -> Result<bool, Error> {
+        let mut subgraph_names = self.subgraph_names.lock().unwrap();
+        let mut pending_subgraph_ids = self.pending_subgraph_ids.lock().unwrap();
+        if pending_subgraph_ids.get(&name) != Some(&id) {
+            return Ok(false);
+        }
+        pending_subgraph_ids.remove(&name);
+        subgraph_names.insert(name, Some(id));
+        Ok(true)
+    }
+
     fn delete_subgraph_name(&self, name: String) -> Result<(), Error> {
         let mut subgraph_names = self.subgraph_names.lock().unwrap();
+        let mut pending_subgraph_ids = self.pending_subgraph_ids.lock().unwrap();
         subgraph_names.remove(&name);
+        pending_subgraph_ids.remove(&name);
         Ok(())
     }
 
-    fn add_subgraph_if_missing(&self, _: SubgraphId, _: EthereumBlockPointer) -> Result<(), Error> {
+    fn add_subgraph_if_missing(
+        &self,
+        id: SubgraphId,
+        _: EthereumBlockPointer,
+    ) -> Result<(), Error> {
+        self.subgraph_ids.lock().unwrap().insert(id);
+        Ok(())
+    }
+
+    fn read_all_subgraph_ids(&self) -> Result<Vec<SubgraphId>, Error> {
+        let mut ids = self
+            .subgraph_ids
+            .lock()
+            .unwrap()
+            .iter()
+            .cloned()
+            .collect::<Vec<_>>();
+        ids.sort();
+        Ok(ids)
+    }
+
+    fn count_entities(&self, _: SubgraphId) -> Result<u64, Error> {
+        Ok(0)
+    }
+
+    fn remove_unused_subgraph(&self, id: SubgraphId) -> Result<Option<u64>, Error> {
+        let subgraph_names = self.subgraph_names.lock().unwrap();
+        let pending_subgraph_ids = self.pending_subgraph_ids.lock().unwrap();
+        let is_current = subgraph_names
+            .values()
+            .any(|id_opt| id_opt.as_ref() == Some(&id));
+        let is_pending = pending_subgraph_ids
+            .values()
+            .any(|pending_id| *pending_id == id);
+
+        if is_current || is_pending {
+            Ok(None)
+        } else {
+            self.subgraph_ids.lock().unwrap().remove(&id);
+            Ok(Some(0))
+        }
+    }
+
+    fn remove_subgraph_data(&self, _: SubgraphId) -> Result<(), Error> {
+        unimplemented!();
+    }
+
+    fn set_subgraph_paused(&self, id: SubgraphId, paused: bool) -> Result<(), Error> {
+        let mut paused_subgraph_ids = self.paused_subgraph_ids.lock().unwrap();
+        if paused {
+            paused_subgraph_ids.insert(id);
+        } else {
+            paused_subgraph_ids.remove(&id);
+        }
+        Ok(())
+    }
+
+    fn is_subgraph_paused(&self, id: SubgraphId) -> Result<bool, Error> {
+        Ok(self.paused_subgraph_ids.lock().unwrap().contains(&id))
+    }
+
+    fn create_full_text_indexes(
+        &self,
+        _: SubgraphId,
+        _: Vec<FullTextDefinition>,
+    ) -> Result<(), Error> {
         unimplemented!();
     }
 
@@ -149,6 +328,14 @@ impl Store for MockStore {
     ) -> Result<(), Error> {
         unimplemented!();
     }
+    fn revert_to_block(
+        &self,
+        _: SubgraphId,
+        _: EthereumBlockPointer,
+        _: EthereumBlockPointer,
+    ) -> Result<(), Error> {
+        unimplemented!();
+    }
 
     fn subscribe(&self, _: Vec<SubgraphEntityPair>) -> EntityChangeStream {
         unimplemented!();
@@ -206,11 +393,35 @@ impl Store for FakeStore {
         unimplemented!();
     }
 
-    fn authorize_subgraph_name(&self, _: String, _: String) -> Result<(), Error> {
+    fn aggregate(
+        &self,
+        _: EntityQuery,
+        _: Vec<(Attribute, ValueType)>,
+    ) -> Result<EntityAggregates, QueryExecutionError> {
+        unimplemented!();
+    }
+
+    fn add_api_key(&self, _: ApiKey) -> Result<(), Error> {
+        unimplemented!();
+    }
+
+    fn find_api_key(&self, _: String) -> Result<Option<ApiKey>, Error> {
+        unimplemented!();
+    }
+
+    fn find_api_key_by_token_hash(&self, _: String) -> Result<Option<ApiKey>, Error> {
+        unimplemented!();
+    }
+
+    fn authorize_subgraph_name(&self, _: String, _: String) -> Result<String, Error> {
         unimplemented!();
     }
 
-    fn check_subgraph_name_access_token(&self, _: String, _: String) -> Result<bool, Error> {
+    fn revoke_api_key(&self, _: String) -> Result<bool, Error> {
+        unimplemented!();
+    }
+
+    fn add_admin_audit_entry(&self, _: AdminAuditEntry) -> Result<(), Error> {
         unimplemented!();
     }
 
@@ -230,6 +441,18 @@ impl Store for FakeStore {
         unimplemented!();
     }
 
+    fn read_all_pending_subgraph_ids(&self) -> Result<Vec<(String, SubgraphId)>, Error> {
+        unimplemented!();
+    }
+
+    fn write_pending_subgraph_id(&self, _: String, _: Option<SubgraphId>) -> Result<(), Error> {
+        unimplemented!();
+    }
+
+    fn promote_pending_subgraph_id(&self, _: String, _: SubgraphId) -> Result<bool, Error> {
+        unimplemented!();
+    }
+
     fn delete_subgraph_name(&self, _: String) -> Result<(), Error> {
         unimplemented!();
     }
@@ -238,6 +461,38 @@ impl Store for FakeStore {
         unimplemented!();
     }
 
+    fn read_all_subgraph_ids(&self) -> Result<Vec<SubgraphId>, Error> {
+        unimplemented!();
+    }
+
+    fn count_entities(&self, _: SubgraphId) -> Result<u64, Error> {
+        unimplemented!();
+    }
+
+    fn remove_unused_subgraph(&self, _: SubgraphId) -> Result<Option<u64>, Error> {
+        unimplemented!();
+    }
+
+    fn remove_subgraph_data(&self, _: SubgraphId) -> Result<(), Error> {
+        unimplemented!();
+    }
+
+    fn set_subgraph_paused(&self, _: SubgraphId, _: bool) -> Result<(), Error> {
+        unimplemented!();
+    }
+
+    fn is_subgraph_paused(&self, _: SubgraphId) -> Result<bool, Error> {
+        unimplemented!();
+    }
+
+    fn create_full_text_indexes(
+        &self,
+        _: SubgraphId,
+        _: Vec<FullTextDefinition>,
+    ) -> Result<(), Error> {
+        unimplemented!();
+    }
+
     fn block_ptr(&self, _: SubgraphId) -> Result<EthereumBlockPointer, Error> {
         unimplemented!();
     }
@@ -273,6 +528,14 @@ impl Store for FakeStore {
     ) -> Result<(), Error> {
         unimplemented!();
     }
+    fn revert_to_block(
+        &self,
+        _: SubgraphId,
+        _: EthereumBlockPointer,
+        _: EthereumBlockPointer,
+    ) -> Result<(), Error> {
+        unimplemented!();
+    }
 
     fn subscribe(&self, _: Vec<SubgraphEntityPair>) -> EntityChangeStream {
         unimplemented!();
@@ -13,13 +13,14 @@ extern crate graph_datasource_ethereum;
 extern crate graph_runtime_wasm;
 extern crate graph_server_http;
 extern crate graph_server_json_rpc;
+extern crate graph_server_metrics;
 extern crate graph_server_websocket;
 extern crate graph_store_postgres;
 extern crate http;
 extern crate ipfs_api;
 extern crate url;
 
-use clap::{App, Arg};
+use clap::{App, Arg, ArgMatches};
 use ipfs_api::IpfsClient;
 use itertools::FoldWhile::{Continue, Done};
 use itertools::Itertools;
@@ -31,27 +32,21 @@ use url::Url;
 
 use graph::components::{forward, forward2};
 use graph::prelude::{JsonRpcServer as JsonRpcServerTrait, *};
-use graph::util::log::{guarded_logger, logger, register_panic_hook};
+use graph::util::log::{guarded_logger, logger, register_panic_hook, LogFormat};
 use graph_core::{
-    ElasticLoggingConfig, SubgraphInstanceManager, SubgraphProvider as IpfsSubgraphProvider,
+    ElasticLoggingConfig, RecentSubgraphLogs, SubgraphInstanceManager,
+    SubgraphProvider as IpfsSubgraphProvider,
     SubgraphProviderWithNames as IpfsSubgraphProviderWithNames,
 };
 use graph_datasource_ethereum::{BlockStreamBuilder, Transport};
 use graph_runtime_wasm::RuntimeHostBuilder as WASMRuntimeHostBuilder;
 use graph_server_http::GraphQLServer as GraphQLQueryServer;
 use graph_server_json_rpc::{subgraph_deploy_request, JsonRpcServer};
+use graph_server_metrics::PrometheusMetricsServer;
 use graph_server_websocket::SubscriptionServer as GraphQLSubscriptionServer;
 use graph_store_postgres::{Store as DieselStore, StoreConfig};
 
 fn main() {
-    let (panic_logger, _panic_guard) = guarded_logger();
-    register_panic_hook(panic_logger);
-    tokio::run(future::lazy(async_main))
-}
-
-fn async_main() -> impl Future<Item = (), Error = ()> + Send + 'static {
-    env_logger::init();
-
     // Setup CLI using Clap, provide general info and capture postgres url
     let matches = App::new("graph-node")
         .version("0.1.0")
@@ -128,10 +123,25 @@ fn async_main() -> impl Future<Item = (), Error = ()> + Send + 'static {
                 .long("admin-port")
                 .value_name("PORT")
                 .help("Port for the JSON-RPC admin server"),
+        ).arg(
+            Arg::with_name("metrics-port")
+                .default_value("8040")
+                .long("metrics-port")
+                .value_name("PORT")
+                .help("Port for the Prometheus metrics server"),
         ).arg(
             Arg::with_name("debug")
                 .long("debug")
                 .help("Enable debug logging"),
+        ).arg(
+            Arg::with_name("log-format")
+                .takes_value(true)
+                .long("log-format")
+                .value_name("FORMAT")
+                .possible_values(&["text", "json"])
+                .default_value("text")
+                .env("GRAPH_LOG_FORMAT")
+                .help("Format of the logs written to the terminal"),
         ).arg(
             Arg::with_name("elasticsearch-url")
                 .long("elasticsearch-url")
@@ -160,8 +170,25 @@ fn async_main() -> impl Future<Item = (), Error = ()> + Send + 'static {
                 .help("How often to poll the Ethereum node for new blocks"),
         ).get_matches();
 
+    // Write all logs, including those of panics, in the same format
+    let log_format = match matches.value_of("log-format") {
+        Some("json") => LogFormat::Json,
+        _ => LogFormat::Text,
+    };
+
+    let (panic_logger, _panic_guard) = guarded_logger(log_format);
+    register_panic_hook(panic_logger);
+    tokio::run(future::lazy(move || async_main(matches, log_format)))
+}
+
+fn async_main(
+    matches: ArgMatches<'static>,
+    log_format: LogFormat,
+) -> impl Future<Item = (), Error = ()> + Send + 'static {
+    env_logger::init();
+
     // Set up logger
-    let logger = logger(matches.is_present("debug"));
+    let logger = logger(matches.is_present("debug"), log_format);
 
     // Safe to unwrap because a value is required by CLI
     let postgres_url = matches.value_of("postgres-url").unwrap().to_string();
@@ -201,6 +228,13 @@ fn async_main() -> impl Future<Item = (), Error = ()> + Send + 'static {
         .parse()
         .expect("invalid admin port");
 
+    // Obtain metrics server port
+    let metrics_port = matches
+        .value_of("metrics-port")
+        .unwrap()
+        .parse()
+        .expect("invalid metrics port");
+
     debug!(logger, "Setting up Sentry");
 
     // Set up Sentry, with release tracking and panic handling;
@@ -359,6 +393,13 @@ fn async_main() -> impl Future<Item = (), Error = ()> + Send + 'static {
                 password: matches.value_of("elasticsearch-password").map(|s| s.into()),
             });
 
+    // Keep the most recent logs of each subgraph around, so that developers
+    // can query them through the admin API
+    let max_recent_logs = env::var_os("GRAPH_SUBGRAPH_MAX_RECENT_LOGS")
+        .map(|s| s.to_str().unwrap().parse().unwrap())
+        .unwrap_or(1000);
+    let subgraph_logs = Arc::new(RecentSubgraphLogs::new(max_recent_logs));
+
     // Prepare for hosting WASM runtimes and managing subgraph instances
     let runtime_host_builder =
         WASMRuntimeHostBuilder::new(ethereum.clone(), ipfs_client.clone(), store.clone());
@@ -368,11 +409,12 @@ fn async_main() -> impl Future<Item = (), Error = ()> + Send + 'static {
         runtime_host_builder,
         block_stream_builder,
         elastic_config,
+        subgraph_logs.clone(),
     );
 
     // Create IPFS-based subgraph provider
     let mut subgraph_provider =
-        IpfsSubgraphProvider::new(logger.clone(), ipfs_client, store.clone());
+        IpfsSubgraphProvider::new(logger.clone(), ipfs_client, store.clone(), ethereum.clone());
 
     // Forward subgraph events from the subgraph provider to the subgraph instance manager
     tokio::spawn(forward(&mut subgraph_provider, &subgraph_instance_manager).unwrap());
@@ -396,6 +438,23 @@ fn async_main() -> impl Future<Item = (), Error = ()> + Send + 'static {
         .expect("failed to initialize subgraph provider"),
     );
 
+    // Switch subgraph names over to newly deployed versions once these have
+    // caught up with the chain head
+    tokio::spawn(named_subgraph_provider.poll_pending_versions());
+
+    // Remove the data of subgraph versions that no subgraph name refers to
+    // anymore, or only log what would be removed if this is a dry run
+    let subgraph_gc_interval = env::var_os("GRAPH_SUBGRAPH_GC_INTERVAL")
+        .map(|s| s.to_str().unwrap().parse().unwrap())
+        .unwrap_or(3600);
+    let subgraph_gc_dry_run = env::var_os("GRAPH_SUBGRAPH_GC_DRY_RUN")
+        .map(|s| s.to_str().unwrap().parse().unwrap())
+        .unwrap_or(false);
+    named_subgraph_provider.collect_garbage_periodically(
+        Duration::from_secs(subgraph_gc_interval),
+        subgraph_gc_dry_run,
+    );
+
     // Start admin JSON-RPC server.
     let json_rpc_server = JsonRpcServer::serve(
         json_rpc_port,
@@ -403,6 +462,7 @@ fn async_main() -> impl Future<Item = (), Error = ()> + Send + 'static {
         ws_port,
         named_subgraph_provider,
         store.clone(),
+        subgraph_logs,
         logger.clone(),
     ).expect("failed to start JSON-RPC admin server");
 
@@ -452,6 +512,13 @@ fn async_main() -> impl Future<Item = (), Error = ()> + Send + 'static {
             .expect("Failed to start GraphQL subscription server"),
     );
 
+    // Export metrics for Prometheus. We will listen on port 8040.
+    tokio::spawn(
+        PrometheusMetricsServer::new(&logger)
+            .serve(metrics_port)
+            .expect("Failed to start metrics server"),
+    );
+
     future::empty()
 }
 
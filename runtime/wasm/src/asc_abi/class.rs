@@ -350,6 +350,7 @@ pub enum StoreValueKind {
     Null,
     Bytes,
     BigInt,
+    BigDecimal,
 }
 
 impl StoreValueKind {
@@ -365,6 +366,7 @@ impl StoreValueKind {
             Value::Null => StoreValueKind::Null,
             Value::Bytes(_) => StoreValueKind::Bytes,
             Value::BigInt(_) => StoreValueKind::BigInt,
+            Value::BigDecimal(_) => StoreValueKind::BigDecimal,
         }
     }
 }
@@ -394,6 +396,15 @@ pub(crate) type Bytes = Uint8Array;
 /// cases correctly.
 pub(crate) type AscBigInt = Uint8Array;
 
+/// Big decimals are represented as `digits * 10^exp`.
+#[repr(C)]
+pub(crate) struct AscBigDecimal {
+    pub digits: AscPtr<AscBigInt>,
+    pub exp: AscPtr<AscBigInt>,
+}
+
+impl AscType for AscBigDecimal {}
+
 pub(crate) type AscAddress = Uint8Array;
 pub(crate) type AscH160 = Uint8Array;
 pub(crate) type AscH256 = Uint8Array;
@@ -510,3 +521,19 @@ impl JsonValueKind {
         }
     }
 }
+
+#[repr(u32)]
+#[derive(Copy, Clone)]
+pub(crate) enum RlpValueKind {
+    Bytes,
+    List,
+}
+
+impl Default for RlpValueKind {
+    fn default() -> Self {
+        RlpValueKind::Bytes
+    }
+}
+
+impl AscType for RlpValueKind {}
+impl AscValue for RlpValueKind {}
@@ -12,7 +12,9 @@ mod test;
 ///! AssemblyScript (Asc) WASM module. Objects are passed through
 ///! the `asc_new` and `asc_get` methods of an `AscHeap` implementation.
 ///! These methods take types that implement `To`/`FromAscObj` and are
-///! therefore convertible to/from an `AscType`.
+///! therefore convertible to/from an `AscType`. Types that not every Asc
+///! object can be converted to implement `TryFromAscObj` and are read with
+///! `try_asc_get`.
 ///! Implementations of `AscType` live in the `class` module.
 ///! Implementations of `To`/`FromAscObj` live in the `to_from` module.
 
@@ -56,6 +58,16 @@ pub trait AscHeap: Sized {
     {
         T::from_asc_obj(asc_ptr.read_ptr(self), self)
     }
+
+    /// Like `asc_get`, but fails if the Asc object does not hold a valid value
+    /// of the Rust type.
+    fn try_asc_get<T, C>(&self, asc_ptr: AscPtr<C>) -> Result<T, String>
+    where
+        C: AscType,
+        T: TryFromAscObj<C>,
+    {
+        T::try_from_asc_obj(asc_ptr.read_ptr(self), self)
+    }
 }
 
 /// Type that can be converted to an Asc object of class `C`.
@@ -68,6 +80,11 @@ pub trait FromAscObj<C: AscType> {
     fn from_asc_obj<H: AscHeap>(obj: C, heap: &H) -> Self;
 }
 
+/// Type that can be converted from some Asc objects of class `C`.
+pub trait TryFromAscObj<C: AscType>: Sized {
+    fn try_from_asc_obj<H: AscHeap>(obj: C, heap: &H) -> Result<Self, String>;
+}
+
 // `AscType` is not really public, implementors should live inside the `class` module.
 
 /// A type that has a direct corespondence to an Asc type, which
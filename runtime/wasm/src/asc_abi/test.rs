@@ -9,6 +9,7 @@ use wasmi::{
 
 use graph::prelude::BigInt;
 use graph::web3::types::{H160, U256};
+use std::str::FromStr;
 
 use super::class::*;
 use super::{AscHeap, AscPtr};
@@ -352,49 +353,55 @@ fn abi_store_value() {
         .expect("call returned nothing")
         .try_into()
         .expect("call did not return ptr");
-    let null_value: Value = module.asc_get(null_value_ptr);
+    let null_value: Value = module.try_asc_get(null_value_ptr).unwrap();
     assert_eq!(null_value, Value::Null);
 
     // Value::String
     let string = "some string";
-    let new_value: Value =
-        module.asc_get(module.takes_ptr_returns_ptr("value_from_string", module.asc_new(string)));
+    let new_value: Value = module
+        .try_asc_get(module.takes_ptr_returns_ptr("value_from_string", module.asc_new(string)))
+        .unwrap();
     assert_eq!(new_value, Value::from(string));
 
     // Value::Int
     let int = i32::min_value();
-    let new_value: Value =
-        module.asc_get(module.takes_val_returns_ptr("value_from_int", RuntimeValue::from(int)));
+    let new_value: Value = module
+        .try_asc_get(module.takes_val_returns_ptr("value_from_int", RuntimeValue::from(int)))
+        .unwrap();
     assert_eq!(new_value, Value::Int(int));
 
     // Value::Float
     let float: f32 = 3.14159001;
     let float_runtime = RuntimeValue::F32(F32::from_float(float));
-    let new_value: Value =
-        module.asc_get(module.takes_val_returns_ptr("value_from_float", float_runtime));
+    let new_value: Value = module
+        .try_asc_get(module.takes_val_returns_ptr("value_from_float", float_runtime))
+        .unwrap();
     assert_eq!(new_value, Value::Float(float));
 
     // Value::Bool
     let boolean = true;
-    let new_value: Value = module.asc_get(module.takes_val_returns_ptr(
-        "value_from_bool",
-        RuntimeValue::I32(if boolean { 1 } else { 0 }),
-    ));
+    let new_value: Value = module
+        .try_asc_get(module.takes_val_returns_ptr(
+            "value_from_bool",
+            RuntimeValue::I32(if boolean { 1 } else { 0 }),
+        ))
+        .unwrap();
     assert_eq!(new_value, Value::Bool(boolean));
 
     // Value::List
-    let new_value: Value = module.asc_get(
-        module
-            .module
-            .invoke_export(
-                "array_from_values",
-                &[RuntimeValue::from(module.asc_new(string)), float_runtime],
-                &mut NopExternals,
-            ).expect("call failed")
-            .expect("call returned nothing")
-            .try_into()
-            .expect("call did not return ptr"),
-    );
+    let new_value: Value = module
+        .try_asc_get(
+            module
+                .module
+                .invoke_export(
+                    "array_from_values",
+                    &[RuntimeValue::from(module.asc_new(string)), float_runtime],
+                    &mut NopExternals,
+                ).expect("call failed")
+                .expect("call returned nothing")
+                .try_into()
+                .expect("call did not return ptr"),
+        ).unwrap();
     assert_eq!(
         new_value,
         Value::List(vec![Value::from(string), Value::Float(float)])
@@ -405,8 +412,9 @@ fn abi_store_value() {
         Value::String("bar".to_owned()),
     ];
     let array_ptr = module.asc_new(array);
-    let new_value: Value =
-        module.asc_get(module.takes_ptr_returns_ptr("value_from_array", array_ptr));
+    let new_value: Value = module
+        .try_asc_get(module.takes_ptr_returns_ptr("value_from_array", array_ptr))
+        .unwrap();
     assert_eq!(
         new_value,
         Value::List(vec![
@@ -418,17 +426,26 @@ fn abi_store_value() {
     // Value::Bytes
     let bytes: &[u8] = &[0, 2, 5];
     let bytes_ptr: AscPtr<Bytes> = module.asc_new(bytes);
-    let new_value: Value =
-        module.asc_get(module.takes_ptr_returns_ptr("value_from_bytes", bytes_ptr));
+    let new_value: Value = module
+        .try_asc_get(module.takes_ptr_returns_ptr("value_from_bytes", bytes_ptr))
+        .unwrap();
     assert_eq!(new_value, Value::Bytes(bytes.into()));
 
     // Value::BigInt
     let bytes: &[u8] = &[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1];
     let bytes_ptr: AscPtr<Uint8Array> = module.asc_new(bytes);
-    let new_value: Value =
-        module.asc_get(module.takes_ptr_returns_ptr("value_from_bigint", bytes_ptr));
+    let new_value: Value = module
+        .try_asc_get(module.takes_ptr_returns_ptr("value_from_bigint", bytes_ptr))
+        .unwrap();
     assert_eq!(
         new_value,
         Value::BigInt(::graph::data::store::scalar::BigInt::from_unsigned_bytes_le(bytes))
     );
+
+    // Value::BigDecimal
+    let big_decimal =
+        Value::BigDecimal(::graph::data::store::scalar::BigDecimal::from_str("-0.0042").unwrap());
+    let value_ptr: AscPtr<AscEnum<StoreValueKind>> = module.asc_new(&big_decimal);
+    let new_value: Value = module.try_asc_get(value_ptr).unwrap();
+    assert_eq!(new_value, big_decimal);
 }
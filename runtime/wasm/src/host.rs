@@ -1,21 +1,21 @@
 use failure::*;
-use futures::sync::mpsc::{channel, Sender};
-use futures::sync::oneshot;
-use std::thread;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
 
 use graph::components::ethereum::*;
 use graph::components::store::Store;
 use graph::data::subgraph::{DataSource, Source};
-use graph::ethabi::LogParam;
 use graph::ethabi::RawLog;
 use graph::prelude::{
     MappingABI, RuntimeHost as RuntimeHostTrait, RuntimeHostBuilder as RuntimeHostBuilderTrait, *,
 };
 use graph::util;
 use graph::web3::types::{Log, Transaction};
+use num_cpus;
 
-use super::EventHandlerContext;
+use super::EthereumEvent;
 use module::{WasmiModule, WasmiModuleConfig};
+use pool::ModulePool;
 
 pub struct RuntimeHostConfig {
     subgraph_id: SubgraphId,
@@ -26,6 +26,7 @@ pub struct RuntimeHostBuilder<T, L, S> {
     ethereum_adapter: Arc<T>,
     link_resolver: Arc<L>,
     store: Arc<S>,
+    module_pool: Arc<ModulePool>,
 }
 
 impl<T, L, S> Clone for RuntimeHostBuilder<T, L, S>
@@ -39,6 +40,7 @@ where
             ethereum_adapter: self.ethereum_adapter.clone(),
             link_resolver: self.link_resolver.clone(),
             store: self.store.clone(),
+            module_pool: self.module_pool.clone(),
         }
     }
 }
@@ -50,10 +52,16 @@ where
     S: Store,
 {
     pub fn new(ethereum_adapter: Arc<T>, link_resolver: Arc<L>, store: Arc<S>) -> Self {
+        // The mappings of all subgraphs share the threads of the module pool
+        let mapping_threads = parse_env_var("GRAPH_MAPPING_THREADS")
+            .unwrap_or_else(|e| panic!("{}", e))
+            .unwrap_or_else(num_cpus::get);
+
         RuntimeHostBuilder {
             ethereum_adapter,
             link_resolver,
             store,
+            module_pool: Arc::new(ModulePool::new(mapping_threads)),
         }
     }
 }
@@ -77,6 +85,7 @@ where
             self.ethereum_adapter.clone(),
             self.link_resolver.clone(),
             self.store.clone(),
+            self.module_pool.clone(),
             RuntimeHostConfig {
                 subgraph_id,
                 data_source,
@@ -85,36 +94,24 @@ where
     }
 }
 
-type HandleEventResponse = Result<Vec<EntityOperation>, Error>;
-
-#[derive(Debug)]
-struct HandleEventRequest {
-    handler: MappingEventHandler,
-    logger: Logger,
-    block: Arc<EthereumBlock>,
-    transaction: Arc<Transaction>,
-    log: Arc<Log>,
-    params: Vec<LogParam>,
-    entity_operations: Vec<EntityOperation>,
-    result_sender: oneshot::Sender<HandleEventResponse>,
-}
-
 #[derive(Debug)]
 pub struct RuntimeHost {
     data_source_name: String,
     data_source_contract: Source,
     data_source_contract_abi: MappingABI,
     data_source_event_handlers: Vec<MappingEventHandler>,
-    handle_event_sender: Sender<HandleEventRequest>,
-    _guard: oneshot::Sender<()>,
+    module_pool: Arc<ModulePool>,
+    module_id: usize,
+    heap_size: Arc<AtomicUsize>,
 }
 
 impl RuntimeHost {
-    pub fn new<T, L, S>(
+    pub(crate) fn new<T, L, S>(
         logger: &Logger,
         ethereum_adapter: Arc<T>,
         link_resolver: Arc<L>,
         store: Arc<S>,
+        module_pool: Arc<ModulePool>,
         config: RuntimeHostConfig,
     ) -> Result<Self, Error>
     where
@@ -127,21 +124,6 @@ impl RuntimeHost {
             "data_source" => config.data_source.name.clone(),
         ));
 
-        // Create channel for canceling the module
-        let (cancel_sender, cancel_receiver) = oneshot::channel();
-
-        // Create channel for event handling requests
-        let (handle_event_sender, handle_event_receiver) = channel(100);
-
-        // wasmi modules are not `Send` therefore they cannot be scheduled by
-        // the regular tokio executor, so we create a dedicated thread.
-        //
-        // This thread can spawn tasks on the runtime by sending them to
-        // `task_receiver`.
-        let (task_sender, task_receiver) = channel(100);
-        tokio::spawn(task_receiver.for_each(tokio::spawn));
-
-        // Start the WASMI module runtime
         let module_logger = logger.clone();
         let data_source_name = config.data_source.name.clone();
         let data_source_contract = config.data_source.source.clone();
@@ -160,58 +142,33 @@ impl RuntimeHost {
                 )
             })?.clone();
 
-        thread::spawn(move || {
-            debug!(module_logger, "Start WASM runtime");
+        // Limit the gas and time a single handler may use, so that e.g. an
+        // infinite loop in a mapping fails the subgraph instead of hanging it
+        let gas_limit = parse_env_var("GRAPH_MAPPING_GAS_LIMIT")?.unwrap_or(10_000_000_000);
+        let handler_timeout = parse_env_var("GRAPH_MAPPING_HANDLER_TIMEOUT")?
+            .map(Duration::from_secs)
+            .unwrap_or(Duration::from_secs(600));
+
+        // Limit the memory of the module, so that a buggy mapping cannot
+        // exhaust the memory of the node
+        let max_memory = parse_env_var("GRAPH_MAPPING_MAX_MEMORY")?.unwrap_or(512 * 1024 * 1024);
+
+        // Load the mapping of the data source as a WASM module
+        let wasmi_config = WasmiModuleConfig {
+            subgraph_id: config.subgraph_id,
+            data_source: config.data_source,
+            ethereum_adapter,
+            link_resolver,
+            store,
+            gas_limit: Some(gas_limit),
+            handler_timeout: Some(handler_timeout),
+            max_memory: Some(max_memory),
+        };
 
-            // Load the mapping of the data source as a WASM module
-            let wasmi_config = WasmiModuleConfig {
-                subgraph_id: config.subgraph_id,
-                data_source: config.data_source,
-                ethereum_adapter: ethereum_adapter.clone(),
-                link_resolver: link_resolver.clone(),
-                store: store.clone(),
-            };
-
-            // Start the mapping as a WASM module
-            let mut module = WasmiModule::new(&module_logger, wasmi_config, task_sender);
-
-            // Pass incoming events to the WASM module and send entity changes back;
-            // stop when cancelled from the outside
-            handle_event_receiver
-                .map(Some)
-                .select(cancel_receiver.into_stream().map(|_| None).map_err(|_| ()))
-                .for_each(move |request: Option<HandleEventRequest>| {
-                    if let Some(request) = request {
-                        let HandleEventRequest {
-                            handler,
-                            logger,
-                            block,
-                            transaction,
-                            log,
-                            params,
-                            entity_operations,
-                            result_sender,
-                        } = request;
-
-                        let ctx = EventHandlerContext {
-                            logger,
-                            block,
-                            transaction,
-                            entity_operations,
-                        };
-
-                        let result = module.handle_ethereum_event(
-                            ctx,
-                            handler.handler.as_str(),
-                            log,
-                            params,
-                        );
-                        future::result(result_sender.send(result).map_err(|_| ()))
-                    } else {
-                        future::err(())
-                    }
-                }).wait()
-                .ok();
+        // Start the mapping as a WASM module on one of the threads of the pool
+        let module_id = module_pool.add(move || {
+            debug!(module_logger, "Start WASM runtime");
+            Box::new(WasmiModule::new(&module_logger, wasmi_config))
         });
 
         Ok(RuntimeHost {
@@ -219,8 +176,9 @@ impl RuntimeHost {
             data_source_contract,
             data_source_contract_abi,
             data_source_event_handlers,
-            handle_event_sender,
-            _guard: cancel_sender,
+            module_pool,
+            module_id,
+            heap_size: Arc::new(AtomicUsize::new(0)),
         })
     }
 
@@ -228,6 +186,13 @@ impl RuntimeHost {
         self.data_source_contract.address == log.address
     }
 
+    fn matches_log_block(&self, log: &Log) -> bool {
+        // Other data sources of the subgraph may start earlier than this one
+        log.block_number.map_or(true, |number| {
+            number >= self.data_source_contract.start_block.into()
+        })
+    }
+
     fn matches_log_signature(&self, log: &Log) -> bool {
         if log.topics.is_empty() {
             return false;
@@ -256,58 +221,43 @@ impl RuntimeHost {
                 )
             })
     }
-}
 
-impl RuntimeHostTrait for RuntimeHost {
-    fn matches_log(&self, log: &Log) -> bool {
-        self.matches_log_address(log) && self.matches_log_signature(log)
-    }
-
-    fn process_log(
+    /// Parses a log into an event for the matching event handler.
+    fn event_for_log(
         &self,
         logger: &Logger,
-        block: Arc<EthereumBlock>,
         transaction: Arc<Transaction>,
         log: Arc<Log>,
-        entity_operations: Vec<EntityOperation>,
-    ) -> Box<Future<Item = Vec<EntityOperation>, Error = Error> + Send> {
+    ) -> Result<EthereumEvent, Error> {
         // Identify event handler for this log
-        let event_handler = match self.event_handler_for_log(&log) {
-            Ok(handler) => handler,
-            Err(e) => return Box::new(future::err(e)),
-        };
+        let event_handler = self.event_handler_for_log(&log)?;
 
         // Identify the event ABI in the contract
-        let event_abi = match util::ethereum::contract_event_with_signature(
+        let event_abi = util::ethereum::contract_event_with_signature(
             &self.data_source_contract_abi.contract,
             event_handler.event.as_str(),
-        ) {
-            Some(event_abi) => event_abi,
-            None => {
-                return Box::new(future::err(format_err!(
-                    "Event with the signature \"{}\" not found in \
-                     contract \"{}\" of data source \"{}\"",
-                    event_handler.event,
-                    self.data_source_contract_abi.name,
-                    self.data_source_name
-                )))
-            }
-        };
+        ).ok_or_else(|| {
+            format_err!(
+                "Event with the signature \"{}\" not found in \
+                 contract \"{}\" of data source \"{}\"",
+                event_handler.event,
+                self.data_source_contract_abi.name,
+                self.data_source_name
+            )
+        })?;
 
         // Parse the log into an event
-        let params = match event_abi.parse_log(RawLog {
-            topics: log.topics.clone(),
-            data: log.data.clone().0,
-        }) {
-            Ok(log) => log.params,
-            Err(e) => {
-                return Box::new(future::err(format_err!(
+        let params = event_abi
+            .parse_log(RawLog {
+                topics: log.topics.clone(),
+                data: log.data.clone().0,
+            }).map_err(|e| {
+                format_err!(
                     "Failed to parse parameters of event: {}: {}",
                     event_handler.event,
                     e
-                )))
-            }
-        };
+                )
+            })?.params;
 
         debug!(
             logger, "Process Ethereum event";
@@ -315,38 +265,69 @@ impl RuntimeHostTrait for RuntimeHost {
             "handler" => &event_handler.handler
         );
 
-        // Call the event handler and asynchronously wait for the result
-        let (result_sender, result_receiver) = oneshot::channel();
+        Ok(EthereumEvent {
+            handler: event_handler.clone(),
+            transaction,
+            log,
+            params,
+        })
+    }
+}
+
+impl RuntimeHostTrait for RuntimeHost {
+    fn matches_log(&self, log: &Log) -> bool {
+        self.matches_log_address(log)
+            && self.matches_log_block(log)
+            && self.matches_log_signature(log)
+    }
+
+    fn heap_size(&self) -> usize {
+        self.heap_size.load(Ordering::SeqCst)
+    }
 
-        let before_event_signature = event_handler.event.clone();
-        let event_signature = event_handler.event.clone();
+    fn process_logs(
+        &self,
+        logger: &Logger,
+        block: Arc<EthereumBlock>,
+        logs: Vec<(Arc<Transaction>, Arc<Log>)>,
+        entity_operations: Vec<EntityOperation>,
+    ) -> Box<Future<Item = Vec<EntityOperation>, Error = Error> + Send> {
+        // Parse all logs into events up front
+        let events = match logs
+            .into_iter()
+            .map(|(transaction, log)| self.event_for_log(logger, transaction, log))
+            .collect::<Result<Vec<_>, _>>()
+        {
+            Ok(events) => events,
+            Err(e) => return Box::new(future::err(e)),
+        };
+
+        // Call the event handlers on the thread of the module and
+        // asynchronously wait for the result
+        let logger = logger.clone();
+        let heap_size = self.heap_size.clone();
+        let data_source_name = self.data_source_name.clone();
 
         Box::new(
-            self.handle_event_sender
-                .clone()
-                .send(HandleEventRequest {
-                    handler: event_handler.clone(),
-                    logger: logger.clone(),
-                    block: block.clone(),
-                    transaction: transaction.clone(),
-                    log: log.clone(),
-                    params,
-                    entity_operations,
-                    result_sender,
+            self.module_pool
+                .with_module(self.module_id, move |module| {
+                    let result =
+                        module.handle_ethereum_events(&logger, block, events, entity_operations);
+                    heap_size.store(module.heap_size(), Ordering::SeqCst);
+                    result
                 }).map_err(move |_| {
                     format_err!(
-                        "Mapping terminated before passing in Ethereum event: {}",
-                        before_event_signature
+                        "Mapping of data source \"{}\" terminated before finishing \
+                         to handle Ethereum events",
+                        data_source_name,
                     )
-                }).and_then(|_| {
-                    result_receiver.map_err(move |_| {
-                        format_err!(
-                            "Mapping terminated before finishing to handle \
-                             Ethereum event: {}",
-                            event_signature,
-                        )
-                    })
                 }).and_then(|result| result),
         )
     }
 }
+
+impl Drop for RuntimeHost {
+    fn drop(&mut self) {
+        self.module_pool.remove(self.module_id);
+    }
+}
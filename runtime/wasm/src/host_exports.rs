@@ -1,26 +1,36 @@
 use ethabi::Token;
-use futures::sync::oneshot;
 use graph::components::ethereum::*;
 use graph::components::store::EntityKey;
 use graph::data::store::scalar;
 use graph::data::subgraph::DataSource;
 use graph::prelude::*;
 use graph::serde_json;
+use graph::tokio::runtime::current_thread;
 use graph::web3::types::H160;
+use rlp;
+use secp256k1;
+use sha2::Digest;
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fmt;
 use std::mem;
 use std::ops::Deref;
 use std::str::FromStr;
+use std::time::Instant;
 use EventHandlerContext;
+use RlpValue;
 use UnresolvedContractCall;
 
+/// Lists nested deeper than this are rejected by `rlp.decode`, so that
+/// decoding and converting the result can't exhaust the stack.
+const MAX_RLP_DEPTH: usize = 64;
+
 pub(crate) trait ExportError: fmt::Debug + fmt::Display + Send + Sync + 'static {}
 impl<E> ExportError for E where E: fmt::Debug + fmt::Display + Send + Sync + 'static {}
 
 /// Error raised in host functions.
 #[derive(Debug)]
-pub(crate) struct HostExportError<E>(E);
+pub(crate) struct HostExportError<E>(pub(crate) E);
 
 impl<E: fmt::Display> fmt::Display for HostExportError<E> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -28,22 +38,28 @@ impl<E: fmt::Display> fmt::Display for HostExportError<E> {
     }
 }
 
-pub(crate) struct HostExports<E, L, S, U> {
+thread_local! {
+    /// Runs the futures of host exports that need the network on the mapping
+    /// thread itself, shared by all module instances on that thread.
+    static RUNTIME: RefCell<current_thread::Runtime> = RefCell::new(
+        current_thread::Runtime::new().expect("Failed to create runtime for host exports"),
+    );
+}
+
+pub(crate) struct HostExports<E, L, S> {
     subgraph_id: SubgraphId,
     data_source: DataSource,
     ethereum_adapter: Arc<E>,
     link_resolver: Arc<L>,
     store: Arc<S>,
-    task_sink: U,
     pub(crate) ctx: Option<EventHandlerContext>,
 }
 
-impl<E, L, S, U> HostExports<E, L, S, U>
+impl<E, L, S> HostExports<E, L, S>
 where
     E: EthereumAdapter,
     L: LinkResolver,
     S: Store + Send + Sync,
-    U: Sink<SinkItem = Box<Future<Item = (), Error = ()> + Send>> + Clone,
 {
     pub(crate) fn new(
         subgraph_id: SubgraphId,
@@ -51,7 +67,6 @@ where
         ethereum_adapter: Arc<E>,
         link_resolver: Arc<L>,
         store: Arc<S>,
-        task_sink: U,
         ctx: Option<EventHandlerContext>,
     ) -> Self {
         HostExports {
@@ -60,7 +75,6 @@ where
             ethereum_adapter,
             link_resolver,
             store,
-            task_sink,
             ctx,
         }
     }
@@ -206,6 +220,7 @@ where
     pub(crate) fn ethereum_call(
         &self,
         unresolved_call: UnresolvedContractCall,
+        deadline: Option<Instant>,
     ) -> Result<Vec<Token>, HostExportError<impl ExportError>> {
         let ctx = self.ctx.as_ref().expect("processing event without context");
 
@@ -246,16 +261,19 @@ where
             args: unresolved_call.function_args.clone(),
         };
 
-        // Run Ethereum call in tokio runtime
+        // Run Ethereum call on this thread's runtime
         let eth_adapter = self.ethereum_adapter.clone();
         let logger = ctx.logger.clone();
         self.block_on(future::lazy(move || {
-            eth_adapter.contract_call(&logger, call).map_err(move |e| {
-                HostExportError(format!(
-                    "Failed to call function \"{}\" of contract \"{}\": {}",
-                    unresolved_call.function_name, unresolved_call.contract_name, e
-                ))
-            })
+            with_deadline(
+                eth_adapter.contract_call(&logger, call).map_err(move |e| {
+                    format!(
+                        "Failed to call function \"{}\" of contract \"{}\": {}",
+                        unresolved_call.function_name, unresolved_call.contract_name, e
+                    )
+                }),
+                deadline,
+            )
         }))
     }
 
@@ -326,12 +344,12 @@ where
     pub(crate) fn ipfs_cat(
         &self,
         link: String,
+        deadline: Option<Instant>,
     ) -> Result<Vec<u8>, HostExportError<impl ExportError>> {
-        self.block_on(
-            self.link_resolver
-                .cat(&Link { link })
-                .map_err(HostExportError),
-        )
+        let link_resolver = self.link_resolver.clone();
+        self.block_on(future::lazy(move || {
+            with_deadline(link_resolver.cat(&Link { link }), deadline)
+        }))
     }
 
     /// Expects a decimal string.
@@ -375,6 +393,107 @@ where
         ::tiny_keccak::keccak256(&input)
     }
 
+    pub(crate) fn crypto_sha256(&self, input: Vec<u8>) -> Vec<u8> {
+        ::sha2::Sha256::digest(&input).to_vec()
+    }
+
+    pub(crate) fn crypto_ripemd160(&self, input: Vec<u8>) -> Vec<u8> {
+        ::ripemd160::Ripemd160::digest(&input).to_vec()
+    }
+
+    /// Recovers the address that signed `hash`. The signature is expected in
+    /// the 65 byte `r || s || v` form used by Ethereum, where `v` is either
+    /// 0/1 or 27/28. Returns `None` if no public key can be recovered.
+    pub(crate) fn crypto_ecrecover(
+        &self,
+        hash: Vec<u8>,
+        signature: Vec<u8>,
+    ) -> Result<Option<H160>, HostExportError<impl ExportError>> {
+        if hash.len() != 32 {
+            return Err(HostExportError(format!(
+                "Hash passed to ecrecover must be 32 bytes long, got {} bytes",
+                hash.len()
+            )));
+        }
+        if signature.len() != 65 {
+            return Err(HostExportError(format!(
+                "Signature passed to ecrecover must be 65 bytes long, got {} bytes",
+                signature.len()
+            )));
+        }
+
+        let v = match signature[64] {
+            v @ 0...1 => v,
+            v @ 27...28 => v - 27,
+            _ => return Ok(None),
+        };
+
+        let message = secp256k1::Message::parse_slice(&hash).unwrap();
+        let public_key = secp256k1::Signature::parse_slice(&signature[..64])
+            .and_then(|signature| {
+                secp256k1::RecoveryId::parse(v)
+                    .and_then(|id| secp256k1::recover(&message, &signature, &id))
+            }).ok();
+
+        // The address is the last 20 bytes of the Keccak-256 hash of the
+        // uncompressed public key, without its leading `0x04` tag byte.
+        Ok(public_key.map(|public_key| {
+            let hash = ::tiny_keccak::keccak256(&public_key.serialize()[1..]);
+            H160::from_slice(&hash[12..])
+        }))
+    }
+
+    pub(crate) fn rlp_decode(
+        &self,
+        bytes: Vec<u8>,
+    ) -> Result<RlpValue, HostExportError<impl ExportError>> {
+        fn decode(rlp: &rlp::Rlp, depth: usize) -> Result<RlpValue, String> {
+            if rlp.is_data() {
+                return Ok(RlpValue::Bytes(
+                    rlp.data().map_err(|e| e.to_string())?.to_vec(),
+                ));
+            }
+
+            if depth >= MAX_RLP_DEPTH {
+                return Err(format!(
+                    "lists are nested more than {} levels deep",
+                    MAX_RLP_DEPTH
+                ));
+            }
+
+            // `Rlp::iter` stops at the first malformed item, so make sure the
+            // items add up to the declared length of the list.
+            let payload_len = rlp.payload_info().map_err(|e| e.to_string())?.value_len;
+            let items = rlp.iter().collect::<Vec<_>>();
+            if items.iter().map(|item| item.as_raw().len()).sum::<usize>() != payload_len {
+                return Err(rlp::DecoderError::RlpInconsistentLengthAndData.to_string());
+            }
+
+            items
+                .iter()
+                .map(|item| decode(item, depth + 1))
+                .collect::<Result<_, _>>()
+                .map(RlpValue::List)
+        }
+
+        let rlp = rlp::Rlp::new(&bytes);
+        rlp.payload_info()
+            .map_err(|e| e.to_string())
+            .and_then(|payload_info| {
+                // Reject trailing bytes after the top-level item.
+                if payload_info.total() == bytes.len() {
+                    decode(&rlp, 0)
+                } else {
+                    Err(rlp::DecoderError::RlpIsTooBig.to_string())
+                }
+            }).map_err(|e| HostExportError(format!("Failed to decode RLP: {}", e)))
+    }
+
+    /// Encodes bytes using the Bitcoin base58 alphabet, as used by IPFS hashes.
+    pub(crate) fn bytes_to_base58(&self, bytes: Vec<u8>) -> String {
+        ::bs58::encode(&bytes).into_string()
+    }
+
     pub(crate) fn big_int_plus(&self, x: BigInt, y: BigInt) -> BigInt {
         x + y
     }
@@ -395,19 +514,106 @@ where
         x % y
     }
 
-    pub(crate) fn block_on<I: Send + 'static, ER: Send + 'static>(
+    pub(crate) fn big_decimal_plus(&self, x: BigDecimal, y: BigDecimal) -> BigDecimal {
+        x + y
+    }
+
+    pub(crate) fn big_decimal_minus(&self, x: BigDecimal, y: BigDecimal) -> BigDecimal {
+        x - y
+    }
+
+    pub(crate) fn big_decimal_times(&self, x: BigDecimal, y: BigDecimal) -> BigDecimal {
+        x * y
+    }
+
+    pub(crate) fn big_decimal_divided_by(
         &self,
-        future: impl Future<Item = I, Error = ER> + Send + 'static,
-    ) -> Result<I, ER> {
-        let (return_sender, return_receiver) = oneshot::channel();
-        self.task_sink
-            .clone()
-            .send(Box::new(future.then(|res| {
-                return_sender.send(res).map_err(|_| unreachable!())
-            }))).wait()
-            .map_err(|_| panic!("task receiver dropped"))
-            .unwrap();
-        return_receiver.wait().expect("`return_sender` dropped")
+        x: BigDecimal,
+        y: BigDecimal,
+    ) -> Result<BigDecimal, HostExportError<impl ExportError>> {
+        x.checked_div(y).map_err(|e| HostExportError(e.to_string()))
+    }
+
+    pub(crate) fn big_decimal_equals(&self, x: BigDecimal, y: BigDecimal) -> bool {
+        x == y
+    }
+
+    pub(crate) fn big_decimal_to_string(&self, x: BigDecimal) -> String {
+        x.to_string()
+    }
+
+    pub(crate) fn big_decimal_from_string(
+        &self,
+        s: String,
+    ) -> Result<BigDecimal, HostExportError<impl ExportError>> {
+        BigDecimal::from_str(&s)
+            .map_err(|e| HostExportError(format!("Failed to parse BigDecimal `{}`: {}", s, e)))
+    }
+
+    pub(crate) fn log_log(
+        &self,
+        level: i32,
+        msg: String,
+    ) -> Result<(), HostExportError<impl ExportError>> {
+        let logger = &self
+            .ctx
+            .as_ref()
+            .expect("logging outside of an event handler")
+            .logger;
+        match level {
+            0 => crit!(logger, "{}", msg),
+            1 => error!(logger, "{}", msg),
+            2 => warn!(logger, "{}", msg),
+            3 => info!(logger, "{}", msg),
+            4 => debug!(logger, "{}", msg),
+            _ => return Err(HostExportError(format!("Invalid log level {}", level))),
+        }
+        Ok(())
+    }
+
+    pub(crate) fn data_source_address(&self) -> H160 {
+        self.data_source.source.address.clone()
+    }
+
+    pub(crate) fn data_source_name(&self) -> String {
+        self.data_source.name.clone()
+    }
+
+    pub(crate) fn data_source_network(&self) -> Option<String> {
+        self.data_source.network.clone()
+    }
+
+    pub(crate) fn subgraph_id(&self) -> SubgraphId {
+        self.subgraph_id.clone()
+    }
+
+    /// Runs `future` to completion on this thread's runtime.
+    fn block_on<I, ER>(&self, future: impl Future<Item = I, Error = ER>) -> Result<I, ER> {
+        RUNTIME.with(|runtime| runtime.borrow_mut().block_on(future))
+    }
+}
+
+/// Fails `future` if it does not complete before `deadline`, so that host
+/// exports blocking on it cannot run past the handler timeout.
+fn with_deadline<I, E>(
+    future: impl Future<Item = I, Error = E> + Send + 'static,
+    deadline: Option<Instant>,
+) -> impl Future<Item = I, Error = HostExportError<String>> + Send + 'static
+where
+    I: Send + 'static,
+    E: fmt::Display + Send + 'static,
+{
+    match deadline {
+        Some(deadline) => future::Either::A(future.deadline(deadline).map_err(|e| {
+            if e.is_elapsed() {
+                HostExportError("Handler timed out".to_owned())
+            } else if e.is_timer() {
+                HostExportError(format!("Timer error: {}", e.into_timer().unwrap()))
+            } else {
+                HostExportError(e.into_inner().unwrap().to_string())
+            }
+        })),
+        None => future::Either::B(future.map_err(|e| HostExportError(e.to_string()))),
     }
 }
 
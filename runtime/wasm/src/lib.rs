@@ -1,3 +1,4 @@
+extern crate bs58;
 extern crate ethabi;
 #[macro_use]
 extern crate failure;
@@ -5,6 +6,13 @@ extern crate futures;
 extern crate graph;
 extern crate hex;
 extern crate nan_preserving_float;
+extern crate num_cpus;
+extern crate parity_wasm;
+extern crate pwasm_utils;
+extern crate ripemd160;
+extern crate rlp;
+extern crate secp256k1;
+extern crate sha2;
 extern crate tiny_keccak;
 extern crate uuid;
 extern crate wasmi;
@@ -12,13 +20,14 @@ extern crate wasmi;
 mod asc_abi;
 mod host;
 mod module;
+mod pool;
 mod to_from;
 
 /// Runtime-agnostic implementation of exports to WASM.
 mod host_exports;
 
 use self::graph::prelude::*;
-use self::graph::web3::types::{Address, Transaction};
+use self::graph::web3::types::{Address, Log, Transaction};
 
 pub use self::host::{RuntimeHost, RuntimeHostBuilder, RuntimeHostConfig};
 
@@ -30,6 +39,22 @@ pub(crate) struct UnresolvedContractCall {
     pub function_args: Vec<ethabi::Token>,
 }
 
+/// A decoded RLP item.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum RlpValue {
+    Bytes(Vec<u8>),
+    List(Vec<RlpValue>),
+}
+
+/// An Ethereum event parsed for the event handler that will handle it.
+#[derive(Debug)]
+pub(crate) struct EthereumEvent {
+    handler: MappingEventHandler,
+    transaction: Arc<Transaction>,
+    log: Arc<Log>,
+    params: Vec<ethabi::LogParam>,
+}
+
 #[derive(Debug)]
 pub(crate) struct EventHandlerContext {
     logger: Logger,
@@ -1,21 +1,29 @@
 use failure::Error as FailureError;
 use nan_preserving_float::F64;
+use parity_wasm::elements::{MemoryType, Module as ParityModule};
+use pwasm_utils;
+use std::cell::Cell;
+use std::cmp;
 use std::fmt;
 use std::ops::Deref;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
 
 use wasmi::{
     Error, Externals, FuncInstance, FuncRef, HostError, ImportsBuilder, MemoryRef, Module,
-    ModuleImportResolver, ModuleInstance, ModuleRef, NopExternals, RuntimeArgs, RuntimeValue,
-    Signature, Trap, ValueType,
+    ModuleImportResolver, ModuleInstance, ModuleRef, RuntimeArgs, RuntimeValue, Signature, Trap,
+    TrapKind, ValueType,
 };
 
 use graph::components::ethereum::*;
+use graph::components::metrics::HANDLER_EXECUTION_DURATION;
 use graph::data::subgraph::DataSource;
 use graph::ethabi::LogParam;
 use graph::prelude::*;
-use graph::web3::types::{Log, U256};
+use graph::web3::types::{Log, H256, U256};
 use host_exports;
-use EventHandlerContext;
+use pool::PooledModule;
+use {EthereumEvent, EventHandlerContext};
 
 use asc_abi::asc_ptr::*;
 use asc_abi::class::*;
@@ -24,28 +32,185 @@ use asc_abi::*;
 #[cfg(test)]
 mod test;
 
+/// Size of a page of WASM linear memory in bytes.
+const WASM_PAGE_SIZE: usize = 65536;
+
+/// Error raised when a mapping handler exceeds one of its execution limits.
+#[derive(Debug, Fail)]
+enum ExecutionLimitError {
+    #[fail(display = "exceeded the gas limit of {}", _0)]
+    GasLimitExceeded(u64),
+    #[fail(display = "timed out after {:?}", _0)]
+    Timeout(Duration),
+    #[fail(display = "exceeded the memory limit of {} bytes", _0)]
+    MemoryLimitExceeded(usize),
+}
+
+impl HostError for ExecutionLimitError {}
+
+/// Meters the gas used by the handler that is currently running and enforces
+/// the gas limit and timeout of the module.
+///
+/// Gas is charged by calls to `env.gas` that are injected into the module when
+/// it is loaded, so the wall-clock timeout is checked at the same points. Host
+/// exports that block on the network are bounded by the timeout separately.
+struct GasMeter {
+    gas_limit: Option<u64>,
+    timeout: Option<Duration>,
+    gas_used: Cell<u64>,
+    start_time: Cell<Instant>,
+}
+
+impl GasMeter {
+    fn new(gas_limit: Option<u64>, timeout: Option<Duration>) -> Self {
+        GasMeter {
+            gas_limit,
+            timeout,
+            gas_used: Cell::new(0),
+            start_time: Cell::new(Instant::now()),
+        }
+    }
+
+    /// Starts metering a new handler invocation.
+    fn reset(&self) {
+        self.gas_used.set(0);
+        self.start_time.set(Instant::now());
+    }
+
+    /// When the handler that is currently running times out.
+    fn deadline(&self) -> Option<Instant> {
+        self.timeout.map(|timeout| self.start_time.get() + timeout)
+    }
+
+    fn charge(&self, amount: u64) {
+        self.gas_used.set(self.gas_used.get().saturating_add(amount));
+    }
+
+    fn check_limits(&self) -> Result<(), Trap> {
+        match self.gas_limit {
+            Some(gas_limit) if self.gas_used.get() > gas_limit => {
+                return Err(ExecutionLimitError::GasLimitExceeded(gas_limit).into())
+            }
+            _ => (),
+        }
+
+        match self.timeout {
+            Some(timeout) if self.start_time.get().elapsed() > timeout => {
+                Err(ExecutionLimitError::Timeout(timeout).into())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Externals for running the module's memory allocator, which may only call
+/// the injected gas function.
+///
+/// Allocations made on behalf of host functions must not fail, so the gas used
+/// by them is only charged here; exceeded limits trap as soon as the mapping
+/// runs again.
+struct AllocatorExternals<'a>(&'a GasMeter);
+
+impl<'a> Externals for AllocatorExternals<'a> {
+    fn invoke_index(
+        &mut self,
+        index: usize,
+        args: RuntimeArgs,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+        match index {
+            GAS_FUNC_INDEX => {
+                self.0.charge(args.nth_checked::<u32>(0)? as u64);
+                Ok(None)
+            }
+            _ => Err(TrapKind::Unreachable.into()),
+        }
+    }
+}
+
 /// AssemblyScript-compatible WASM memory heap.
+///
+/// Allocations that would exceed the memory limit are rejected. Since
+/// `asc_new` cannot fail, they result in a null pointer and the failure is
+/// reported by `check_memory_limit` once the host export returns.
 #[derive(Clone)]
 struct WasmiAscHeap {
     module: ModuleRef,
     memory: MemoryRef,
+    max_memory: Option<usize>,
+    gas_meter: Rc<GasMeter>,
+    allocation_failed: Rc<Cell<bool>>,
 }
 
 impl WasmiAscHeap {
-    pub fn new(module: ModuleRef, memory: MemoryRef) -> Self {
-        WasmiAscHeap { module, memory }
+    pub fn new(
+        module: ModuleRef,
+        max_memory: Option<usize>,
+        gas_meter: Rc<GasMeter>,
+        allocation_failed: Rc<Cell<bool>>,
+    ) -> Self {
+        // Provide access to the WASM runtime linear memory
+        let memory = module
+            .export_by_name("memory")
+            .expect("Failed to find memory export in the WASM module")
+            .as_memory()
+            .expect("Export \"memory\" has an invalid type")
+            .clone();
+
+        WasmiAscHeap {
+            module,
+            memory,
+            max_memory,
+            gas_meter,
+            allocation_failed,
+        }
+    }
+
+    /// Size of the linear memory in bytes.
+    pub fn size(&self) -> usize {
+        self.memory.current_size().0 * WASM_PAGE_SIZE
+    }
+
+    /// Whether the memory cannot grow by another page within the memory limit.
+    fn memory_exhausted(&self) -> bool {
+        self.max_memory.map_or(false, |max_memory| {
+            self.size() + WASM_PAGE_SIZE > max_memory
+        })
+    }
+
+    fn check_memory_limit(&self) -> Result<(), Trap> {
+        match self.max_memory {
+            Some(max_memory) if self.allocation_failed.get() || self.size() > max_memory => {
+                Err(ExecutionLimitError::MemoryLimitExceeded(max_memory).into())
+            }
+            _ => Ok(()),
+        }
     }
 }
 
 impl AscHeap for WasmiAscHeap {
     fn raw_new(&self, bytes: &[u8]) -> Result<u32, Error> {
-        let address = self
-            .module
-            .invoke_export(
-                "allocate_memory",
-                &[RuntimeValue::I32(bytes.len() as i32)],
-                &mut NopExternals,
-            ).expect("Failed to invoke memory allocation function")
+        match self.max_memory {
+            Some(max_memory) if self.allocation_failed.get() || bytes.len() > max_memory => {
+                self.allocation_failed.set(true);
+                return Ok(0);
+            }
+            _ => (),
+        }
+
+        // The allocator traps if it cannot grow the memory, which is capped
+        // at the memory limit
+        let address = match self.module.invoke_export(
+            "allocate_memory",
+            &[RuntimeValue::I32(bytes.len() as i32)],
+            &mut AllocatorExternals(&self.gas_meter),
+        ) {
+            Err(_) if self.max_memory.is_some() => {
+                self.allocation_failed.set(true);
+                return Ok(0);
+            }
+            result => result.expect("Failed to invoke memory allocation function"),
+        };
+        let address = address
             .expect("Function did not return a value")
             .try_into::<u32>()
             .expect("Function did not return u32");
@@ -85,6 +250,24 @@ const BIG_INT_MINUS: usize = 20;
 const BIG_INT_TIMES: usize = 21;
 const BIG_INT_DIVIDED_BY: usize = 22;
 const BIG_INT_MOD: usize = 23;
+const GAS_FUNC_INDEX: usize = 24;
+const CRYPTO_SHA256_INDEX: usize = 25;
+const CRYPTO_RIPEMD160_INDEX: usize = 26;
+const CRYPTO_ECRECOVER_INDEX: usize = 27;
+const RLP_DECODE_FUNC_INDEX: usize = 28;
+const TYPE_CONVERSION_BYTES_TO_BASE58_FUNC_INDEX: usize = 29;
+const BIG_DECIMAL_PLUS: usize = 30;
+const BIG_DECIMAL_MINUS: usize = 31;
+const BIG_DECIMAL_TIMES: usize = 32;
+const BIG_DECIMAL_DIVIDED_BY: usize = 33;
+const BIG_DECIMAL_EQUALS: usize = 34;
+const BIG_DECIMAL_TO_STRING: usize = 35;
+const BIG_DECIMAL_FROM_STRING: usize = 36;
+const LOG_FUNC_INDEX: usize = 37;
+const DATA_SOURCE_ADDRESS_FUNC_INDEX: usize = 38;
+const DATA_SOURCE_NAME_FUNC_INDEX: usize = 39;
+const DATA_SOURCE_NETWORK_FUNC_INDEX: usize = 40;
+const DATA_SOURCE_SUBGRAPH_ID_FUNC_INDEX: usize = 41;
 
 pub struct WasmiModuleConfig<T, L, S> {
     pub subgraph_id: SubgraphId,
@@ -92,61 +275,108 @@ pub struct WasmiModuleConfig<T, L, S> {
     pub ethereum_adapter: Arc<T>,
     pub link_resolver: Arc<L>,
     pub store: Arc<S>,
+    /// Maximum amount of gas (roughly, WASM instructions) a single handler may use.
+    pub gas_limit: Option<u64>,
+    /// Maximum wall-clock time a single handler may run for.
+    pub handler_timeout: Option<Duration>,
+    /// Maximum size of the module's linear memory in bytes.
+    pub max_memory: Option<usize>,
+}
+
+/// The memory and globals of a module instance right after it was started.
+struct ModuleSnapshot {
+    memory: Vec<u8>,
+    globals: Vec<RuntimeValue>,
+}
+
+impl ModuleSnapshot {
+    fn take(module: &ModuleRef, heap: &WasmiAscHeap) -> Self {
+        ModuleSnapshot {
+            memory: heap
+                .memory
+                .get(0, heap.size())
+                .expect("Failed to read WASM module memory"),
+            globals: module.globals().iter().map(|global| global.get()).collect(),
+        }
+    }
+
+    /// Restores the state of the module instance the snapshot was taken of.
+    fn restore(&self, module: &ModuleRef, heap: &WasmiAscHeap) {
+        heap.memory
+            .set(0, &self.memory)
+            .expect("Failed to restore WASM module memory");
+
+        for (global, value) in module.globals().iter().zip(self.globals.iter()) {
+            if global.is_mutable() {
+                global
+                    .set(*value)
+                    .expect("Failed to restore WASM module global");
+            }
+        }
+    }
 }
 
 /// A WASM module based on wasmi that powers a subgraph runtime.
-pub struct WasmiModule<T, L, S, U> {
+pub struct WasmiModule<T, L, S> {
     pub logger: Logger,
     pub module: ModuleRef,
-    externals: HostExternals<T, L, S, U>,
+    valid_module: Module,
+    externals: HostExternals<T, L, S>,
     heap: WasmiAscHeap,
+    snapshot: ModuleSnapshot,
+    /// The block the module has last handled events for.
+    current_block: Option<H256>,
 }
 
-impl<T, L, S, U> WasmiModule<T, L, S, U>
+impl<T, L, S> WasmiModule<T, L, S>
 where
     T: EthereumAdapter,
     L: LinkResolver,
     S: Store + Send + Sync + 'static,
-    U: Sink<SinkItem = Box<Future<Item = (), Error = ()> + Send>> + Clone + 'static,
 {
     /// Creates a new wasmi module
-    pub fn new(logger: &Logger, config: WasmiModuleConfig<T, L, S>, task_sink: U) -> Self {
+    pub fn new(logger: &Logger, config: WasmiModuleConfig<T, L, S>) -> Self {
         let logger = logger.new(o!("component" => "WasmiModule"));
 
-        let module = Module::from_parity_wasm_module(config.data_source.mapping.runtime.clone())
-            .unwrap_or_else(|_| {
-                panic!(
-                    "Wasmi could not interpret module of data source: {}",
-                    config.data_source.name
-                )
-            });
-
-        // Build import resolver
-        let mut imports = ImportsBuilder::new();
-        imports.push_resolver("env", &EnvModuleResolver);
-        imports.push_resolver("store", &StoreModuleResolver);
-        imports.push_resolver("ethereum", &EthereumModuleResolver);
-        imports.push_resolver("typeConversion", &TypeConversionModuleResolver);
-        imports.push_resolver("json", &JsonModuleResolver);
-        imports.push_resolver("ipfs", &IpfsModuleResolver);
-        imports.push_resolver("crypto", &CryptoModuleResolver);
-        imports.push_resolver("bigInt", &BigIntModuleResolver);
+        // Instrument the module with calls to `env.gas` for metering
+        let mut runtime = pwasm_utils::inject_gas_counter(
+            config.data_source.mapping.runtime.clone(),
+            &Default::default(),
+        ).unwrap_or_else(|_| {
+            panic!(
+                "Failed to inject gas counter into module of data source: {}",
+                config.data_source.name
+            )
+        });
+
+        // Keep the linear memory from growing beyond the memory limit
+        if let Some(max_memory) = config.max_memory {
+            limit_memory_pages(&mut runtime, (max_memory / WASM_PAGE_SIZE) as u32);
+        }
+
+        // Validate the module once; it is instantiated again whenever the
+        // module is reset
+        let valid_module = Module::from_parity_wasm_module(runtime).unwrap_or_else(|_| {
+            panic!(
+                "Wasmi could not interpret module of data source: {}",
+                config.data_source.name
+            )
+        });
 
         // Instantiate the runtime module using hosted functions and import resolver
-        let module =
-            ModuleInstance::new(&module, &imports).expect("Failed to instantiate WASM module");
+        let module = ModuleInstance::new(&valid_module, &imports())
+            .expect("Failed to instantiate WASM module");
 
-        // Provide access to the WASM runtime linear memory
-        let not_started_module = module.not_started_instance().clone();
-        let memory = not_started_module
-            .export_by_name("memory")
-            .expect("Failed to find memory export in the WASM module")
-            .as_memory()
-            .expect("Export \"memory\" has an invalid type")
-            .clone();
+        // Meter the gas used by the module, shared by the heap and the host functions
+        let gas_meter = Rc::new(GasMeter::new(config.gas_limit, config.handler_timeout));
 
         // Create a AssemblyScript-compatible WASM memory heap
-        let heap = WasmiAscHeap::new(not_started_module, memory);
+        let heap = WasmiAscHeap::new(
+            module.not_started_instance().clone(),
+            config.max_memory,
+            gas_meter,
+            Rc::new(Cell::new(false)),
+        );
 
         // Create new instance of externally hosted functions invoker
         let mut externals = HostExternals {
@@ -157,7 +387,6 @@ where
                 config.ethereum_adapter.clone(),
                 config.link_resolver.clone(),
                 config.store.clone(),
-                task_sink,
                 None,
             ),
         };
@@ -165,15 +394,94 @@ where
         let module = module
             .run_start(&mut externals)
             .expect("Failed to start WASM module instance");
+        let snapshot = ModuleSnapshot::take(&module, &heap);
 
         WasmiModule {
             logger,
+            valid_module,
             module,
             externals,
             heap,
+            snapshot,
+            current_block: None,
         }
     }
 
+    /// Restores the memory and globals the module instance had right after it
+    /// was started, discarding the state left behind by previous handlers.
+    pub fn reset(&mut self) {
+        // Linear memory cannot shrink, so if the handlers grew it, the only
+        // way back is a fresh instance
+        if self.heap.size() == self.snapshot.memory.len() {
+            self.snapshot.restore(&self.module, &self.heap);
+        } else {
+            self.reinstantiate();
+        }
+    }
+
+    /// Replaces the module instance with a fresh one.
+    pub(crate) fn reinstantiate(&mut self) {
+        let module = ModuleInstance::new(&self.valid_module, &imports())
+            .expect("Failed to instantiate WASM module");
+
+        self.heap = WasmiAscHeap::new(
+            module.not_started_instance().clone(),
+            self.heap.max_memory,
+            self.heap.gas_meter.clone(),
+            self.heap.allocation_failed.clone(),
+        );
+        self.externals.heap = self.heap.clone();
+
+        self.module = module
+            .run_start(&mut self.externals)
+            .expect("Failed to start WASM module instance");
+        self.snapshot = ModuleSnapshot::take(&self.module, &self.heap);
+    }
+
+    /// Size of the module's linear memory in bytes.
+    pub fn heap_size(&self) -> usize {
+        self.heap.size()
+    }
+
+    /// Handles a batch of events from the same block, one after the other,
+    /// passing in the entity operations collected by the previous events.
+    ///
+    /// The module is reset whenever it moves on to a new block, so that state
+    /// left behind by one block cannot leak into the next.
+    pub(crate) fn handle_ethereum_events(
+        &mut self,
+        logger: &Logger,
+        block: Arc<EthereumBlock>,
+        events: Vec<EthereumEvent>,
+        entity_operations: Vec<EntityOperation>,
+    ) -> Result<Vec<EntityOperation>, FailureError> {
+        let block_hash = EthereumBlockPointer::from(&*block).hash;
+        if self.current_block.map_or(false, |hash| hash != block_hash) {
+            self.reset();
+        }
+        self.current_block = Some(block_hash);
+
+        events
+            .into_iter()
+            .fold(Ok(entity_operations), |result, event| {
+                result.and_then(|entity_operations| {
+                    let ctx = EventHandlerContext {
+                        logger: logger.clone(),
+                        block: block.clone(),
+                        transaction: event.transaction,
+                        entity_operations,
+                    };
+
+                    self.handle_ethereum_event(
+                        ctx,
+                        event.handler.handler.as_str(),
+                        event.log,
+                        event.params,
+                    )
+                })
+            })
+    }
+
     pub(crate) fn handle_ethereum_event(
         &mut self,
         ctx: EventHandlerContext,
@@ -181,7 +489,16 @@ where
         log: Arc<Log>,
         params: Vec<LogParam>,
     ) -> Result<Vec<EntityOperation>, FailureError> {
-        self.externals.host_exports.ctx = Some(ctx);
+        let block_ptr = EthereumBlockPointer::from(&*ctx.block);
+
+        // Tag everything the mapping logs with where it happened
+        let logger = ctx.logger.new(o!(
+            "block_number" => block_ptr.number,
+            "block_hash" => block_ptr.hash_hex(),
+            "transaction" => format!("{:x}", ctx.transaction.hash),
+            "handler" => handler_name.to_owned(),
+        ));
+        self.externals.host_exports.ctx = Some(EventHandlerContext { logger, ..ctx });
 
         // Prepare an EthereumEvent for the WASM runtime
         let event = EthereumEventData {
@@ -211,12 +528,32 @@ where
             params,
         };
 
-        // Invoke the event handler
-        let result = self.module.invoke_export(
-            handler_name,
-            &[RuntimeValue::from(self.heap.asc_new(&event))],
-            &mut self.externals,
-        );
+        // Invoke the event handler with a fresh gas budget
+        self.heap.gas_meter.reset();
+        self.heap.allocation_failed.set(false);
+        let subgraph_id = self.externals.host_exports.subgraph_id();
+        let timer = HANDLER_EXECUTION_DURATION
+            .with_label_values(&[subgraph_id.as_str(), handler_name])
+            .start_timer();
+        let event_ptr = self.heap.asc_new(&event);
+        let result = match self.heap.check_memory_limit() {
+            Ok(()) => self.module.invoke_export(
+                handler_name,
+                &[RuntimeValue::from(event_ptr)],
+                &mut self.externals,
+            ),
+            Err(trap) => Err(Error::Trap(trap)),
+        };
+        timer.observe_duration();
+
+        // The allocator traps when the memory cannot grow any further
+        let memory_limit_exceeded = match (&result, self.heap.max_memory) {
+            (Err(Error::Trap(trap)), Some(max_memory)) => match *trap.kind() {
+                TrapKind::Unreachable if self.heap.memory_exhausted() => Some(max_memory),
+                _ => None,
+            },
+            _ => None,
+        };
 
         // Return either the collected entity operations or an error
         result
@@ -228,32 +565,81 @@ where
                     .expect("processing event without context")
                     .entity_operations
             }).map_err(|e| {
+                // Report exceeded execution limits without the trap noise
+                let reason = match e
+                    .as_host_error()
+                    .and_then(|e| e.downcast_ref::<ExecutionLimitError>())
+                {
+                    Some(e) => e.to_string(),
+                    None => match memory_limit_exceeded {
+                        Some(max_memory) => {
+                            ExecutionLimitError::MemoryLimitExceeded(max_memory).to_string()
+                        }
+                        None => e.to_string(),
+                    },
+                };
+
                 format_err!(
-                    "Failed to handle Ethereum event with handler \"{}\": {}",
+                    "Failed to handle Ethereum event with handler \"{}\" in block #{} ({}): {}",
                     handler_name,
-                    e
+                    block_ptr.number,
+                    block_ptr.hash_hex(),
+                    reason
                 )
             })
     }
 }
 
+impl<T, L, S> PooledModule for WasmiModule<T, L, S>
+where
+    T: EthereumAdapter,
+    L: LinkResolver,
+    S: Store + Send + Sync + 'static,
+{
+    fn handle_ethereum_events(
+        &mut self,
+        logger: &Logger,
+        block: Arc<EthereumBlock>,
+        events: Vec<EthereumEvent>,
+        entity_operations: Vec<EntityOperation>,
+    ) -> Result<Vec<EntityOperation>, FailureError> {
+        WasmiModule::handle_ethereum_events(self, logger, block, events, entity_operations)
+    }
+
+    fn heap_size(&self) -> usize {
+        WasmiModule::heap_size(self)
+    }
+}
+
 impl<E> HostError for host_exports::HostExportError<E> where
     E: fmt::Debug + fmt::Display + Send + Sync + 'static
 {}
 
 /// Hosted functions for external use by wasm module
-pub struct HostExternals<T, L, S, U> {
+pub struct HostExternals<T, L, S> {
     heap: WasmiAscHeap,
-    host_exports: host_exports::HostExports<T, L, S, U>,
+    host_exports: host_exports::HostExports<T, L, S>,
 }
 
-impl<T, L, S, U> HostExternals<T, L, S, U>
+impl<T, L, S> HostExternals<T, L, S>
 where
     T: EthereumAdapter,
     L: LinkResolver,
     S: Store + Send + Sync + 'static,
-    U: Sink<SinkItem = Box<Future<Item = (), Error = ()> + Send>> + Clone + 'static,
 {
+    /// Reads an Asc object that may not hold a valid value of `T`, failing the
+    /// host call if it doesn't.
+    fn try_asc_get<T, C>(&self, asc_ptr: AscPtr<C>) -> Result<T, Trap>
+    where
+        C: AscType,
+        T: TryFromAscObj<C>,
+    {
+        Ok(self
+            .heap
+            .try_asc_get(asc_ptr)
+            .map_err(host_exports::HostExportError)?)
+    }
+
     /// function abort(message?: string | null, fileName?: string | null, lineNumber?: u32, columnNumber?: u32): void
     /// Always returns a trap.
     fn abort(
@@ -296,7 +682,7 @@ where
         self.host_exports.store_set(
             self.heap.asc_get(entity_ptr),
             self.heap.asc_get(id_ptr),
-            self.heap.asc_get(data_ptr),
+            self.try_asc_get(data_ptr)?,
         )?;
         Ok(None)
     }
@@ -333,10 +719,13 @@ where
         &self,
         call_ptr: AscPtr<AscUnresolvedContractCall>,
     ) -> Result<Option<RuntimeValue>, Trap> {
+        self.heap.gas_meter.check_limits()?;
         let result = self
             .host_exports
-            .ethereum_call(self.heap.asc_get(call_ptr))?;
-        Ok(Some(RuntimeValue::from(self.heap.asc_new(&*result))))
+            .ethereum_call(self.heap.asc_get(call_ptr), self.heap.gas_meter.deadline());
+        // Fail with the timeout error if the call ran into it
+        self.heap.gas_meter.check_limits()?;
+        Ok(Some(RuntimeValue::from(self.heap.asc_new(&*result?))))
     }
 
     /// function typeConversion.bytesToString(bytes: Bytes): string
@@ -403,8 +792,13 @@ where
 
     /// function ipfs.cat(link: String): Bytes
     fn ipfs_cat(&self, link_ptr: AscPtr<AscString>) -> Result<Option<RuntimeValue>, Trap> {
-        let bytes = self.host_exports.ipfs_cat(self.heap.asc_get(link_ptr))?;
-        let bytes_obj: AscPtr<Uint8Array> = self.heap.asc_new(&*bytes);
+        self.heap.gas_meter.check_limits()?;
+        let bytes = self
+            .host_exports
+            .ipfs_cat(self.heap.asc_get(link_ptr), self.heap.gas_meter.deadline());
+        // Fail with the timeout error if the request ran into it
+        self.heap.gas_meter.check_limits()?;
+        let bytes_obj: AscPtr<Uint8Array> = self.heap.asc_new(&*bytes?);
         Ok(Some(RuntimeValue::from(bytes_obj)))
     }
 
@@ -451,6 +845,63 @@ where
         Ok(Some(RuntimeValue::from(hash_ptr)))
     }
 
+    /// function crypto.sha256(input: Bytes): Bytes
+    fn crypto_sha256(&self, input_ptr: AscPtr<Uint8Array>) -> Result<Option<RuntimeValue>, Trap> {
+        let hash = self
+            .host_exports
+            .crypto_sha256(self.heap.asc_get(input_ptr));
+        let hash_ptr: AscPtr<Uint8Array> = self.heap.asc_new(&*hash);
+        Ok(Some(RuntimeValue::from(hash_ptr)))
+    }
+
+    /// function crypto.ripemd160(input: Bytes): Bytes
+    fn crypto_ripemd160(
+        &self,
+        input_ptr: AscPtr<Uint8Array>,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+        let hash = self
+            .host_exports
+            .crypto_ripemd160(self.heap.asc_get(input_ptr));
+        let hash_ptr: AscPtr<Uint8Array> = self.heap.asc_new(&*hash);
+        Ok(Some(RuntimeValue::from(hash_ptr)))
+    }
+
+    /// Returns null if no address can be recovered from the signature.
+    /// function crypto.ecrecover(hash: Bytes, signature: Bytes): Address | null
+    fn crypto_ecrecover(
+        &self,
+        hash_ptr: AscPtr<Uint8Array>,
+        signature_ptr: AscPtr<Uint8Array>,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+        let address = self.host_exports.crypto_ecrecover(
+            self.heap.asc_get(hash_ptr),
+            self.heap.asc_get(signature_ptr),
+        )?;
+
+        Ok(Some(match address {
+            Some(address) => {
+                let address_ptr: AscPtr<AscAddress> = self.heap.asc_new(&address);
+                RuntimeValue::from(address_ptr)
+            }
+            None => RuntimeValue::from(0),
+        }))
+    }
+
+    /// function rlp.decode(bytes: Bytes): RLPValue
+    fn rlp_decode(&self, bytes_ptr: AscPtr<Uint8Array>) -> Result<Option<RuntimeValue>, Trap> {
+        let value = self.host_exports.rlp_decode(self.heap.asc_get(bytes_ptr))?;
+        let value_ptr: AscPtr<AscEnum<RlpValueKind>> = self.heap.asc_new(&value);
+        Ok(Some(RuntimeValue::from(value_ptr)))
+    }
+
+    /// function typeConversion.bytesToBase58(bytes: Bytes): string
+    fn bytes_to_base58(&self, bytes_ptr: AscPtr<Uint8Array>) -> Result<Option<RuntimeValue>, Trap> {
+        let result = self
+            .host_exports
+            .bytes_to_base58(self.heap.asc_get(bytes_ptr));
+        Ok(Some(RuntimeValue::from(self.heap.asc_new(&result))))
+    }
+
     /// function bigInt.plus(x: BigInt, y: BigInt): BigInt
     fn big_int_plus(
         &self,
@@ -515,21 +966,152 @@ where
         let result_ptr: AscPtr<AscBigInt> = self.heap.asc_new(&result);
         Ok(Some(RuntimeValue::from(result_ptr)))
     }
+
+    /// function bigDecimal.plus(x: BigDecimal, y: BigDecimal): BigDecimal
+    fn big_decimal_plus(
+        &self,
+        x_ptr: AscPtr<AscBigDecimal>,
+        y_ptr: AscPtr<AscBigDecimal>,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+        let result = self
+            .host_exports
+            .big_decimal_plus(self.try_asc_get(x_ptr)?, self.try_asc_get(y_ptr)?);
+        let result_ptr: AscPtr<AscBigDecimal> = self.heap.asc_new(&result);
+        Ok(Some(RuntimeValue::from(result_ptr)))
+    }
+
+    /// function bigDecimal.minus(x: BigDecimal, y: BigDecimal): BigDecimal
+    fn big_decimal_minus(
+        &self,
+        x_ptr: AscPtr<AscBigDecimal>,
+        y_ptr: AscPtr<AscBigDecimal>,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+        let result = self
+            .host_exports
+            .big_decimal_minus(self.try_asc_get(x_ptr)?, self.try_asc_get(y_ptr)?);
+        let result_ptr: AscPtr<AscBigDecimal> = self.heap.asc_new(&result);
+        Ok(Some(RuntimeValue::from(result_ptr)))
+    }
+
+    /// function bigDecimal.times(x: BigDecimal, y: BigDecimal): BigDecimal
+    fn big_decimal_times(
+        &self,
+        x_ptr: AscPtr<AscBigDecimal>,
+        y_ptr: AscPtr<AscBigDecimal>,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+        let result = self
+            .host_exports
+            .big_decimal_times(self.try_asc_get(x_ptr)?, self.try_asc_get(y_ptr)?);
+        let result_ptr: AscPtr<AscBigDecimal> = self.heap.asc_new(&result);
+        Ok(Some(RuntimeValue::from(result_ptr)))
+    }
+
+    /// function bigDecimal.dividedBy(x: BigDecimal, y: BigDecimal): BigDecimal
+    fn big_decimal_divided_by(
+        &self,
+        x_ptr: AscPtr<AscBigDecimal>,
+        y_ptr: AscPtr<AscBigDecimal>,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+        let result = self
+            .host_exports
+            .big_decimal_divided_by(self.try_asc_get(x_ptr)?, self.try_asc_get(y_ptr)?)?;
+        let result_ptr: AscPtr<AscBigDecimal> = self.heap.asc_new(&result);
+        Ok(Some(RuntimeValue::from(result_ptr)))
+    }
+
+    /// function bigDecimal.equals(x: BigDecimal, y: BigDecimal): bool
+    fn big_decimal_equals(
+        &self,
+        x_ptr: AscPtr<AscBigDecimal>,
+        y_ptr: AscPtr<AscBigDecimal>,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+        let equal = self
+            .host_exports
+            .big_decimal_equals(self.try_asc_get(x_ptr)?, self.try_asc_get(y_ptr)?);
+        Ok(Some(RuntimeValue::I32(equal as i32)))
+    }
+
+    /// function bigDecimal.toString(x: BigDecimal): string
+    fn big_decimal_to_string(
+        &self,
+        big_decimal_ptr: AscPtr<AscBigDecimal>,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+        let result = self
+            .host_exports
+            .big_decimal_to_string(self.try_asc_get(big_decimal_ptr)?);
+        Ok(Some(RuntimeValue::from(self.heap.asc_new(&result))))
+    }
+
+    /// function bigDecimal.fromString(x: string): BigDecimal
+    fn big_decimal_from_string(
+        &self,
+        string_ptr: AscPtr<AscString>,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+        let result = self
+            .host_exports
+            .big_decimal_from_string(self.heap.asc_get(string_ptr))?;
+        let result_ptr: AscPtr<AscBigDecimal> = self.heap.asc_new(&result);
+        Ok(Some(RuntimeValue::from(result_ptr)))
+    }
+
+    /// function log.log(level: i32, msg: string): void
+    fn log_log(&self, level: i32, msg: AscPtr<AscString>) -> Result<Option<RuntimeValue>, Trap> {
+        self.host_exports.log_log(level, self.heap.asc_get(msg))?;
+        Ok(None)
+    }
+
+    /// function dataSource.address(): Address
+    fn data_source_address(&self) -> Result<Option<RuntimeValue>, Trap> {
+        let address_ptr: AscPtr<AscAddress> =
+            self.heap.asc_new(&self.host_exports.data_source_address());
+        Ok(Some(RuntimeValue::from(address_ptr)))
+    }
+
+    /// function dataSource.name(): string
+    fn data_source_name(&self) -> Result<Option<RuntimeValue>, Trap> {
+        let name_ptr: AscPtr<AscString> = self.heap.asc_new(&self.host_exports.data_source_name());
+        Ok(Some(RuntimeValue::from(name_ptr)))
+    }
+
+    /// function dataSource.network(): string | null
+    fn data_source_network(&self) -> Result<Option<RuntimeValue>, Trap> {
+        Ok(Some(match self.host_exports.data_source_network() {
+            Some(network) => {
+                let network_ptr: AscPtr<AscString> = self.heap.asc_new(&network);
+                RuntimeValue::from(network_ptr)
+            }
+            None => RuntimeValue::from(0),
+        }))
+    }
+
+    /// function dataSource.subgraphId(): string
+    fn data_source_subgraph_id(&self) -> Result<Option<RuntimeValue>, Trap> {
+        let id_ptr: AscPtr<AscString> = self.heap.asc_new(&self.host_exports.subgraph_id());
+        Ok(Some(RuntimeValue::from(id_ptr)))
+    }
+
+    /// Injected into the module when it is loaded; not callable from mappings.
+    /// function env.gas(amount: u32): void
+    fn gas(&self, amount: u32) -> Result<Option<RuntimeValue>, Trap> {
+        self.heap.gas_meter.charge(amount as u64);
+        self.heap.gas_meter.check_limits()?;
+        self.heap.check_memory_limit()?;
+        Ok(None)
+    }
 }
 
-impl<T, L, S, U> Externals for HostExternals<T, L, S, U>
+impl<T, L, S> Externals for HostExternals<T, L, S>
 where
     T: EthereumAdapter,
     L: LinkResolver,
     S: Store + Send + Sync + 'static,
-    U: Sink<SinkItem = Box<Future<Item = (), Error = ()> + Send>> + Clone + 'static,
 {
     fn invoke_index(
         &mut self,
         index: usize,
         args: RuntimeArgs,
     ) -> Result<Option<RuntimeValue>, Trap> {
-        match index {
+        let result = match index {
             ABORT_FUNC_INDEX => self.abort(
                 args.nth_checked(0)?,
                 args.nth_checked(1)?,
@@ -571,11 +1153,75 @@ where
                 self.big_int_divided_by(args.nth_checked(0)?, args.nth_checked(1)?)
             }
             BIG_INT_MOD => self.big_int_mod(args.nth_checked(0)?, args.nth_checked(1)?),
+            GAS_FUNC_INDEX => self.gas(args.nth_checked(0)?),
+            CRYPTO_SHA256_INDEX => self.crypto_sha256(args.nth_checked(0)?),
+            CRYPTO_RIPEMD160_INDEX => self.crypto_ripemd160(args.nth_checked(0)?),
+            CRYPTO_ECRECOVER_INDEX => {
+                self.crypto_ecrecover(args.nth_checked(0)?, args.nth_checked(1)?)
+            }
+            RLP_DECODE_FUNC_INDEX => self.rlp_decode(args.nth_checked(0)?),
+            TYPE_CONVERSION_BYTES_TO_BASE58_FUNC_INDEX => {
+                self.bytes_to_base58(args.nth_checked(0)?)
+            }
+            BIG_DECIMAL_PLUS => self.big_decimal_plus(args.nth_checked(0)?, args.nth_checked(1)?),
+            BIG_DECIMAL_MINUS => self.big_decimal_minus(args.nth_checked(0)?, args.nth_checked(1)?),
+            BIG_DECIMAL_TIMES => self.big_decimal_times(args.nth_checked(0)?, args.nth_checked(1)?),
+            BIG_DECIMAL_DIVIDED_BY => {
+                self.big_decimal_divided_by(args.nth_checked(0)?, args.nth_checked(1)?)
+            }
+            BIG_DECIMAL_EQUALS => {
+                self.big_decimal_equals(args.nth_checked(0)?, args.nth_checked(1)?)
+            }
+            BIG_DECIMAL_TO_STRING => self.big_decimal_to_string(args.nth_checked(0)?),
+            BIG_DECIMAL_FROM_STRING => self.big_decimal_from_string(args.nth_checked(0)?),
+            LOG_FUNC_INDEX => self.log_log(args.nth_checked(0)?, args.nth_checked(1)?),
+            DATA_SOURCE_ADDRESS_FUNC_INDEX => self.data_source_address(),
+            DATA_SOURCE_NAME_FUNC_INDEX => self.data_source_name(),
+            DATA_SOURCE_NETWORK_FUNC_INDEX => self.data_source_network(),
+            DATA_SOURCE_SUBGRAPH_ID_FUNC_INDEX => self.data_source_subgraph_id(),
             _ => panic!("Unimplemented function at {}", index),
+        };
+
+        // Allocating the return value may have run into the memory limit
+        self.heap.check_memory_limit()?;
+        result
+    }
+}
+
+/// Caps the number of pages the linear memory defined by `module` may grow to.
+fn limit_memory_pages(module: &mut ParityModule, max_pages: u32) {
+    if let Some(section) = module.memory_section_mut() {
+        for memory in section.entries_mut().iter_mut() {
+            let initial = memory.limits().initial();
+            let maximum = memory
+                .limits()
+                .maximum()
+                .map_or(max_pages, |maximum| cmp::min(maximum, max_pages));
+
+            // The module could not be instantiated with a maximum below its
+            // initial size; it fails on the memory limit once it runs instead
+            *memory = MemoryType::new(initial, Some(cmp::max(initial, maximum)));
         }
     }
 }
 
+/// Resolves the host functions imported by mappings.
+fn imports() -> ImportsBuilder<'static> {
+    ImportsBuilder::new()
+        .with_resolver("env", &EnvModuleResolver)
+        .with_resolver("store", &StoreModuleResolver)
+        .with_resolver("ethereum", &EthereumModuleResolver)
+        .with_resolver("typeConversion", &TypeConversionModuleResolver)
+        .with_resolver("json", &JsonModuleResolver)
+        .with_resolver("ipfs", &IpfsModuleResolver)
+        .with_resolver("crypto", &CryptoModuleResolver)
+        .with_resolver("bigInt", &BigIntModuleResolver)
+        .with_resolver("bigDecimal", &BigDecimalModuleResolver)
+        .with_resolver("rlp", &RlpModuleResolver)
+        .with_resolver("log", &LogModuleResolver)
+        .with_resolver("dataSource", &DataSourceModuleResolver)
+}
+
 /// Env module resolver
 pub struct EnvModuleResolver;
 
@@ -594,6 +1240,10 @@ impl ModuleImportResolver for EnvModuleResolver {
                 ),
                 ABORT_FUNC_INDEX,
             ),
+            "gas" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32][..], None),
+                GAS_FUNC_INDEX,
+            ),
             _ => {
                 return Err(Error::Instantiation(format!(
                     "Export '{}' not found",
@@ -666,6 +1316,10 @@ impl ModuleImportResolver for TypeConversionModuleResolver {
                 Signature::new(&[ValueType::I32][..], Some(ValueType::I32)),
                 TYPE_CONVERSION_BYTES_TO_HEX_FUNC_INDEX,
             ),
+            "bytesToBase58" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32][..], Some(ValueType::I32)),
+                TYPE_CONVERSION_BYTES_TO_BASE58_FUNC_INDEX,
+            ),
             "bigIntToString" => FuncInstance::alloc_host(
                 Signature::new(&[ValueType::I32][..], Some(ValueType::I32)),
                 TYPE_CONVERSION_BIG_INT_TO_STRING_FUNC_INDEX,
@@ -759,6 +1413,37 @@ impl ModuleImportResolver for CryptoModuleResolver {
                 Signature::new(&[ValueType::I32][..], Some(ValueType::I32)),
                 CRYPTO_KECCAK_256_INDEX,
             ),
+            "sha256" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32][..], Some(ValueType::I32)),
+                CRYPTO_SHA256_INDEX,
+            ),
+            "ripemd160" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32][..], Some(ValueType::I32)),
+                CRYPTO_RIPEMD160_INDEX,
+            ),
+            "ecrecover" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32][..], Some(ValueType::I32)),
+                CRYPTO_ECRECOVER_INDEX,
+            ),
+            _ => {
+                return Err(Error::Instantiation(format!(
+                    "Export '{}' not found",
+                    field_name
+                )))
+            }
+        })
+    }
+}
+
+struct RlpModuleResolver;
+
+impl ModuleImportResolver for RlpModuleResolver {
+    fn resolve_func(&self, field_name: &str, _signature: &Signature) -> Result<FuncRef, Error> {
+        Ok(match field_name {
+            "decode" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32][..], Some(ValueType::I32)),
+                RLP_DECODE_FUNC_INDEX,
+            ),
             _ => {
                 return Err(Error::Instantiation(format!(
                     "Export '{}' not found",
@@ -803,3 +1488,96 @@ impl ModuleImportResolver for BigIntModuleResolver {
         })
     }
 }
+
+struct BigDecimalModuleResolver;
+
+impl ModuleImportResolver for BigDecimalModuleResolver {
+    fn resolve_func(&self, field_name: &str, _signature: &Signature) -> Result<FuncRef, Error> {
+        Ok(match field_name {
+            "plus" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32][..], Some(ValueType::I32)),
+                BIG_DECIMAL_PLUS,
+            ),
+            "minus" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32][..], Some(ValueType::I32)),
+                BIG_DECIMAL_MINUS,
+            ),
+            "times" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32][..], Some(ValueType::I32)),
+                BIG_DECIMAL_TIMES,
+            ),
+            "dividedBy" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32][..], Some(ValueType::I32)),
+                BIG_DECIMAL_DIVIDED_BY,
+            ),
+            "equals" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32][..], Some(ValueType::I32)),
+                BIG_DECIMAL_EQUALS,
+            ),
+            "toString" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32][..], Some(ValueType::I32)),
+                BIG_DECIMAL_TO_STRING,
+            ),
+            "fromString" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32][..], Some(ValueType::I32)),
+                BIG_DECIMAL_FROM_STRING,
+            ),
+            _ => {
+                return Err(Error::Instantiation(format!(
+                    "Export '{}' not found",
+                    field_name
+                )))
+            }
+        })
+    }
+}
+
+struct LogModuleResolver;
+
+impl ModuleImportResolver for LogModuleResolver {
+    fn resolve_func(&self, field_name: &str, _signature: &Signature) -> Result<FuncRef, Error> {
+        Ok(match field_name {
+            "log" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32][..], None),
+                LOG_FUNC_INDEX,
+            ),
+            _ => {
+                return Err(Error::Instantiation(format!(
+                    "Export '{}' not found",
+                    field_name
+                )))
+            }
+        })
+    }
+}
+
+struct DataSourceModuleResolver;
+
+impl ModuleImportResolver for DataSourceModuleResolver {
+    fn resolve_func(&self, field_name: &str, _signature: &Signature) -> Result<FuncRef, Error> {
+        Ok(match field_name {
+            "address" => FuncInstance::alloc_host(
+                Signature::new(&[][..], Some(ValueType::I32)),
+                DATA_SOURCE_ADDRESS_FUNC_INDEX,
+            ),
+            "name" => FuncInstance::alloc_host(
+                Signature::new(&[][..], Some(ValueType::I32)),
+                DATA_SOURCE_NAME_FUNC_INDEX,
+            ),
+            "network" => FuncInstance::alloc_host(
+                Signature::new(&[][..], Some(ValueType::I32)),
+                DATA_SOURCE_NETWORK_FUNC_INDEX,
+            ),
+            "subgraphId" => FuncInstance::alloc_host(
+                Signature::new(&[][..], Some(ValueType::I32)),
+                DATA_SOURCE_SUBGRAPH_ID_FUNC_INDEX,
+            ),
+            _ => {
+                return Err(Error::Instantiation(format!(
+                    "Export '{}' not found",
+                    field_name
+                )))
+            }
+        })
+    }
+}
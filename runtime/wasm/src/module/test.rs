@@ -6,7 +6,6 @@ extern crate parity_wasm;
 use self::graph_mock::FakeStore;
 use ethabi::{LogParam, Token};
 use failure::Error;
-use futures::sync::mpsc::{channel, Sender};
 use graph::components::ethereum::*;
 use graph::components::store::*;
 use graph::components::subgraph::*;
@@ -16,9 +15,13 @@ use graph::util;
 use graph::web3::types::{Bytes, *};
 use hex;
 use std::collections::HashMap;
+use std::fmt::{self, Write};
 use std::io::Cursor;
 use std::iter::FromIterator;
 use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use RlpValue;
 
 use super::*;
 
@@ -76,20 +79,20 @@ impl EthereumAdapter for MockEthereumAdapter {
     }
 }
 
-fn test_module(
+type TestModule = WasmiModule<MockEthereumAdapter, ipfs_api::IpfsClient, FakeStore>;
+
+fn test_module(data_source: DataSource) -> TestModule {
+    test_module_with_limits(data_source, None, None, None)
+}
+
+fn test_module_with_limits(
     data_source: DataSource,
-) -> (WasmiModule<
-    MockEthereumAdapter,
-    ipfs_api::IpfsClient,
-    FakeStore,
-    Sender<Box<Future<Item = (), Error = ()> + Send>>,
->) {
+    gas_limit: Option<u64>,
+    handler_timeout: Option<Duration>,
+    max_memory: Option<usize>,
+) -> TestModule {
     let logger = slog::Logger::root(slog::Discard, o!());
     let mock_ethereum_adapter = Arc::new(MockEthereumAdapter::default());
-    let (task_sender, task_receiver) = channel(100);
-    let mut runtime = tokio::runtime::Runtime::new().unwrap();
-    runtime.spawn(task_receiver.for_each(tokio::spawn));
-    ::std::mem::forget(runtime);
     WasmiModule::new(
         &logger,
         WasmiModuleConfig {
@@ -98,8 +101,10 @@ fn test_module(
             ethereum_adapter: mock_ethereum_adapter,
             link_resolver: Arc::new(ipfs_api::IpfsClient::default()),
             store: Arc::new(FakeStore),
+            gas_limit,
+            handler_timeout,
+            max_memory,
         },
-        task_sender,
     )
 }
 
@@ -113,6 +118,7 @@ fn mock_data_source(path: &str) -> DataSource {
         source: Source {
             address: Address::from_str("0123123123012312312301231231230123123123").unwrap(),
             abi: String::from("123123"),
+            start_block: 0,
         },
         mapping: Mapping {
             kind: String::from("ethereum/events"),
@@ -391,6 +397,188 @@ fn crypto_keccak256() {
     );
 }
 
+#[test]
+fn crypto_sha256_and_ripemd160() {
+    let mut module = test_module(mock_data_source("wasm_test/crypto_encoding.wasm"));
+    let input: &[u8] = "eth".as_ref();
+
+    let input_ptr: AscPtr<Uint8Array> = module.heap.asc_new(input);
+    let hash: AscPtr<Uint8Array> =
+        module.takes_val_returns_ptr("sha256", RuntimeValue::from(input_ptr));
+    let hash: Vec<u8> = module.heap.asc_get(hash);
+    assert_eq!(
+        hex::encode(hash),
+        "c69ea13227bace6e1f8a06364d93f4a6f04632432b64ca9b1fc4036baea4d34c"
+    );
+
+    let input_ptr: AscPtr<Uint8Array> = module.heap.asc_new(input);
+    let hash: AscPtr<Uint8Array> =
+        module.takes_val_returns_ptr("ripemd160", RuntimeValue::from(input_ptr));
+    let hash: Vec<u8> = module.heap.asc_get(hash);
+    assert_eq!(
+        hex::encode(hash),
+        "71ccfeb69d983a6af967c8afe14eeb420fa68f52"
+    );
+}
+
+#[test]
+fn crypto_ecrecover() {
+    let mut module = test_module(mock_data_source("wasm_test/crypto_encoding.wasm"));
+
+    // keccak256("graph"), signed by the private key
+    // 0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318.
+    let hash =
+        hex::decode("a0ed527785244825d33465d49867f1f6bf4936894648395a2a06d501921477b4").unwrap();
+    let mut signature = hex::decode(
+        "bb50e2d89a4ed70663d080659fe0ad4b9bc3e06c17a227433966cb59ceee020d\
+         3810691e07c1b569cdbafbce72c228726918a419bb5fd5c8124462a8dd052d221b",
+    ).unwrap();
+
+    let ecrecover = |module: &mut TestModule, hash: &[u8], signature: &[u8]| {
+        let hash_ptr: AscPtr<Uint8Array> = module.heap.asc_new(hash);
+        let signature_ptr: AscPtr<Uint8Array> = module.heap.asc_new(signature);
+        module.module.invoke_export(
+            "ecrecover",
+            &[
+                RuntimeValue::from(hash_ptr),
+                RuntimeValue::from(signature_ptr),
+            ],
+            &mut module.externals,
+        )
+    };
+
+    let address: AscPtr<AscAddress> = ecrecover(&mut module, &hash, &signature)
+        .expect("call failed")
+        .expect("call returned nothing")
+        .try_into()
+        .expect("call did not return pointer");
+    let address: H160 = module.heap.asc_get(address);
+    assert_eq!(
+        address,
+        H160::from_str("2c7536e3605d9c16a7a3d7b1898e529396a65c23").unwrap()
+    );
+
+    // A recovery ID of 0 is equivalent to 27.
+    signature[64] = 0;
+    let address: AscPtr<AscAddress> = ecrecover(&mut module, &hash, &signature)
+        .expect("call failed")
+        .expect("call returned nothing")
+        .try_into()
+        .expect("call did not return pointer");
+    let address: H160 = module.heap.asc_get(address);
+    assert_eq!(
+        address,
+        H160::from_str("2c7536e3605d9c16a7a3d7b1898e529396a65c23").unwrap()
+    );
+
+    // An invalid recovery ID returns null.
+    signature[64] = 29;
+    let address = ecrecover(&mut module, &hash, &signature)
+        .expect("call failed")
+        .expect("call returned nothing");
+    assert_eq!(address, RuntimeValue::from(0));
+
+    // A signature of the wrong length is an error.
+    let err = ecrecover(&mut module, &hash, &signature[..64]).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Signature passed to ecrecover must be 65 bytes long, got 64 bytes"));
+}
+
+#[test]
+fn rlp_decode() {
+    let mut module = test_module(mock_data_source("wasm_test/crypto_encoding.wasm"));
+
+    let decode = |module: &mut TestModule, bytes: &str| {
+        let bytes_ptr: AscPtr<Uint8Array> = module.heap.asc_new(&*hex::decode(bytes).unwrap());
+        module
+            .module
+            .invoke_export(
+                "decodeRlp",
+                &[RuntimeValue::from(bytes_ptr)],
+                &mut module.externals,
+            ).map(|value| {
+                let ptr: AscPtr<AscEnum<RlpValueKind>> = value
+                    .expect("call returned nothing")
+                    .try_into()
+                    .expect("call did not return pointer");
+                module.heap.asc_get::<RlpValue, _>(ptr)
+            })
+    };
+
+    // A single byte below 0x80 is its own encoding.
+    assert_eq!(
+        decode(&mut module, "0f").unwrap(),
+        RlpValue::Bytes(vec![0x0f])
+    );
+
+    // ["cat", "dog"]
+    assert_eq!(
+        decode(&mut module, "c88363617483646f67").unwrap(),
+        RlpValue::List(vec![
+            RlpValue::Bytes(b"cat".to_vec()),
+            RlpValue::Bytes(b"dog".to_vec()),
+        ])
+    );
+
+    // [ [], [[]], [ [], [[]] ] ]
+    assert_eq!(
+        decode(&mut module, "c7c0c1c0c3c0c1c0").unwrap(),
+        RlpValue::List(vec![
+            RlpValue::List(vec![]),
+            RlpValue::List(vec![RlpValue::List(vec![])]),
+            RlpValue::List(vec![
+                RlpValue::List(vec![]),
+                RlpValue::List(vec![RlpValue::List(vec![])]),
+            ]),
+        ])
+    );
+
+    // Truncated input, trailing bytes and empty input are errors.
+    for bytes in &["c88363617483646f", "c88363617483646f6700", ""] {
+        let err = decode(&mut module, bytes).unwrap_err();
+        assert!(err.to_string().contains("Failed to decode RLP"));
+    }
+
+    // Lists may be nested 64 levels deep, but not deeper.
+    let nested = |depth: usize| {
+        let mut bytes = String::from("c0");
+        for _ in 1..depth {
+            let len = bytes.len() / 2;
+            bytes = if len < 56 {
+                format!("{:02x}{}", 0xc0 + len, bytes)
+            } else {
+                format!("f8{:02x}{}", len, bytes)
+            };
+        }
+        bytes
+    };
+    assert!(decode(&mut module, &nested(64)).is_ok());
+    let err = decode(&mut module, &nested(65)).unwrap_err();
+    assert!(err.to_string().contains("nested more than 64 levels deep"));
+}
+
+#[test]
+fn bytes_to_base58() {
+    let mut module = test_module(mock_data_source("wasm_test/crypto_encoding.wasm"));
+
+    let to_base58 = |module: &mut TestModule, bytes: &[u8]| -> String {
+        let bytes_ptr: AscPtr<Uint8Array> = module.heap.asc_new(bytes);
+        let string_ptr: AscPtr<AscString> =
+            module.takes_val_returns_ptr("toBase58", RuntimeValue::from(bytes_ptr));
+        module.heap.asc_get(string_ptr)
+    };
+
+    assert_eq!(to_base58(&mut module, b""), "");
+    assert_eq!(to_base58(&mut module, b"hello world"), "StV1DL6CwTryKyV");
+
+    // Leading zero bytes are encoded as leading `1`s.
+    assert_eq!(
+        to_base58(&mut module, &hex::decode("0000287fb4cd").unwrap()),
+        "11233QC4"
+    );
+}
+
 #[test]
 fn token_numeric_conversion() {
     let mut module = test_module(mock_data_source("wasm_test/token_to_numeric.wasm"));
@@ -598,6 +786,105 @@ fn big_int_arithmetic() {
     assert_eq!(result, BigInt::from(1));
 }
 
+#[test]
+fn big_decimal_arithmetic() {
+    let mut module = test_module(mock_data_source("wasm_test/big_decimal.wasm"));
+
+    let binary_op = |module: &mut TestModule, op: &str, x: &str, y: &str| {
+        let x = BigDecimal::from_str(x).unwrap();
+        let x: AscPtr<AscBigDecimal> = module.heap.asc_new(&x);
+        let y = BigDecimal::from_str(y).unwrap();
+        let y: AscPtr<AscBigDecimal> = module.heap.asc_new(&y);
+        module.module.invoke_export(
+            op,
+            &[RuntimeValue::from(x), RuntimeValue::from(y)],
+            &mut module.externals,
+        )
+    };
+    let decimal_op = |module: &mut TestModule, op: &str, x: &str, y: &str| -> String {
+        let result_ptr: AscPtr<AscBigDecimal> = binary_op(module, op, x, y)
+            .expect("call failed")
+            .expect("call returned nothing")
+            .try_into()
+            .expect("call did not return pointer");
+        let result: BigDecimal = module.heap.try_asc_get(result_ptr).unwrap();
+        result.to_string()
+    };
+
+    assert_eq!(decimal_op(&mut module, "plus", "0.1", "0.2"), "0.3");
+    assert_eq!(decimal_op(&mut module, "minus", "5", "10.5"), "-5.5");
+    assert_eq!(decimal_op(&mut module, "times", "-2.5", "0.4"), "-1");
+    assert_eq!(decimal_op(&mut module, "dividedBy", "1", "8"), "0.125");
+    assert_eq!(
+        decimal_op(&mut module, "times", "1e18", "0.000000000000000123"),
+        "123"
+    );
+
+    // Equality ignores trailing zeros
+    let equal = binary_op(&mut module, "equals", "1.50", "1.5")
+        .expect("call failed")
+        .expect("call returned nothing");
+    assert_eq!(equal, RuntimeValue::I32(1));
+    let equal = binary_op(&mut module, "equals", "1.5", "-1.5")
+        .expect("call failed")
+        .expect("call returned nothing");
+    assert_eq!(equal, RuntimeValue::I32(0));
+
+    // Division by zero is an error
+    let err = binary_op(&mut module, "dividedBy", "1", "0").unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Attempted to divide BigDecimal `1` by zero"));
+
+    // Conversion from and to strings
+    let string_ptr: AscPtr<AscString> = module.heap.asc_new("-0012.3400");
+    let big_decimal_ptr: AscPtr<AscBigDecimal> =
+        module.takes_val_returns_ptr("bigDecimalFromString", RuntimeValue::from(string_ptr));
+    let string_ptr: AscPtr<AscString> =
+        module.takes_val_returns_ptr("bigDecimalToString", RuntimeValue::from(big_decimal_ptr));
+    let string: String = module.heap.asc_get(string_ptr);
+    assert_eq!(string, "-12.34");
+
+    let string_ptr: AscPtr<AscString> = module.heap.asc_new("not a number");
+    let err = module
+        .module
+        .invoke_export(
+            "bigDecimalFromString",
+            &[RuntimeValue::from(string_ptr)],
+            &mut module.externals,
+        ).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Failed to parse BigDecimal `not a number`"));
+
+    // Exponents are limited, whether parsed or passed in by the mapping
+    let string_ptr: AscPtr<AscString> = module.heap.asc_new("1e100000000");
+    let err = module
+        .module
+        .invoke_export(
+            "bigDecimalFromString",
+            &[RuntimeValue::from(string_ptr)],
+            &mut module.externals,
+        ).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("exponent 100000000 is outside the range -6144 to 6144"));
+
+    let big_decimal_ptr: AscPtr<AscBigDecimal> = module
+        .heap
+        .asc_new(&BigDecimal::new(BigInt::from(1), 100_000_000));
+    let err = module
+        .module
+        .invoke_export(
+            "bigDecimalToString",
+            &[RuntimeValue::from(big_decimal_ptr)],
+            &mut module.externals,
+        ).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("BigDecimal exponent 100000000 is outside the range -6144 to 6144"));
+}
+
 #[test]
 fn abort() {
     let mut module = test_module(mock_data_source("wasm_test/abort.wasm"));
@@ -607,3 +894,336 @@ fn abort() {
         .unwrap_err();
     assert_eq!(err.to_string(), "Trap: Trap { kind: Host(HostExportError(\"Mapping aborted at abort.ts, line 1, column 1, with message: aborted\")) }");
 }
+
+#[test]
+fn infinite_loop_exceeds_gas_limit() {
+    let mut module = test_module_with_limits(
+        mock_data_source("wasm_test/infinite_loop.wasm"),
+        Some(1_000_000),
+        None,
+        None,
+    );
+
+    let (block, transaction, log) = generate_fake_block();
+
+    let ctx = EventHandlerContext {
+        logger: Logger::root(slog::Discard, o!()),
+        block: Arc::new(block),
+        transaction: Arc::new(transaction),
+        entity_operations: vec![],
+    };
+
+    let err = module
+        .handle_ethereum_event(ctx, "loop", Arc::new(log), vec![])
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .starts_with("Failed to handle Ethereum event with handler \"loop\" in block #5")
+    );
+    assert!(err.to_string().ends_with("exceeded the gas limit of 1000000"));
+}
+
+#[test]
+fn infinite_loop_times_out() {
+    let mut module = test_module_with_limits(
+        mock_data_source("wasm_test/infinite_loop.wasm"),
+        None,
+        Some(Duration::from_millis(100)),
+        None,
+    );
+
+    let (block, transaction, log) = generate_fake_block();
+
+    let ctx = EventHandlerContext {
+        logger: Logger::root(slog::Discard, o!()),
+        block: Arc::new(block),
+        transaction: Arc::new(transaction),
+        entity_operations: vec![],
+    };
+
+    let err = module
+        .handle_ethereum_event(ctx, "loop", Arc::new(log), vec![])
+        .unwrap_err();
+    assert!(err.to_string().ends_with("timed out after 100ms"));
+}
+
+#[test]
+fn allocating_forever_exceeds_memory_limit() {
+    let mut module = test_module_with_limits(
+        mock_data_source("wasm_test/allocate_forever.wasm"),
+        None,
+        None,
+        Some(16 * 1024 * 1024),
+    );
+
+    let (block, transaction, log) = generate_fake_block();
+
+    let ctx = EventHandlerContext {
+        logger: Logger::root(slog::Discard, o!()),
+        block: Arc::new(block),
+        transaction: Arc::new(transaction),
+        entity_operations: vec![],
+    };
+
+    let err = module
+        .handle_ethereum_event(ctx, "allocateForever", Arc::new(log), vec![])
+        .unwrap_err();
+    assert!(err.to_string().ends_with("exceeded the memory limit of 16777216 bytes"));
+
+    // The memory never grows beyond the limit
+    assert!(module.heap_size() <= 16 * 1024 * 1024);
+}
+
+#[test]
+fn host_allocations_respect_memory_limit() {
+    let module = test_module_with_limits(
+        mock_data_source("wasm_test/abi_classes.wasm"),
+        None,
+        None,
+        Some(1024 * 1024),
+    );
+
+    // Allocations for host exports fail on the limit instead of growing the memory
+    let bytes = vec![0u8; 2 * 1024 * 1024];
+    let bytes_ptr: AscPtr<Uint8Array> = module.heap.asc_new(&*bytes);
+    assert!(bytes_ptr.is_null());
+    assert!(module.heap.check_memory_limit().is_err());
+    assert!(module.heap_size() <= 1024 * 1024);
+}
+
+#[test]
+fn reset_discards_module_state() {
+    let mut module = test_module(mock_data_source("wasm_test/counter.wasm"));
+
+    let increment = |module: &mut TestModule| -> i32 {
+        module
+            .module
+            .invoke_export("increment", &[], &mut module.externals)
+            .expect("call failed")
+            .expect("call returned nothing")
+            .try_into()
+            .expect("call did not return i32")
+    };
+
+    assert_eq!(increment(&mut module), 1);
+    assert_eq!(increment(&mut module), 2);
+
+    module.reset();
+
+    assert_eq!(increment(&mut module), 1);
+}
+
+/// Compares resetting the module between blocks by instantiating it anew,
+/// as the module was reset before it was pooled, to restoring the snapshot
+/// taken after it was started. Run with `--nocapture` to see the results.
+#[test]
+fn bench_event_handling_throughput() {
+    const BLOCKS: usize = 100;
+    const EVENTS_PER_BLOCK: usize = 10;
+
+    let handle_event = |module: &mut TestModule| {
+        let (block, transaction, log) = generate_fake_block();
+        let ctx = EventHandlerContext {
+            logger: Logger::root(slog::Discard, o!()),
+            block: Arc::new(block),
+            transaction: Arc::new(transaction),
+            entity_operations: vec![],
+        };
+        module
+            .handle_ethereum_event(
+                ctx,
+                "handleExampleEvent",
+                Arc::new(log),
+                vec![LogParam {
+                    name: "exampleParam".to_owned(),
+                    value: Token::String("some data".to_owned()),
+                }],
+            ).expect("failed to handle event");
+    };
+
+    let mut module = test_module(mock_data_source("wasm_test/example_event_handler.wasm"));
+
+    let start = Instant::now();
+    for _ in 0..BLOCKS {
+        module.reinstantiate();
+        for _ in 0..EVENTS_PER_BLOCK {
+            handle_event(&mut module);
+        }
+    }
+    let reinstantiated = start.elapsed();
+
+    let start = Instant::now();
+    for _ in 0..BLOCKS {
+        module.reset();
+        for _ in 0..EVENTS_PER_BLOCK {
+            handle_event(&mut module);
+        }
+    }
+    let restored = start.elapsed();
+
+    println!(
+        "{} blocks with {} events each: module instantiated per block: {:?}, \
+         module restored per block: {:?}",
+        BLOCKS, EVENTS_PER_BLOCK, reinstantiated, restored
+    );
+}
+
+/// Collects log records as `(level, message, key-value pairs)`.
+#[derive(Clone, Default)]
+struct RecordingDrain(Arc<Mutex<Vec<(slog::Level, String, String)>>>);
+
+struct KvRecorder(String);
+
+impl slog::Serializer for KvRecorder {
+    fn emit_arguments(&mut self, key: slog::Key, val: &fmt::Arguments) -> slog::Result {
+        write!(self.0, "{}={} ", key, val).unwrap();
+        Ok(())
+    }
+}
+
+impl slog::Drain for RecordingDrain {
+    type Ok = ();
+    type Err = slog::Never;
+
+    fn log(&self, record: &slog::Record, values: &slog::OwnedKVList) -> Result<(), slog::Never> {
+        let mut kv = KvRecorder(String::new());
+        slog::KV::serialize(values, record, &mut kv).unwrap();
+        self.0
+            .lock()
+            .unwrap()
+            .push((record.level(), record.msg().to_string(), kv.0));
+        Ok(())
+    }
+}
+
+#[test]
+fn log_messages_from_event_handler() {
+    let mut module = test_module(mock_data_source("wasm_test/log.wasm"));
+    let drain = RecordingDrain::default();
+
+    let (block, transaction, log) = generate_fake_block();
+    let transaction_hash = format!("{:x}", transaction.hash);
+
+    let ctx = EventHandlerContext {
+        logger: Logger::root(drain.clone(), o!()),
+        block: Arc::new(block),
+        transaction: Arc::new(transaction),
+        entity_operations: vec![],
+    };
+
+    module
+        .handle_ethereum_event(ctx, "handleEvent", Arc::new(log), vec![])
+        .expect("failed to handle event");
+
+    let records = drain.0.lock().unwrap();
+    assert_eq!(records.len(), 1);
+    let (level, msg, kv) = &records[0];
+    assert_eq!(*level, slog::Level::Info);
+    assert_eq!(msg, "Handled event");
+    assert!(kv.contains("block_number=5 "));
+    assert!(kv.contains(&format!("transaction={} ", transaction_hash)));
+    assert!(kv.contains("handler=handleEvent "));
+}
+
+#[test]
+fn log_levels() {
+    let mut module = test_module(mock_data_source("wasm_test/log.wasm"));
+    let drain = RecordingDrain::default();
+
+    let (block, transaction, _) = generate_fake_block();
+    module.externals.host_exports.ctx = Some(EventHandlerContext {
+        logger: Logger::root(drain.clone(), o!()),
+        block: Arc::new(block),
+        transaction: Arc::new(transaction),
+        entity_operations: vec![],
+    });
+
+    for level in 0..5 {
+        let msg: AscPtr<AscString> = module.heap.asc_new(&format!("level {}", level));
+        module
+            .module
+            .invoke_export(
+                "log",
+                &[RuntimeValue::from(level), RuntimeValue::from(msg)],
+                &mut module.externals,
+            ).expect("call failed");
+    }
+
+    let levels: Vec<_> = drain
+        .0
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(level, msg, _)| (*level, msg.clone()))
+        .collect();
+    assert_eq!(
+        levels,
+        vec![
+            (slog::Level::Critical, "level 0".to_owned()),
+            (slog::Level::Error, "level 1".to_owned()),
+            (slog::Level::Warning, "level 2".to_owned()),
+            (slog::Level::Info, "level 3".to_owned()),
+            (slog::Level::Debug, "level 4".to_owned()),
+        ]
+    );
+
+    // Unknown levels are rejected
+    let msg: AscPtr<AscString> = module.heap.asc_new("oops");
+    let err = module
+        .module
+        .invoke_export(
+            "log",
+            &[RuntimeValue::from(5), RuntimeValue::from(msg)],
+            &mut module.externals,
+        ).unwrap_err();
+    assert!(err.to_string().contains("Invalid log level 5"));
+}
+
+#[test]
+fn data_source_accessors() {
+    let mut module = test_module(mock_data_source("wasm_test/data_source.wasm"));
+
+    let call = |module: &mut TestModule, fn_name: &str| -> RuntimeValue {
+        module
+            .module
+            .invoke_export(fn_name, &[], &mut module.externals)
+            .expect("call failed")
+            .expect("call returned nothing")
+    };
+
+    let address_ptr: AscPtr<AscAddress> = call(&mut module, "dataSourceAddress")
+        .try_into()
+        .expect("call did not return pointer");
+    let address: H160 = module.heap.asc_get(address_ptr);
+    assert_eq!(
+        address,
+        H160::from_str("0123123123012312312301231231230123123123").unwrap()
+    );
+
+    let name_ptr: AscPtr<AscString> = call(&mut module, "dataSourceName")
+        .try_into()
+        .expect("call did not return pointer");
+    let name: String = module.heap.asc_get(name_ptr);
+    assert_eq!(name, "example data source");
+
+    let network_ptr: AscPtr<AscString> = call(&mut module, "dataSourceNetwork")
+        .try_into()
+        .expect("call did not return pointer");
+    let network: String = module.heap.asc_get(network_ptr);
+    assert_eq!(network, "mainnet");
+
+    let subgraph_id_ptr: AscPtr<AscString> = call(&mut module, "dataSourceSubgraphId")
+        .try_into()
+        .expect("call did not return pointer");
+    let subgraph_id: String = module.heap.asc_get(subgraph_id_ptr);
+    assert_eq!(subgraph_id, "test_subgraph");
+
+    // Data sources without a network return null
+    let mut data_source = mock_data_source("wasm_test/data_source.wasm");
+    data_source.network = None;
+    let mut module = test_module(data_source);
+    let network: i32 = call(&mut module, "dataSourceNetwork")
+        .try_into()
+        .expect("call did not return i32");
+    assert_eq!(network, 0);
+}
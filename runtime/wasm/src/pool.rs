@@ -0,0 +1,130 @@
+use futures::sync::mpsc::{unbounded, UnboundedSender};
+use futures::sync::oneshot;
+use std::collections::HashMap;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use graph::components::ethereum::EthereumBlock;
+use graph::prelude::*;
+
+use EthereumEvent;
+
+/// A mapping module instance kept in a `ModulePool`.
+pub(crate) trait PooledModule {
+    /// Handles a batch of events from the same block, one after the other.
+    fn handle_ethereum_events(
+        &mut self,
+        logger: &Logger,
+        block: Arc<EthereumBlock>,
+        events: Vec<EthereumEvent>,
+        entity_operations: Vec<EntityOperation>,
+    ) -> Result<Vec<EntityOperation>, Error>;
+
+    /// Size of the module's linear memory in bytes.
+    fn heap_size(&self) -> usize;
+}
+
+type Modules = HashMap<usize, Box<PooledModule>>;
+type Job = Box<FnOnce(&mut Modules) + Send>;
+
+/// Runs the mapping modules of all runtime hosts on a fixed number of threads.
+///
+/// wasmi modules are not `Send`, so every module instance is created on one of
+/// the pool's threads and stays there, handling the events of its runtime
+/// host block after block.
+pub(crate) struct ModulePool {
+    job_senders: Vec<UnboundedSender<(usize, Job)>>,
+    next_module_id: AtomicUsize,
+}
+
+impl ModulePool {
+    pub fn new(threads: usize) -> Self {
+        let job_senders = (0..threads.max(1))
+            .map(|i| {
+                let (job_sender, job_receiver) = unbounded::<(usize, Job)>();
+
+                thread::Builder::new()
+                    .name(format!("mapping-{}", i))
+                    .spawn(move || {
+                        let mut modules = Modules::new();
+
+                        // Run jobs until the pool is dropped. A panicking job
+                        // only takes down the module it was run for
+                        for (module_id, job) in job_receiver.wait().filter_map(|job| job.ok()) {
+                            if panic::catch_unwind(AssertUnwindSafe(|| job(&mut modules))).is_err()
+                            {
+                                modules.remove(&module_id);
+                            }
+                        }
+                    }).expect("Failed to spawn mapping thread");
+
+                job_sender
+            }).collect();
+
+        ModulePool {
+            job_senders,
+            next_module_id: AtomicUsize::new(0),
+        }
+    }
+
+    /// Adds the module instance returned by `create` to the pool and returns
+    /// the ID of the module.
+    pub fn add<F>(&self, create: F) -> usize
+    where
+        F: FnOnce() -> Box<PooledModule> + Send + 'static,
+    {
+        let module_id = self.next_module_id.fetch_add(1, Ordering::SeqCst);
+        self.run(module_id, move |modules| {
+            modules.insert(module_id, create());
+        });
+        module_id
+    }
+
+    /// Runs `f` with the module instance on the thread of the module.
+    pub fn with_module<F, R>(
+        &self,
+        module_id: usize,
+        f: F,
+    ) -> impl Future<Item = R, Error = Error> + Send
+    where
+        F: FnOnce(&mut PooledModule) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let (result_sender, result_receiver) = oneshot::channel();
+
+        self.run(module_id, move |modules| {
+            let module = modules
+                .get_mut(&module_id)
+                .expect("mapping module is not in the pool");
+            result_sender.send(f(&mut **module)).ok();
+        });
+
+        result_receiver.map_err(move |_| format_err!("Mapping module {} terminated", module_id))
+    }
+
+    /// Removes the module instance from the pool.
+    pub fn remove(&self, module_id: usize) {
+        self.run(module_id, move |modules| {
+            modules.remove(&module_id);
+        });
+    }
+
+    fn run<F>(&self, module_id: usize, job: F)
+    where
+        F: FnOnce(&mut Modules) + Send + 'static,
+    {
+        self.job_senders[module_id % self.job_senders.len()]
+            .unbounded_send((module_id, Box::new(job)))
+            .expect("mapping thread terminated");
+    }
+}
+
+impl fmt::Debug for ModulePool {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ModulePool")
+            .field("threads", &self.job_senders.len())
+            .finish()
+    }
+}
@@ -1,16 +1,18 @@
 use ethabi;
 use std::collections::HashMap;
+use std::str::FromStr;
 
 use graph::components::ethereum::{EthereumBlockData, EthereumEventData, EthereumTransactionData};
 use graph::data::store;
-use graph::prelude::BigInt;
+use graph::data::store::scalar::MAX_BIG_DECIMAL_EXP;
+use graph::prelude::{BigDecimal, BigInt};
 use graph::serde_json;
 use graph::web3::types as web3;
 
 use asc_abi::class::*;
-use asc_abi::{AscHeap, AscPtr, FromAscObj, ToAscObj};
+use asc_abi::{AscHeap, AscPtr, FromAscObj, ToAscObj, TryFromAscObj};
 
-use UnresolvedContractCall;
+use {RlpValue, UnresolvedContractCall};
 
 impl ToAscObj<Uint8Array> for web3::H160 {
     fn to_asc_obj<H: AscHeap>(&self, heap: &H) -> Uint8Array {
@@ -58,6 +60,30 @@ impl FromAscObj<AscBigInt> for BigInt {
     }
 }
 
+impl ToAscObj<AscBigDecimal> for BigDecimal {
+    fn to_asc_obj<H: AscHeap>(&self, heap: &H) -> AscBigDecimal {
+        let (digits, exp) = self.as_digits_and_exp();
+        AscBigDecimal {
+            digits: heap.asc_new(&digits),
+            exp: heap.asc_new(&BigInt::from(exp)),
+        }
+    }
+}
+
+impl TryFromAscObj<AscBigDecimal> for BigDecimal {
+    fn try_from_asc_obj<H: AscHeap>(big_decimal: AscBigDecimal, heap: &H) -> Result<Self, String> {
+        let digits: BigInt = heap.asc_get(big_decimal.digits);
+        let exp: BigInt = heap.asc_get(big_decimal.exp);
+        match i64::from_str(&exp.to_string()) {
+            Ok(exp) if exp.abs() <= MAX_BIG_DECIMAL_EXP => Ok(BigDecimal::new(digits, exp)),
+            _ => Err(format!(
+                "BigDecimal exponent {} is outside the range -{} to {}",
+                exp, MAX_BIG_DECIMAL_EXP, MAX_BIG_DECIMAL_EXP
+            )),
+        }
+    }
+}
+
 impl ToAscObj<AscEnum<EthereumValueKind>> for ethabi::Token {
     fn to_asc_obj<H: AscHeap>(&self, heap: &H) -> AscEnum<EthereumValueKind> {
         use ethabi::Token::*;
@@ -133,12 +159,15 @@ impl FromAscObj<AscEnum<EthereumValueKind>> for ethabi::Token {
     }
 }
 
-impl FromAscObj<AscEnum<StoreValueKind>> for store::Value {
-    fn from_asc_obj<H: AscHeap>(asc_enum: AscEnum<StoreValueKind>, heap: &H) -> Self {
+impl TryFromAscObj<AscEnum<StoreValueKind>> for store::Value {
+    fn try_from_asc_obj<H: AscHeap>(
+        asc_enum: AscEnum<StoreValueKind>,
+        heap: &H,
+    ) -> Result<Self, String> {
         use self::store::Value;
 
         let payload = asc_enum.payload;
-        match asc_enum.kind {
+        Ok(match asc_enum.kind {
             StoreValueKind::String => {
                 let ptr: AscPtr<AscString> = AscPtr::from(payload);
                 Value::String(heap.asc_get(ptr))
@@ -149,7 +178,7 @@ impl FromAscObj<AscEnum<StoreValueKind>> for store::Value {
             StoreValueKind::Bool => Value::Bool(bool::from(payload)),
             StoreValueKind::Array => {
                 let ptr: AscEnumArray<StoreValueKind> = AscPtr::from(payload);
-                Value::List(heap.asc_get(ptr))
+                Value::List(heap.try_asc_get(ptr)?)
             }
             StoreValueKind::Null => Value::Null,
             StoreValueKind::Bytes => {
@@ -162,7 +191,11 @@ impl FromAscObj<AscEnum<StoreValueKind>> for store::Value {
                 let array: Vec<u8> = heap.asc_get(ptr);
                 Value::BigInt(store::scalar::BigInt::from_signed_bytes_le(&array))
             }
-        }
+            StoreValueKind::BigDecimal => {
+                let ptr: AscPtr<AscBigDecimal> = AscPtr::from(payload);
+                Value::BigDecimal(heap.try_asc_get(ptr)?)
+            }
+        })
     }
 }
 
@@ -185,6 +218,10 @@ impl ToAscObj<AscEnum<StoreValueKind>> for store::Value {
                 let bytes_obj: AscPtr<Uint8Array> = heap.asc_new(&*big_int.to_signed_bytes_le());
                 bytes_obj.into()
             }
+            Value::BigDecimal(big_decimal) => {
+                let big_decimal_obj: AscPtr<AscBigDecimal> = heap.asc_new(big_decimal);
+                big_decimal_obj.into()
+            }
         };
 
         AscEnum {
@@ -247,6 +284,38 @@ impl ToAscObj<AscEnum<JsonValueKind>> for serde_json::Value {
     }
 }
 
+impl ToAscObj<AscEnum<RlpValueKind>> for RlpValue {
+    fn to_asc_obj<H: AscHeap>(&self, heap: &H) -> AscEnum<RlpValueKind> {
+        match self {
+            RlpValue::Bytes(bytes) => AscEnum {
+                kind: RlpValueKind::Bytes,
+                payload: heap.asc_new::<Uint8Array, _>(bytes.as_slice()).into(),
+            },
+            RlpValue::List(items) => AscEnum {
+                kind: RlpValueKind::List,
+                payload: heap
+                    .asc_new::<Array<AscPtr<AscEnum<RlpValueKind>>>, _>(items.as_slice())
+                    .into(),
+            },
+        }
+    }
+}
+
+impl FromAscObj<AscEnum<RlpValueKind>> for RlpValue {
+    fn from_asc_obj<H: AscHeap>(asc_enum: AscEnum<RlpValueKind>, heap: &H) -> Self {
+        match asc_enum.kind {
+            RlpValueKind::Bytes => {
+                let ptr: AscPtr<Uint8Array> = AscPtr::from(asc_enum.payload);
+                RlpValue::Bytes(heap.asc_get(ptr))
+            }
+            RlpValueKind::List => {
+                let ptr: AscEnumArray<RlpValueKind> = AscPtr::from(asc_enum.payload);
+                RlpValue::List(heap.asc_get(ptr))
+            }
+        }
+    }
+}
+
 impl ToAscObj<AscEthereumBlock> for EthereumBlockData {
     fn to_asc_obj<H: AscHeap>(&self, heap: &H) -> AscEthereumBlock {
         AscEthereumBlock {
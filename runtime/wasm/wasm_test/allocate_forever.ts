@@ -0,0 +1,9 @@
+import "allocator/arena";
+
+export { allocate_memory };
+
+export function allocateForever(event: usize): void {
+    while (true) {
+        allocate_memory(1048576);
+    }
+}
@@ -0,0 +1,48 @@
+import "allocator/arena";
+
+export { allocate_memory };
+
+type BigInt = Uint8Array;
+
+class BigDecimal {
+    digits: BigInt
+    exp: BigInt
+}
+
+declare namespace bigDecimal {
+    function plus(x: BigDecimal, y: BigDecimal): BigDecimal
+    function minus(x: BigDecimal, y: BigDecimal): BigDecimal
+    function times(x: BigDecimal, y: BigDecimal): BigDecimal
+    function dividedBy(x: BigDecimal, y: BigDecimal): BigDecimal
+    function equals(x: BigDecimal, y: BigDecimal): boolean
+    function toString(x: BigDecimal): string
+    function fromString(s: string): BigDecimal
+}
+
+export function plus(x: BigDecimal, y: BigDecimal): BigDecimal {
+    return bigDecimal.plus(x, y)
+}
+
+export function minus(x: BigDecimal, y: BigDecimal): BigDecimal {
+    return bigDecimal.minus(x, y)
+}
+
+export function times(x: BigDecimal, y: BigDecimal): BigDecimal {
+    return bigDecimal.times(x, y)
+}
+
+export function dividedBy(x: BigDecimal, y: BigDecimal): BigDecimal {
+    return bigDecimal.dividedBy(x, y)
+}
+
+export function equals(x: BigDecimal, y: BigDecimal): boolean {
+    return bigDecimal.equals(x, y)
+}
+
+export function bigDecimalToString(x: BigDecimal): string {
+    return bigDecimal.toString(x)
+}
+
+export function bigDecimalFromString(s: string): BigDecimal {
+    return bigDecimal.fromString(s)
+}
@@ -0,0 +1,10 @@
+import "allocator/arena";
+
+export { allocate_memory };
+
+let counter: i32 = 0;
+
+export function increment(): i32 {
+    counter += 1;
+    return counter;
+}
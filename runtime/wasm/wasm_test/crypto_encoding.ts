@@ -0,0 +1,49 @@
+import "allocator/arena";
+
+export { allocate_memory };
+
+type Address = Uint8Array;
+
+declare namespace crypto {
+    function sha256(input: Uint8Array): Uint8Array
+    function ripemd160(input: Uint8Array): Uint8Array
+    function ecrecover(hash: Uint8Array, signature: Uint8Array): Address | null
+}
+
+declare namespace rlp {
+    function decode(bytes: Uint8Array): RLPValue
+}
+
+declare namespace typeConversion {
+    function bytesToBase58(bytes: Uint8Array): string
+}
+
+enum RLPValueKind {
+    BYTES = 0,
+    LIST = 1,
+}
+
+class RLPValue {
+    kind: RLPValueKind
+    data: u64
+}
+
+export function sha256(input: Uint8Array): Uint8Array {
+    return crypto.sha256(input)
+}
+
+export function ripemd160(input: Uint8Array): Uint8Array {
+    return crypto.ripemd160(input)
+}
+
+export function ecrecover(hash: Uint8Array, signature: Uint8Array): Address | null {
+    return crypto.ecrecover(hash, signature)
+}
+
+export function decodeRlp(bytes: Uint8Array): RLPValue {
+    return rlp.decode(bytes)
+}
+
+export function toBase58(bytes: Uint8Array): string {
+    return typeConversion.bytesToBase58(bytes)
+}
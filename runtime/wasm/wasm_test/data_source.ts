@@ -0,0 +1,28 @@
+import "allocator/arena";
+
+export { allocate_memory };
+
+type Address = Uint8Array;
+
+declare namespace dataSource {
+    function address(): Address
+    function name(): string
+    function network(): string | null
+    function subgraphId(): string
+}
+
+export function dataSourceAddress(): Address {
+    return dataSource.address()
+}
+
+export function dataSourceName(): string {
+    return dataSource.name()
+}
+
+export function dataSourceNetwork(): string | null {
+    return dataSource.network()
+}
+
+export function dataSourceSubgraphId(): string {
+    return dataSource.subgraphId()
+}
@@ -0,0 +1,7 @@
+import "allocator/arena";
+
+export { allocate_memory };
+
+export function loop(event: usize): void {
+    while (true) {}
+}
@@ -0,0 +1,23 @@
+import "allocator/arena";
+
+export { allocate_memory };
+
+enum LogLevel {
+    CRITICAL = 0,
+    ERROR = 1,
+    WARNING = 2,
+    INFO = 3,
+    DEBUG = 4,
+}
+
+declare namespace log {
+    function log(level: LogLevel, msg: string): void
+}
+
+export function handleEvent(event: i32): void {
+    log.log(LogLevel.INFO, "Handled event")
+}
+
+export function log(level: LogLevel, msg: string): void {
+    log.log(level, msg)
+}
@@ -14,8 +14,8 @@ mod response;
 mod server;
 mod service;
 
-pub use self::request::GraphQLRequest;
-pub use self::response::GraphQLResponse;
+pub use self::request::{GraphQLBatchRequest, GraphQLRequest};
+pub use self::response::{GraphQLBatchResponse, GraphQLResponse};
 pub use self::server::GraphQLServer;
 pub use self::service::{GraphQLService, GraphQLServiceResponse};
 
@@ -30,48 +30,125 @@ impl Future for GraphQLRequest {
         let json: serde_json::Value = serde_json::from_slice(&self.body)
             .map_err(|e| GraphQLServerError::ClientError(format!("{}", e)))?;
 
-        // Ensure the JSON data is an object
-        let obj = json.as_object().ok_or_else(|| {
-            GraphQLServerError::ClientError(String::from("Request data is not an object"))
-        })?;
+        parse_query(&json, schema).map(Async::Ready)
+    }
+}
 
-        // Ensure the JSON data has a "query" field
-        let query_value = obj.get("query").ok_or_else(|| {
-            GraphQLServerError::ClientError(String::from(
-                "The \"query\" field missing in request data",
-            ))
-        })?;
+/// Future for a batch of queries parsed from an HTTP request whose body is
+/// a JSON array of query objects.
+///
+/// Each element is parsed independently, so that a malformed query only
+/// fails its own entry in the batch. Batches with more than `max_size`
+/// queries are rejected as a whole.
+pub struct GraphQLBatchRequest {
+    body: Chunk,
+    schema: Schema,
+    max_size: usize,
+}
+
+impl GraphQLBatchRequest {
+    /// Creates a new GraphQLBatchRequest future based on an HTTP request body.
+    pub fn new(body: Chunk, schema: Schema, max_size: usize) -> Self {
+        GraphQLBatchRequest {
+            body,
+            schema,
+            max_size,
+        }
+    }
+
+    /// Returns whether an HTTP request body looks like a batch of queries,
+    /// i.e. whether it is a JSON array rather than an object.
+    pub fn is_batch(body: &[u8]) -> bool {
+        body.iter()
+            .find(|b| !b.is_ascii_whitespace())
+            .map_or(false, |b| *b == b'[')
+    }
+}
 
-        // Ensure the "query" field is a string
-        let query_string = query_value.as_str().ok_or_else(|| {
-            GraphQLServerError::ClientError(String::from("The\"query\" field is not a string"))
+impl Future for GraphQLBatchRequest {
+    type Item = Vec<Result<Query, GraphQLServerError>>;
+    type Error = GraphQLServerError;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        // Parse request body as JSON
+        let json: serde_json::Value = serde_json::from_slice(&self.body)
+            .map_err(|e| GraphQLServerError::ClientError(format!("{}", e)))?;
+
+        // Ensure the JSON data is a non-empty array
+        let items = json.as_array().ok_or_else(|| {
+            GraphQLServerError::ClientError(String::from("Request data is not an array"))
         })?;
+        if items.is_empty() {
+            return Err(GraphQLServerError::ClientError(String::from(
+                "Request data contains no queries",
+            )));
+        }
+        if items.len() > self.max_size {
+            return Err(GraphQLServerError::ClientError(format!(
+                "Request data contains {} queries, but at most {} are allowed in a batch",
+                items.len(),
+                self.max_size
+            )));
+        }
 
-        // Parse the "query" field of the JSON body
-        let document = graphql_parser::parse_query(query_string)
-            .map_err(|e| GraphQLServerError::from(QueryError::from(e)))?;
-
-        // Parse the "variables" field of the JSON body, if present
-        let variables = match obj.get("variables") {
-            None | Some(serde_json::Value::Null) => Ok(None),
-            Some(variables @ serde_json::Value::Object(_)) => {
-                serde_json::from_value(variables.clone())
-                    .map_err(|e| GraphQLServerError::ClientError(e.to_string()))
-                    .map(Some)
-            }
-            _ => Err(GraphQLServerError::ClientError(
-                "Invalid query variables provided".to_string(),
-            )),
-        }?;
-
-        Ok(Async::Ready(Query {
-            document,
-            variables,
-            schema,
-        }))
+        Ok(Async::Ready(
+            items
+                .iter()
+                .map(|item| parse_query(item, self.schema.clone()))
+                .collect(),
+        ))
     }
 }
 
+/// Parses a single JSON query object into a `Query` against `schema`.
+fn parse_query(json: &serde_json::Value, schema: Schema) -> Result<Query, GraphQLServerError> {
+    // Ensure the JSON data is an object
+    let obj = json.as_object().ok_or_else(|| {
+        GraphQLServerError::ClientError(String::from("Request data is not an object"))
+    })?;
+
+    // Ensure the JSON data has a "query" field
+    let query_value = obj.get("query").ok_or_else(|| {
+        GraphQLServerError::ClientError(String::from("The \"query\" field missing in request data"))
+    })?;
+
+    // Ensure the "query" field is a string
+    let query_string = query_value.as_str().ok_or_else(|| {
+        GraphQLServerError::ClientError(String::from("The\"query\" field is not a string"))
+    })?;
+
+    // Parse the "query" field of the JSON body
+    let document = graphql_parser::parse_query(query_string)
+        .map_err(|e| GraphQLServerError::from(QueryError::from(e)))?;
+
+    // Parse the "variables" field of the JSON body, if present
+    let variables = match obj.get("variables") {
+        None | Some(serde_json::Value::Null) => Ok(None),
+        Some(variables @ serde_json::Value::Object(_)) => serde_json::from_value(variables.clone())
+            .map_err(|e| GraphQLServerError::ClientError(e.to_string()))
+            .map(Some),
+        _ => Err(GraphQLServerError::ClientError(
+            "Invalid query variables provided".to_string(),
+        )),
+    }?;
+
+    // Parse the "operationName" field of the JSON body, if present
+    let operation_name = match obj.get("operationName") {
+        None | Some(serde_json::Value::Null) => Ok(None),
+        Some(serde_json::Value::String(name)) => Ok(Some(name.clone())),
+        _ => Err(GraphQLServerError::ClientError(
+            "Invalid operation name provided".to_string(),
+        )),
+    }?;
+
+    Ok(Query {
+        document,
+        variables,
+        schema,
+        operation_name,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use graphql_parser;
@@ -79,7 +156,7 @@ mod tests {
 
     use graph::prelude::*;
 
-    use super::GraphQLRequest;
+    use super::{GraphQLBatchRequest, GraphQLRequest};
 
     const EXAMPLE_SCHEMA: &'static str = "type Query { users: [User!] }";
 
@@ -211,4 +288,122 @@ mod tests {
         assert_eq!(query.document, expected_query);
         assert_eq!(query.variables, Some(expected_variables));
     }
+
+    #[test]
+    fn parses_operation_name() {
+        let schema = Schema {
+            id: "test".to_string(),
+            document: graphql_parser::parse_schema(EXAMPLE_SCHEMA).unwrap(),
+        };
+        let request = GraphQLRequest::new(
+            hyper::Chunk::from(
+                "\
+                 {\
+                 \"query\": \"query a { user { name } } query b { user { id } }\", \
+                 \"operationName\": \"b\" \
+                 }",
+            ),
+            schema,
+        );
+        let query = request.wait().expect("Should accept operation names");
+        assert_eq!(query.operation_name, Some("b".to_owned()));
+    }
+
+    #[test]
+    fn rejects_non_string_operation_name() {
+        let schema = Schema {
+            id: "test".to_string(),
+            document: graphql_parser::parse_schema(EXAMPLE_SCHEMA).unwrap(),
+        };
+        let request = GraphQLRequest::new(
+            hyper::Chunk::from(
+                "\
+                 {\
+                 \"query\": \"{ user { name } }\", \
+                 \"operationName\": 5 \
+                 }",
+            ),
+            schema,
+        );
+        request
+            .wait()
+            .expect_err("Should reject non-string operation names");
+    }
+
+    #[test]
+    fn detects_batches() {
+        assert!(GraphQLBatchRequest::is_batch(b"  \n[{}]"));
+        assert!(!GraphQLBatchRequest::is_batch(
+            b"{\"query\": \"{ user { name } }\"}"
+        ));
+        assert!(!GraphQLBatchRequest::is_batch(b""));
+    }
+
+    #[test]
+    fn parses_batches() {
+        let schema = Schema {
+            id: "test".to_string(),
+            document: graphql_parser::parse_schema(EXAMPLE_SCHEMA).unwrap(),
+        };
+        let request = GraphQLBatchRequest::new(
+            hyper::Chunk::from(
+                "[\
+                 {\"query\": \"{ user { name } }\"}, \
+                 {\"query\": 5}, \
+                 {\"query\": \"{ user { id } }\"}\
+                 ]",
+            ),
+            schema,
+            10,
+        );
+        let queries = request.wait().expect("Should accept batches");
+
+        assert_eq!(queries.len(), 3);
+        assert_eq!(
+            queries[0]
+                .as_ref()
+                .expect("Should accept valid queries")
+                .document,
+            graphql_parser::parse_query("{ user { name } }").unwrap()
+        );
+        assert!(queries[1].is_err());
+        assert_eq!(
+            queries[2]
+                .as_ref()
+                .expect("Should accept valid queries")
+                .document,
+            graphql_parser::parse_query("{ user { id } }").unwrap()
+        );
+    }
+
+    #[test]
+    fn rejects_empty_batches() {
+        let schema = Schema {
+            id: "test".to_string(),
+            document: graphql_parser::parse_schema(EXAMPLE_SCHEMA).unwrap(),
+        };
+        let request = GraphQLBatchRequest::new(hyper::Chunk::from("[]"), schema, 10);
+        request.wait().expect_err("Should reject empty batches");
+    }
+
+    #[test]
+    fn rejects_batches_over_the_size_limit() {
+        let schema = Schema {
+            id: "test".to_string(),
+            document: graphql_parser::parse_schema(EXAMPLE_SCHEMA).unwrap(),
+        };
+        let query = "{\"query\": \"{ user { name } }\"}";
+        let request = GraphQLBatchRequest::new(
+            hyper::Chunk::from(format!("[{}, {}, {}]", query, query, query)),
+            schema,
+            2,
+        );
+        let err = request
+            .wait()
+            .expect_err("Should reject batches over the size limit");
+        assert_eq!(
+            err.to_string(),
+            "Request data contains 3 queries, but at most 2 are allowed in a batch"
+        );
+    }
 }
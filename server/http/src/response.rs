@@ -71,9 +71,54 @@ impl Future for GraphQLResponse {
     }
 }
 
+/// Future for HTTP responses to batches of GraphQL query requests.
+///
+/// A batch that could be parsed always results in a 200 response with one
+/// entry per query; errors of individual queries are reported in their
+/// own entries.
+pub struct GraphQLBatchResponse {
+    result: Result<Vec<GraphQLResponse>, GraphQLResponse>,
+}
+
+impl GraphQLBatchResponse {
+    /// Creates a new GraphQLBatchResponse future based on the results
+    /// generated by running the queries of a batch.
+    pub fn new(
+        result: Result<Vec<Result<QueryResult, GraphQLServerError>>, GraphQLServerError>,
+    ) -> Self {
+        GraphQLBatchResponse {
+            result: result
+                .map(|results| results.into_iter().map(GraphQLResponse::new).collect())
+                .map_err(|e| GraphQLResponse::new(Err(e))),
+        }
+    }
+}
+
+impl Future for GraphQLBatchResponse {
+    type Item = Response<Body>;
+    type Error = GraphQLServerError;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        match self.result {
+            Ok(ref responses) => {
+                let json = serde_json::to_string(responses)
+                    .expect("Failed to serialize GraphQL batch response to JSON");
+                let response = Response::builder()
+                    .status(StatusCode::OK)
+                    .header("Access-Control-Allow-Origin", "*")
+                    .header("Access-Control-Allow-Headers", "Content-Type")
+                    .body(Body::from(json))
+                    .unwrap();
+                Ok(Async::Ready(response))
+            }
+            Err(ref mut response) => response.poll(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::GraphQLResponse;
+    use super::{GraphQLBatchResponse, GraphQLResponse};
     use futures::sync::oneshot;
     use graph::components::server::query::GraphQLServerError;
     use graph::prelude::*;
@@ -237,4 +282,26 @@ mod tests {
 
         assert_eq!(message, "Something went wrong");
     }
+
+    #[test]
+    fn generates_array_for_batches() {
+        let data = graphql_parser::query::Value::Object(BTreeMap::new());
+        let future = GraphQLBatchResponse::new(Ok(vec![
+            Ok(QueryResult::new(Some(data))),
+            Err(GraphQLServerError::ClientError(String::from("foo"))),
+        ]));
+        let response = future.wait().expect("Should generate a response");
+        let results = test_utils::assert_batch_response(response);
+        assert_eq!(results.len(), 2);
+        assert!(results[0].get("data").is_some());
+        assert!(results[1].get("errors").is_some());
+    }
+
+    #[test]
+    fn generates_error_for_invalid_batches() {
+        let error = GraphQLServerError::ClientError(String::from("foo"));
+        let future = GraphQLBatchResponse::new(Err(error));
+        let response = future.wait().expect("Should generate a response");
+        test_utils::assert_error_response(response, StatusCode::BAD_REQUEST);
+    }
 }
@@ -1,14 +1,15 @@
+use futures::sync::oneshot;
 use graph::components::server::query::GraphQLServerError;
 use graph::data::subgraph::schema::SUBGRAPHS_ID;
 use graph::prelude::*;
 use http::header;
 use hyper::service::Service;
-use hyper::{Body, Method, Request, Response, StatusCode};
+use hyper::{Body, Chunk, Method, Request, Response, StatusCode};
 use std::collections::BTreeMap;
 use std::sync::RwLock;
 
-use request::GraphQLRequest;
-use response::GraphQLResponse;
+use request::{GraphQLBatchRequest, GraphQLRequest};
+use response::{GraphQLBatchResponse, GraphQLResponse};
 
 /// An asynchronous response to a GraphQL request.
 pub type GraphQLServiceResponse =
@@ -21,6 +22,7 @@ pub struct GraphQLService<Q, S> {
     schemas: Arc<RwLock<BTreeMap<SubgraphId, Schema>>>,
     graphql_runner: Arc<Q>,
     store: Arc<S>,
+    max_batch_size: usize,
 }
 
 impl<Q, S> Clone for GraphQLService<Q, S> {
@@ -29,6 +31,7 @@ impl<Q, S> Clone for GraphQLService<Q, S> {
             schemas: self.schemas.clone(),
             graphql_runner: self.graphql_runner.clone(),
             store: self.store.clone(),
+            max_batch_size: self.max_batch_size,
         }
     }
 }
@@ -44,10 +47,17 @@ where
         graphql_runner: Arc<Q>,
         store: Arc<S>,
     ) -> Self {
+        // Limit the number of queries in a batch, as they all run at the
+        // same time
+        let max_batch_size = parse_env_var("GRAPH_GRAPHQL_MAX_BATCH_SIZE")
+            .unwrap_or_else(|e| panic!("{}", e))
+            .unwrap_or(100);
+
         GraphQLService {
             schemas,
             graphql_runner,
             store,
+            max_batch_size,
         }
     }
 
@@ -161,18 +171,64 @@ where
                 request_body
                     .concat2()
                     .map_err(|_| GraphQLServerError::from("Failed to read request body"))
-                    .and_then(move |body| GraphQLRequest::new(body, schema.clone()))
-                    .and_then(move |query| {
-                        // Run the query using the query runner
-                        service
-                            .graphql_runner
-                            .run_query(query)
-                            .map_err(|e| GraphQLServerError::from(e))
-                    }).then(|result| GraphQLResponse::new(result)),
+                    .and_then(move |body| -> GraphQLServiceResponse {
+                        if GraphQLBatchRequest::is_batch(&body) {
+                            service.handle_graphql_batch(body, schema)
+                        } else {
+                            Box::new(
+                                GraphQLRequest::new(body, schema)
+                                    .and_then(move |query| service.run_query(query))
+                                    .then(|result| GraphQLResponse::new(result)),
+                            )
+                        }
+                    }),
             ),
         }
     }
 
+    /// Runs all queries of a batch concurrently and responds with an array
+    /// of their results, in the order the queries were sent.
+    ///
+    /// Queries execute synchronously, so each query of the batch is spawned
+    /// as a separate task, which the runtime runs on any of its threads.
+    fn handle_graphql_batch(&self, body: Chunk, schema: Schema) -> GraphQLServiceResponse {
+        let service = self.clone();
+
+        Box::new(
+            GraphQLBatchRequest::new(body, schema, self.max_batch_size)
+                .and_then(move |queries| {
+                    future::join_all(queries.into_iter().map(move |query| {
+                        let service = service.clone();
+                        let (result_sender, result_receiver) = oneshot::channel();
+
+                        tokio::spawn(
+                            future::result(query)
+                                .and_then(move |query| service.run_query(query))
+                                .then(move |result| result_sender.send(result).map_err(|_| ())),
+                        );
+
+                        // Turn failures into results so that one failing
+                        // query doesn't fail the entire batch
+                        result_receiver.then(|result| -> Result<_, GraphQLServerError> {
+                            Ok(result.unwrap_or_else(|_| {
+                                Err(GraphQLServerError::from("Query was aborted"))
+                            }))
+                        })
+                    }))
+                }).then(|result| GraphQLBatchResponse::new(result)),
+        )
+    }
+
+    fn run_query(
+        &self,
+        query: Query,
+    ) -> impl Future<Item = QueryResult, Error = GraphQLServerError> {
+        // Run the query using the query runner
+        self.graphql_runner
+            .run_query(query)
+            .map_err(|e| GraphQLServerError::from(e))
+    }
+
     // Handles OPTIONS requests
     fn handle_graphql_options(&self, _request: Request<Body>) -> GraphQLServiceResponse {
         Box::new(future::ok(
@@ -403,4 +459,65 @@ mod tests {
                 res
             })).unwrap()
     }
+
+    #[test]
+    fn posting_batched_queries_yields_array_response() {
+        let id = "test-schema".to_string();
+        let schema = Arc::new(RwLock::new(BTreeMap::from_iter(once((
+            id.clone(),
+            Schema {
+                id: id.clone(),
+                document: graphql_parser::parse_schema(
+                    "\
+                     scalar String \
+                     type Query { name: String } \
+                     ",
+                ).unwrap(),
+            },
+        )))));
+        let graphql_runner = Arc::new(TestGraphQlRunner);
+        let store = Arc::new(MockStore::new());
+        let mut service = GraphQLService::new(schema, graphql_runner, store);
+
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(format!("http://localhost:8000/subgraphs/id/{}/graphql", id))
+            .body(Body::from(
+                "[{\"query\": \"{ name }\"}, {}, {\"query\": \"{ name }\"}]",
+            )).unwrap();
+
+        // The queries of the batch are spawned onto the runtime, so don't
+        // block one of its threads while waiting for the response
+        let mut runtime = tokio::runtime::Runtime::new().unwrap();
+        let response = runtime
+            .block_on(service.call(request))
+            .expect("Should return a response");
+
+        // The response must be a 200 with one result per query
+        let results = test_utils::assert_batch_response(response);
+        assert_eq!(results.len(), 3);
+
+        for i in &[0, 2] {
+            let name = results[*i]
+                .get("data")
+                .expect("Query result has no \"data\" field")
+                .get("name")
+                .expect("Query result data has no \"name\" field")
+                .as_str()
+                .expect("Query result field \"name\" is not a string");
+            assert_eq!(name, "Jordi".to_string());
+        }
+
+        // The invalid query fails on its own
+        let message = results[1]
+            .get("errors")
+            .expect("Invalid query has no \"errors\" field")
+            .get(0)
+            .expect("Invalid query has no errors")
+            .get("message")
+            .expect("Error contains no message")
+            .as_str()
+            .expect("Error message is not a string");
+        assert_eq!(message, "The \"query\" field missing in request data");
+    }
 }
@@ -53,3 +53,31 @@ pub fn assert_error_response(
         .wait()
         .unwrap()
 }
+
+/// Asserts that the response is a successful GraphQL batch response; returns
+/// the individual results.
+pub fn assert_batch_response(
+    response: Response<Body>,
+) -> Vec<serde_json::Map<String, serde_json::Value>> {
+    assert_eq!(response.status(), StatusCode::OK);
+
+    response
+        .into_body()
+        .concat2()
+        .map(|chunk| {
+            let json: serde_json::Value =
+                serde_json::from_slice(&chunk).expect("GraphQL response is not valid JSON");
+
+            json.as_array()
+                .expect("GraphQL batch response must be an array")
+                .iter()
+                .map(|result| {
+                    result
+                        .as_object()
+                        .expect("GraphQL batch response entries must be objects")
+                        .clone()
+                }).collect()
+        }).map_err(|e| panic!("Truncated response body {:?}", e))
+        .wait()
+        .unwrap()
+}
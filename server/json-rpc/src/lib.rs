@@ -3,9 +3,13 @@ extern crate serde;
 #[macro_use]
 extern crate serde_derive;
 extern crate graph;
+#[cfg(test)]
+extern crate graph_mock;
 
+use graph::chrono::{DateTime, Utc};
 use graph::prelude::{JsonRpcServer as JsonRpcServerTrait, *};
 use graph::serde_json;
+use graph::web3::types::H256;
 use jsonrpc_http_server::{
     hyper::{header, Request, Response, StatusCode},
     jsonrpc_core::{
@@ -23,6 +27,16 @@ const JSON_RPC_DEPLOY_ERROR: i64 = 0;
 const JSON_RPC_REMOVE_ERROR: i64 = 1;
 const JSON_RPC_UNAUTHORIZED_ERROR: i64 = 2;
 const JSON_RPC_INTERNAL_ERROR: i64 = 3;
+const JSON_RPC_LOGS_ERROR: i64 = 4;
+const JSON_RPC_API_KEY_ERROR: i64 = 5;
+const JSON_RPC_PAUSE_ERROR: i64 = 6;
+const JSON_RPC_RESUME_ERROR: i64 = 7;
+const JSON_RPC_REINDEX_ERROR: i64 = 8;
+const JSON_RPC_REWIND_ERROR: i64 = 9;
+const JSON_RPC_GC_ERROR: i64 = 10;
+
+/// Number of logs returned by `subgraph_logs` if `first` is not specified.
+const DEFAULT_LOGS_FIRST: usize = 100;
 
 #[derive(Debug, Serialize, Deserialize)]
 struct SubgraphDeployParams {
@@ -47,12 +61,101 @@ impl fmt::Display for SubgraphRemoveParams {
     }
 }
 
+/// Parameters of the endpoints that act on the versions of a subgraph name,
+/// e.g. `subgraph_pause`.
+#[derive(Debug, Serialize, Deserialize)]
+struct SubgraphNameParams {
+    name: String,
+}
+
+impl fmt::Display for SubgraphNameParams {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SubgraphRewindParams {
+    name: String,
+    block_number: u64,
+    block_hash: Option<H256>,
+}
+
+impl fmt::Display for SubgraphRewindParams {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SubgraphGcParams {
+    #[serde(default)]
+    dry_run: bool,
+}
+
+impl fmt::Display for SubgraphGcParams {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{:?}", self)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct SubgraphAuthorizeParams {
     subgraph_access_tokens: BTreeMap<String, String>,
 }
 
 impl fmt::Display for SubgraphAuthorizeParams {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        // Never log the access tokens themselves
+        write!(
+            f,
+            "SubgraphAuthorizeParams {{ subgraph_names: {:?} }}",
+            self.subgraph_access_tokens.keys().collect::<Vec<_>>()
+        )
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SubgraphLogsParams {
+    name: String,
+    level: Option<String>,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+    text: Option<String>,
+    first: Option<usize>,
+}
+
+impl fmt::Display for SubgraphLogsParams {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ApiKeyCreateParams {
+    roles: Vec<AdminRole>,
+    subgraph_name: Option<String>,
+    expires_at: Option<DateTime<Utc>>,
+}
+
+impl fmt::Display for ApiKeyCreateParams {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ApiKeyCreateResponse {
+    id: String,
+    token: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ApiKeyRevokeParams {
+    id: String,
+}
+
+impl fmt::Display for ApiKeyRevokeParams {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         write!(f, "{:?}", self)
     }
@@ -65,62 +168,148 @@ struct AuthorizationHeader {
 
 impl Metadata for AuthorizationHeader {}
 
-pub struct JsonRpcServer<P, S> {
+pub struct JsonRpcServer<P, S, L> {
     provider: Arc<P>,
     store: Arc<S>,
+    log_store: Arc<L>,
     logger: Logger,
+    master_token: Option<String>,
 }
 
-impl<P, S> JsonRpcServer<P, S>
+impl<P, S, L> JsonRpcServer<P, S, L>
 where
     P: SubgraphProviderWithNames,
     S: Store,
+    L: SubgraphLogStore,
 {
-    fn require_master_token(auth: AuthorizationHeader) -> Result<(), jsonrpc_core::Error> {
-        let master_token = env::var(GRAPH_MASTER_TOKEN_VAR);
-        match &master_token {
-            Ok(master_token) if *master_token == auth.bearer_token => (), // Authorized.
-            Ok(_) => {
-                return Err(json_rpc_error(
-                    JSON_RPC_UNAUTHORIZED_ERROR,
-                    "authorization token is invalid".to_owned(),
-                ))
+    /// Checks that the caller is allowed to make an admin API call that
+    /// requires `role` for all of `subgraph_names`, or for all subgraph names
+    /// if `subgraph_names` is empty.
+    ///
+    /// Returns who the caller is, for the audit log.
+    fn authorize(
+        &self,
+        auth: &AuthorizationHeader,
+        role: AdminRole,
+        subgraph_names: &[&str],
+    ) -> Result<String, jsonrpc_core::Error> {
+        match self.master_token {
+            // The master token only manages API keys; all other calls need
+            // an API key with the required role
+            Some(ref master_token)
+                if constant_time_eq(master_token.as_bytes(), auth.bearer_token.as_bytes()) =>
+            {
+                return if role == AdminRole::Authorize {
+                    Ok("master".to_owned())
+                } else {
+                    Err(json_rpc_error(
+                        JSON_RPC_UNAUTHORIZED_ERROR,
+                        "the master token only grants the `authorize` role".to_owned(),
+                    ))
+                };
             }
-            Err(_) => {
+            Some(_) => (),
+            // Without a master token, access control is disabled; API keys
+            // can't be managed then, since they would not be checked anyway
+            None if role != AdminRole::Authorize => return Ok("anonymous".to_owned()),
+            None => {
                 return Err(json_rpc_error(
                     JSON_RPC_UNAUTHORIZED_ERROR,
-                    "internal error".to_owned(),
+                    format!("{} is not set", GRAPH_MASTER_TOKEN_VAR),
                 ))
             }
         }
-        Ok(())
-    }
-
-    fn check_subgraph_auth(
-        &self,
-        subgraph_name: String,
-        bearer_token: String,
-    ) -> Result<(), jsonrpc_core::Error> {
-        if !should_check_auth() {
-            return Ok(());
-        }
 
-        let is_authd = self
+        let logger = self.logger.clone();
+        let key = self
             .store
-            .check_subgraph_name_access_token(subgraph_name, bearer_token)
+            .find_api_key_by_token_hash(hash_api_token(&auth.bearer_token))
             .map_err(move |e| {
-                error!(self.logger, "Failed to check access token: {}", e);
+                error!(logger, "Failed to look up API key: {}", e);
                 json_rpc_error(JSON_RPC_INTERNAL_ERROR, "database error".to_owned())
+            })?
+            .ok_or_else(|| {
+                json_rpc_error(
+                    JSON_RPC_UNAUTHORIZED_ERROR,
+                    "invalid access token".to_owned(),
+                )
             })?;
 
-        if is_authd {
-            return Ok(());
+        if !key.is_active(Utc::now()) {
+            return Err(json_rpc_error(
+                JSON_RPC_UNAUTHORIZED_ERROR,
+                "access token has expired or was revoked".to_owned(),
+            ));
         }
 
-        return Err(json_rpc_error(
-            JSON_RPC_UNAUTHORIZED_ERROR,
-            "invalid access token".to_owned(),
-        ));
+        let allowed = if subgraph_names.is_empty() {
+            key.allows(role, None)
+        } else {
+            subgraph_names
+                .iter()
+                .all(|name| key.allows(role, Some(name)))
+        };
+
+        if allowed {
+            Ok(key.id)
+        } else {
+            Err(json_rpc_error(
+                JSON_RPC_UNAUTHORIZED_ERROR,
+                format!("access token does not grant the `{}` role", role),
+            ))
+        }
+    }
+
+    /// Authorizes an admin API call like `authorize` does, and records calls
+    /// that are rejected in the audit log.
+    ///
+    /// Only calls that change something are audited; read-only calls like
+    /// `subgraph_list` use `authorize` directly, so that polling them doesn't
+    /// fill up the audit log.
+    fn begin_call(
+        &self,
+        method: &str,
+        params: &fmt::Display,
+        auth: &AuthorizationHeader,
+        role: AdminRole,
+        subgraph_names: &[&str],
+    ) -> Result<String, jsonrpc_core::Error> {
+        info!(self.logger, "Received {} request", method; "params" => params.to_string());
+
+        self.authorize(auth, role, subgraph_names).map_err(|e| {
+            audit_call(
+                &*self.store,
+                &self.logger,
+                AdminAuditEntry {
+                    caller: "unauthorized".to_owned(),
+                    method: method.to_owned(),
+                    params: params.to_string(),
+                    outcome: e.message.clone(),
+                },
+            );
+            e
+        })
+    }
+
+    /// Records the outcome of an authorized admin API call in the audit log.
+    fn finish_call<T>(
+        &self,
+        caller: String,
+        method: &str,
+        params: String,
+        result: Result<T, jsonrpc_core::Error>,
+    ) -> Result<T, jsonrpc_core::Error> {
+        audit_call(
+            &*self.store,
+            &self.logger,
+            AdminAuditEntry {
+                caller,
+                method: method.to_owned(),
+                params,
+                outcome: call_outcome(&result),
+            },
+        );
+        result
     }
 
     /// Handler for the `subgraph_deploy` endpoint.
@@ -131,14 +320,21 @@ where
         params: SubgraphDeployParams,
         auth: AuthorizationHeader,
     ) -> Box<Future<Item = Value, Error = jsonrpc_core::Error> + Send> {
-        let logger = self.logger.clone();
-
-        info!(logger, "Received subgraph_deploy request"; "params" => params.to_string());
-
-        if let Err(e) = self.check_subgraph_auth(params.name.clone(), auth.bearer_token) {
-            return Box::new(future::err(e));
-        }
+        let caller = match self.begin_call(
+            "subgraph_deploy",
+            &params,
+            &auth,
+            AdminRole::Deploy,
+            &[params.name.as_str()],
+        ) {
+            Ok(caller) => caller,
+            Err(e) => return Box::new(future::err(e)),
+        };
 
+        let logger = self.logger.clone();
+        let store = self.store.clone();
+        let audit_logger = self.logger.clone();
+        let audit_params = params.to_string();
         let routes = subgraph_routes(&params.name, http_port, ws_port);
         Box::new(
             self.provider
@@ -150,6 +346,18 @@ where
                     } else {
                         json_rpc_error(JSON_RPC_DEPLOY_ERROR, e.to_string())
                     }
+                }).then(move |result| {
+                    audit_call(
+                        &*store,
+                        &audit_logger,
+                        AdminAuditEntry {
+                            caller,
+                            method: "subgraph_deploy".to_owned(),
+                            params: audit_params,
+                            outcome: call_outcome(&result),
+                        },
+                    );
+                    result
                 }).map(move |_| routes),
         )
     }
@@ -160,14 +368,21 @@ where
         params: SubgraphRemoveParams,
         auth: AuthorizationHeader,
     ) -> Box<Future<Item = Value, Error = jsonrpc_core::Error> + Send> {
-        let logger = self.logger.clone();
-
-        info!(logger, "Received subgraph_remove request"; "params" => params.to_string());
-
-        if let Err(e) = self.check_subgraph_auth(params.name.clone(), auth.bearer_token) {
-            return Box::new(future::err(e));
-        }
+        let caller = match self.begin_call(
+            "subgraph_remove",
+            &params,
+            &auth,
+            AdminRole::Remove,
+            &[params.name.as_str()],
+        ) {
+            Ok(caller) => caller,
+            Err(e) => return Box::new(future::err(e)),
+        };
 
+        let logger = self.logger.clone();
+        let store = self.store.clone();
+        let audit_logger = self.logger.clone();
+        let audit_params = params.to_string();
         Box::new(
             self.provider
                 .remove(params.name)
@@ -178,43 +393,356 @@ where
                     } else {
                         json_rpc_error(JSON_RPC_REMOVE_ERROR, e.to_string())
                     }
-                }).map(|_| Ok(Value::Null))
-                .flatten(),
+                }).then(move |result| {
+                    audit_call(
+                        &*store,
+                        &audit_logger,
+                        AdminAuditEntry {
+                            caller,
+                            method: "subgraph_remove".to_owned(),
+                            params: audit_params,
+                            outcome: call_outcome(&result),
+                        },
+                    );
+                    result
+                }).map(|_| Value::Null),
+        )
+    }
+
+    /// Handler for the `subgraph_list` endpoint.
+    ///
+    /// Returns all subgraph names with the status of their current and
+    /// pending versions.
+    ///
+    /// Requires the `read-status` role for all subgraph names.
+    fn list_handler(&self, auth: AuthorizationHeader) -> Result<Value, jsonrpc_core::Error> {
+        info!(self.logger, "Received subgraph_list request");
+        self.authorize(&auth, AdminRole::ReadStatus, &[])?;
+
+        let logger = self.logger.clone();
+        self.provider
+            .list()
+            .map(|names| serde_json::to_value(names).expect("failed to serialize subgraph list"))
+            .map_err(move |e| {
+                error!(logger, "subgraph_list failed: {}", e);
+                json_rpc_error(JSON_RPC_INTERNAL_ERROR, "internal error".to_owned())
+            })
+    }
+
+    /// Handler for the `subgraph_gc` endpoint.
+    ///
+    /// Removes the data of all subgraph versions that no subgraph name refers
+    /// to and returns these versions. With `dry_run`, nothing is removed.
+    ///
+    /// Requires the `remove` role for all subgraph names.
+    fn gc_handler(
+        &self,
+        params: SubgraphGcParams,
+        auth: AuthorizationHeader,
+    ) -> Box<Future<Item = Value, Error = jsonrpc_core::Error> + Send> {
+        let caller = match self.begin_call("subgraph_gc", &params, &auth, AdminRole::Remove, &[]) {
+            Ok(caller) => caller,
+            Err(e) => return Box::new(future::err(e)),
+        };
+
+        let logger = self.logger.clone();
+        let store = self.store.clone();
+        let audit_logger = self.logger.clone();
+        let audit_params = params.to_string();
+        Box::new(
+            self.provider
+                .collect_garbage(params.dry_run)
+                .map(|versions| {
+                    serde_json::to_value(versions).expect("failed to serialize subgraph versions")
+                }).map_err(move |e| {
+                    error!(logger, "subgraph_gc failed: {}", e);
+                    json_rpc_error(JSON_RPC_GC_ERROR, e.to_string())
+                }).then(move |result| {
+                    audit_call(
+                        &*store,
+                        &audit_logger,
+                        AdminAuditEntry {
+                            caller,
+                            method: "subgraph_gc".to_owned(),
+                            params: audit_params,
+                            outcome: call_outcome(&result),
+                        },
+                    );
+                    result
+                }),
+        )
+    }
+
+    /// Handler for the endpoints that act on the subgraph versions of a
+    /// subgraph name: `subgraph_pause`, `subgraph_resume`, `subgraph_reindex`
+    /// and `subgraph_rewind`.
+    ///
+    /// Requires the `deploy` role for the subgraph name.
+    fn versions_handler<T, F>(
+        &self,
+        method: &'static str,
+        error_code: i64,
+        name: String,
+        params: T,
+        auth: AuthorizationHeader,
+        op: F,
+    ) -> Box<Future<Item = Value, Error = jsonrpc_core::Error> + Send>
+    where
+        T: fmt::Display,
+        F: FnOnce(&P, T) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send>,
+    {
+        let caller = match self.begin_call(
+            method,
+            &params,
+            &auth,
+            AdminRole::Deploy,
+            &[name.as_str()],
+        ) {
+            Ok(caller) => caller,
+            Err(e) => return Box::new(future::err(e)),
+        };
+
+        let logger = self.logger.clone();
+        let store = self.store.clone();
+        let audit_logger = self.logger.clone();
+        let audit_params = params.to_string();
+        Box::new(
+            op(&*self.provider, params)
+                .map_err(move |e| {
+                    if let SubgraphProviderError::Unknown(e) = e {
+                        error!(logger, "{} failed: {}", method, e);
+                        json_rpc_error(error_code, "internal error".to_owned())
+                    } else {
+                        json_rpc_error(error_code, e.to_string())
+                    }
+                }).then(move |result| {
+                    audit_call(
+                        &*store,
+                        &audit_logger,
+                        AdminAuditEntry {
+                            caller,
+                            method: method.to_owned(),
+                            params: audit_params,
+                            outcome: call_outcome(&result),
+                        },
+                    );
+                    result
+                }).map(|_| Value::Null),
         )
     }
 
     /// Handler for the `subgraph_authorize` endpoint.
     ///
-    /// Sets the access tokens for a set of subgraph names.
+    /// Sets the access token of each of a set of subgraph names: creates an
+    /// API key with the token and the roles needed to deploy, remove and
+    /// inspect subgraphs under that name, and revokes the key of the name's
+    /// previous token. Returns the IDs of the keys by name.
     ///
-    /// Requires bearer authorization with the master token.
+    /// Requires the `authorize` role for all of the subgraph names.
     fn authorize_handler(
         &self,
         params: SubgraphAuthorizeParams,
         auth: AuthorizationHeader,
     ) -> Result<Value, jsonrpc_core::Error> {
-        info!(self.logger, "Received subgraph_authorize request"; "params" => params.to_string());
-        Self::require_master_token(auth)?;
+        let names = params
+            .subgraph_access_tokens
+            .keys()
+            .map(|name| name.as_str())
+            .collect::<Vec<_>>();
+        let caller = self.begin_call(
+            "subgraph_authorize",
+            &params,
+            &auth,
+            AdminRole::Authorize,
+            &names,
+        )?;
+
+        let result = params
+            .subgraph_access_tokens
+            .iter()
+            .map(|(subgraph_name, access_token)| {
+                let logger = self.logger.clone();
 
-        for (subgraph_name, access_token) in params.subgraph_access_tokens {
-            let logger = self.logger.clone();
+                self.store
+                    .authorize_subgraph_name(subgraph_name.clone(), access_token.clone())
+                    .map(|key_id| (subgraph_name.clone(), key_id))
+                    .map_err(move |e| {
+                        error!(logger, "Failed to authorize subgraph name: {}", e);
+                        json_rpc_error(JSON_RPC_INTERNAL_ERROR, "database error".to_owned())
+                    })
+            }).collect::<Result<BTreeMap<_, _>, _>>()
+            .map(|key_ids| jsonrpc_core::to_value(key_ids).unwrap());
+
+        self.finish_call(caller, "subgraph_authorize", params.to_string(), result)
+    }
 
-            self.store
-                .authorize_subgraph_name(subgraph_name, access_token)
+    /// Handler for the `api_key_create` endpoint.
+    ///
+    /// Creates an API key with a set of roles, optionally limited to a
+    /// subgraph name and to a period of time. Returns the ID of the key and
+    /// its token; the token cannot be retrieved again later.
+    ///
+    /// Requires the `authorize` role for the subgraph name of the key, or for
+    /// all subgraph names if the key is not limited to one.
+    fn api_key_create_handler(
+        &self,
+        params: ApiKeyCreateParams,
+        auth: AuthorizationHeader,
+    ) -> Result<Value, jsonrpc_core::Error> {
+        let caller = {
+            let names = params.subgraph_name.iter().map(|name| name.as_str());
+            self.begin_call(
+                "api_key_create",
+                &params,
+                &auth,
+                AdminRole::Authorize,
+                &names.collect::<Vec<_>>(),
+            )?
+        };
+
+        let (key, token) = ApiKey::generate(
+            params.roles.clone(),
+            params.subgraph_name.clone(),
+            params.expires_at,
+        );
+        let response = ApiKeyCreateResponse {
+            id: key.id.clone(),
+            token,
+        };
+        let logger = self.logger.clone();
+        let result = self
+            .store
+            .add_api_key(key)
+            .map(|_| serde_json::to_value(response).expect("failed to serialize API key"))
+            .map_err(move |e| {
+                error!(logger, "Failed to create API key: {}", e);
+                json_rpc_error(JSON_RPC_INTERNAL_ERROR, "database error".to_owned())
+            });
+
+        self.finish_call(caller, "api_key_create", params.to_string(), result)
+    }
+
+    /// Handler for the `api_key_revoke` endpoint.
+    ///
+    /// Revokes an API key, after which its token is no longer accepted.
+    ///
+    /// Requires the `authorize` role for the subgraph name of the key, or for
+    /// all subgraph names if the key is not limited to one or doesn't exist.
+    fn api_key_revoke_handler(
+        &self,
+        params: ApiKeyRevokeParams,
+        auth: AuthorizationHeader,
+    ) -> Result<Value, jsonrpc_core::Error> {
+        let logger = self.logger.clone();
+        let key = self
+            .store
+            .find_api_key(params.id.clone())
+            .map_err(move |e| {
+                error!(logger, "Failed to look up API key: {}", e);
+                json_rpc_error(JSON_RPC_INTERNAL_ERROR, "database error".to_owned())
+            })?;
+
+        // Callers only learn whether a key exists once they are authorized
+        let caller = {
+            let names = key
+                .iter()
+                .flat_map(|key| key.subgraph_name.iter())
+                .map(|name| name.as_str());
+            self.begin_call(
+                "api_key_revoke",
+                &params,
+                &auth,
+                AdminRole::Authorize,
+                &names.collect::<Vec<_>>(),
+            )?
+        };
+
+        let logger = self.logger.clone();
+        let revoked = match key {
+            Some(_) => self
+                .store
+                .revoke_api_key(params.id.clone())
                 .map_err(move |e| {
-                    error!(logger, "Failed to authorize subgraph name: {}", e);
+                    error!(logger, "Failed to revoke API key: {}", e);
                     json_rpc_error(JSON_RPC_INTERNAL_ERROR, "database error".to_owned())
-                })?;
-        }
+                }),
+            None => Ok(false),
+        };
+        let result = revoked.and_then(|revoked| {
+            if revoked {
+                Ok(Value::Null)
+            } else {
+                Err(json_rpc_error(
+                    JSON_RPC_API_KEY_ERROR,
+                    format!("API key not found or already revoked: {}", params.id),
+                ))
+            }
+        });
 
-        Ok(Value::Null)
+        self.finish_call(caller, "api_key_revoke", params.to_string(), result)
+    }
+
+    /// Handler for the `subgraph_logs` endpoint.
+    ///
+    /// Returns the most recent logs of the subgraph currently deployed under
+    /// a subgraph name, newest first, filtered by level, time range and text.
+    fn logs_handler(
+        &self,
+        params: SubgraphLogsParams,
+        auth: AuthorizationHeader,
+    ) -> Result<Value, jsonrpc_core::Error> {
+        info!(self.logger, "Received subgraph_logs request"; "params" => params.to_string());
+        self.authorize(&auth, AdminRole::ReadStatus, &[params.name.as_str()])?;
+        self.find_logs(params)
+    }
+
+    fn find_logs(&self, params: SubgraphLogsParams) -> Result<Value, jsonrpc_core::Error> {
+        let level = match params.level {
+            Some(level) => Some(parse_log_level(&level).ok_or_else(|| {
+                json_rpc_error(JSON_RPC_LOGS_ERROR, format!("invalid log level: {}", level))
+            })?),
+            None => None,
+        };
+
+        let logger = self.logger.clone();
+        let subgraph_id = self
+            .store
+            .read_subgraph_name(params.name.clone())
+            .map_err(move |e| {
+                error!(logger, "Failed to read subgraph name: {}", e);
+                json_rpc_error(JSON_RPC_INTERNAL_ERROR, "database error".to_owned())
+            })?
+            .ok_or_else(|| {
+                json_rpc_error(
+                    JSON_RPC_LOGS_ERROR,
+                    format!("subgraph name not found: {}", params.name),
+                )
+            })?;
+
+        let logs = match subgraph_id {
+            Some(subgraph_id) => self.log_store.find_logs(
+                &subgraph_id,
+                &SubgraphLogFilter {
+                    level,
+                    from: params.from,
+                    to: params.to,
+                    text: params.text,
+                    first: Some(params.first.unwrap_or(DEFAULT_LOGS_FIRST)),
+                },
+            ),
+            // No subgraph is deployed under this name
+            None => vec![],
+        };
+
+        Ok(serde_json::to_value(logs).expect("failed to serialize subgraph logs"))
     }
 }
 
-impl<P, S> JsonRpcServerTrait<P, S> for JsonRpcServer<P, S>
+impl<P, S, L> JsonRpcServerTrait<P, S, L> for JsonRpcServer<P, S, L>
 where
     P: SubgraphProviderWithNames,
     S: Store,
+    L: SubgraphLogStore,
 {
     type Server = Server;
 
@@ -224,6 +752,7 @@ where
         ws_port: u16,
         provider: Arc<P>,
         store: Arc<S>,
+        log_store: Arc<L>,
         logger: Logger,
     ) -> Result<Self::Server, io::Error> {
         let logger = logger.new(o!("component" => "JsonRpcServer"));
@@ -237,10 +766,15 @@ where
 
         let mut handler = MetaIoHandler::with_compatibility(Compatibility::Both);
 
+        let master_token =
+            env::var_os(GRAPH_MASTER_TOKEN_VAR).map(|s| s.to_str().unwrap().to_owned());
+
         let arc_self = Arc::new(JsonRpcServer {
             provider,
             store,
+            log_store,
             logger,
+            master_token,
         });
         // `subgraph_deploy` handler.
         let me = arc_self.clone();
@@ -262,6 +796,100 @@ where
                 .and_then(move |params| me.remove_handler(params, auth))
         });
 
+        // `subgraph_list` handler.
+        let me = arc_self.clone();
+        handler.add_method_with_meta("subgraph_list", move |_params: Params, auth| {
+            future::result(me.list_handler(auth))
+        });
+
+        // `subgraph_pause` handler.
+        let me = arc_self.clone();
+        handler.add_method_with_meta("subgraph_pause", move |params: Params, auth| {
+            let me = me.clone();
+            params
+                .parse()
+                .into_future()
+                .and_then(move |params: SubgraphNameParams| {
+                    me.versions_handler(
+                        "subgraph_pause",
+                        JSON_RPC_PAUSE_ERROR,
+                        params.name.clone(),
+                        params,
+                        auth,
+                        |provider, params| provider.pause(params.name),
+                    )
+                })
+        });
+
+        // `subgraph_resume` handler.
+        let me = arc_self.clone();
+        handler.add_method_with_meta("subgraph_resume", move |params: Params, auth| {
+            let me = me.clone();
+            params
+                .parse()
+                .into_future()
+                .and_then(move |params: SubgraphNameParams| {
+                    me.versions_handler(
+                        "subgraph_resume",
+                        JSON_RPC_RESUME_ERROR,
+                        params.name.clone(),
+                        params,
+                        auth,
+                        |provider, params| provider.resume(params.name),
+                    )
+                })
+        });
+
+        // `subgraph_reindex` handler.
+        let me = arc_self.clone();
+        handler.add_method_with_meta("subgraph_reindex", move |params: Params, auth| {
+            let me = me.clone();
+            params
+                .parse()
+                .into_future()
+                .and_then(move |params: SubgraphNameParams| {
+                    me.versions_handler(
+                        "subgraph_reindex",
+                        JSON_RPC_REINDEX_ERROR,
+                        params.name.clone(),
+                        params,
+                        auth,
+                        |provider, params| provider.reindex(params.name),
+                    )
+                })
+        });
+
+        // `subgraph_rewind` handler.
+        let me = arc_self.clone();
+        handler.add_method_with_meta("subgraph_rewind", move |params: Params, auth| {
+            let me = me.clone();
+            params
+                .parse()
+                .into_future()
+                .and_then(move |params: SubgraphRewindParams| {
+                    me.versions_handler(
+                        "subgraph_rewind",
+                        JSON_RPC_REWIND_ERROR,
+                        params.name.clone(),
+                        params,
+                        auth,
+                        |provider, params| {
+                            provider.rewind(params.name, params.block_number, params.block_hash)
+                        },
+                    )
+                })
+        });
+
+        // `subgraph_gc` handler.
+        let me = arc_self.clone();
+        handler.add_method_with_meta("subgraph_gc", move |params: Params, auth| {
+            let me = me.clone();
+            params
+                .parse()
+                .into_future()
+                .and_then(move |params| me.gc_handler(params, auth))
+        });
+
         // `subgraph_authorize` handler.
         let me = arc_self.clone();
         handler.add_method_with_meta("subgraph_authorize", move |params: Params, auth| {
@@ -272,6 +900,36 @@ where
                 .and_then(move |params| me.authorize_handler(params, auth))
         });
 
+        // `subgraph_logs` handler.
+        let me = arc_self.clone();
+        handler.add_method_with_meta("subgraph_logs", move |params: Params, auth| {
+            let me = me.clone();
+            params
+                .parse()
+                .into_future()
+                .and_then(move |params| me.logs_handler(params, auth))
+        });
+
+        // `api_key_create` handler.
+        let me = arc_self.clone();
+        handler.add_method_with_meta("api_key_create", move |params: Params, auth| {
+            let me = me.clone();
+            params
+                .parse()
+                .into_future()
+                .and_then(move |params| me.api_key_create_handler(params, auth))
+        });
+
+        // `api_key_revoke` handler.
+        let me = arc_self.clone();
+        handler.add_method_with_meta("api_key_revoke", move |params: Params, auth| {
+            let me = me.clone();
+            params
+                .parse()
+                .into_future()
+                .and_then(move |params| me.api_key_revoke_handler(params, auth))
+        });
+
         /// Get the `Authorization: Bearer` header if present.
         fn auth_extractor(request: &Request) -> Option<AuthorizationHeader> {
             request
@@ -316,6 +974,12 @@ where
     }
 }
 
+/// Compares two byte strings in a time that only depends on their lengths,
+/// so that checking a guess of a secret doesn't reveal how much of it is right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
 fn should_check_auth() -> bool {
     match env::var(GRAPH_MASTER_TOKEN_VAR) {
         Err(env::VarError::NotPresent) => false,
@@ -323,6 +987,36 @@ fn should_check_auth() -> bool {
     }
 }
 
+/// Writes an entry to the audit log of admin API calls.
+///
+/// Failing to do so is logged but does not fail the call.
+fn audit_call<S: Store>(store: &S, logger: &Logger, entry: AdminAuditEntry) {
+    if let Err(e) = store.add_admin_audit_entry(entry) {
+        error!(logger, "Failed to write admin audit log entry: {}", e);
+    }
+}
+
+/// Describes the outcome of an admin API call for the audit log.
+fn call_outcome<T>(result: &Result<T, jsonrpc_core::Error>) -> String {
+    match result {
+        Ok(_) => "success".to_owned(),
+        Err(e) => e.message.clone(),
+    }
+}
+
+/// Parses a log level as written in subgraph logs, e.g. `warn`.
+fn parse_log_level(level: &str) -> Option<Level> {
+    match level.to_lowercase().as_str() {
+        "critical" => Some(Level::Critical),
+        "error" => Some(Level::Error),
+        "warn" | "warning" => Some(Level::Warning),
+        "info" => Some(Level::Info),
+        "debug" => Some(Level::Debug),
+        "trace" => Some(Level::Trace),
+        _ => None,
+    }
+}
+
 fn json_rpc_error(code: i64, message: String) -> jsonrpc_core::Error {
     jsonrpc_core::Error {
         code: jsonrpc_core::ErrorCode::ServerError(code),
@@ -365,3 +1059,297 @@ fn subgraph_routes(name: &str, http_port: u16, ws_port: u16) -> Value {
     map.insert("subscriptions", format!(":{}/name/{}", ws_port, name));
     jsonrpc_core::to_value(map).unwrap()
 }
+
+#[cfg(test)]
+mod tests {
+    use graph::prelude::*;
+    use graph::web3::types::H256;
+    use graph_mock::MockStore;
+    use std::collections::BTreeMap;
+
+    use super::{
+        ApiKeyCreateParams, ApiKeyRevokeParams, AuthorizationHeader, JsonRpcServer,
+        SubgraphAuthorizeParams,
+    };
+
+    struct TestProvider;
+
+    impl SubgraphProviderWithNames for TestProvider {
+        fn deploy(
+            &self,
+            _: String,
+            _: SubgraphId,
+        ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static> {
+            unimplemented!()
+        }
+
+        fn remove(
+            &self,
+            _: String,
+        ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static> {
+            unimplemented!()
+        }
+
+        fn list(&self) -> Result<Vec<SubgraphNameStatus>, SubgraphProviderError> {
+            Ok(vec![])
+        }
+
+        fn pause(
+            &self,
+            _: String,
+        ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static> {
+            unimplemented!()
+        }
+
+        fn resume(
+            &self,
+            _: String,
+        ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static> {
+            unimplemented!()
+        }
+
+        fn reindex(
+            &self,
+            _: String,
+        ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static> {
+            unimplemented!()
+        }
+
+        fn collect_garbage(
+            &self,
+            _: bool,
+        ) -> Box<Future<Item = Vec<UnusedSubgraphVersion>, Error = SubgraphProviderError> + Send>
+        {
+            unimplemented!()
+        }
+
+        fn rewind(
+            &self,
+            _: String,
+            _: u64,
+            _: Option<H256>,
+        ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static> {
+            unimplemented!()
+        }
+    }
+
+    struct TestLogStore;
+
+    impl SubgraphLogStore for TestLogStore {
+        fn add_log(&self, _: SubgraphLogEntry) {}
+
+        fn find_logs(&self, _: &SubgraphId, _: &SubgraphLogFilter) -> Vec<SubgraphLogEntry> {
+            vec![]
+        }
+
+        fn remove_logs(&self, _: &SubgraphId) {}
+    }
+
+    type TestServer = JsonRpcServer<TestProvider, MockStore, TestLogStore>;
+
+    fn server(master_token: Option<&str>) -> TestServer {
+        JsonRpcServer {
+            provider: Arc::new(TestProvider),
+            store: Arc::new(MockStore::new()),
+            log_store: Arc::new(TestLogStore),
+            logger: Logger::root(slog::Discard, o!()),
+            master_token: master_token.map(|token| token.to_owned()),
+        }
+    }
+
+    fn auth(token: &str) -> AuthorizationHeader {
+        AuthorizationHeader {
+            bearer_token: token.to_owned(),
+        }
+    }
+
+    fn create_params(roles: Vec<AdminRole>, subgraph_name: Option<&str>) -> ApiKeyCreateParams {
+        ApiKeyCreateParams {
+            roles,
+            subgraph_name: subgraph_name.map(|name| name.to_owned()),
+            expires_at: None,
+        }
+    }
+
+    /// Creates an API key with the master token and returns its ID and token.
+    fn create_key(
+        server: &TestServer,
+        roles: Vec<AdminRole>,
+        subgraph_name: Option<&str>,
+    ) -> (String, String) {
+        let response = server
+            .api_key_create_handler(create_params(roles, subgraph_name), auth("master"))
+            .expect("Failed to create API key");
+        (
+            response["id"].as_str().unwrap().to_owned(),
+            response["token"].as_str().unwrap().to_owned(),
+        )
+    }
+
+    fn last_audit_entry(server: &TestServer) -> AdminAuditEntry {
+        server.store.audit_log().last().cloned().unwrap()
+    }
+
+    #[test]
+    fn master_token_only_grants_the_authorize_role() {
+        let server = server(Some("master"));
+
+        let error = server.list_handler(auth("master")).unwrap_err();
+        assert_eq!(
+            error.message,
+            "the master token only grants the `authorize` role"
+        );
+
+        create_key(&server, vec![AdminRole::Deploy], None);
+        assert_eq!(last_audit_entry(&server).caller, "master");
+        assert_eq!(last_audit_entry(&server).outcome, "success");
+
+        let params = ApiKeyRevokeParams {
+            id: "unknown".to_owned(),
+        };
+        let error = server
+            .api_key_revoke_handler(params, auth("not the master token"))
+            .unwrap_err();
+        assert_eq!(error.message, "invalid access token");
+        assert_eq!(
+            last_audit_entry(&server),
+            AdminAuditEntry {
+                caller: "unauthorized".to_owned(),
+                method: "api_key_revoke".to_owned(),
+                params: "ApiKeyRevokeParams { id: \"unknown\" }".to_owned(),
+                outcome: "invalid access token".to_owned(),
+            }
+        );
+    }
+
+    #[test]
+    fn read_only_calls_are_not_audited() {
+        let server = server(Some("master"));
+        let (_, token) = create_key(&server, vec![AdminRole::ReadStatus], None);
+        let audit_entries = server.store.audit_log().len();
+
+        assert!(server.list_handler(auth(&token)).is_ok());
+        assert!(server.list_handler(auth("not a token")).is_err());
+        assert_eq!(server.store.audit_log().len(), audit_entries);
+    }
+
+    #[test]
+    fn api_keys_cannot_be_managed_without_a_master_token() {
+        let server = server(None);
+
+        assert!(server.list_handler(auth("")).is_ok());
+
+        let error = server
+            .api_key_create_handler(create_params(vec![AdminRole::Deploy], None), auth(""))
+            .unwrap_err();
+        assert_eq!(error.message, "GRAPH_MASTER_TOKEN is not set");
+
+        let mut tokens = BTreeMap::new();
+        tokens.insert("example".to_owned(), "token".to_owned());
+        let params = SubgraphAuthorizeParams {
+            subgraph_access_tokens: tokens,
+        };
+        assert!(server.authorize_handler(params, auth("")).is_err());
+        assert_eq!(last_audit_entry(&server).method, "subgraph_authorize");
+    }
+
+    #[test]
+    fn api_keys_only_grant_their_roles_for_their_subgraph_name() {
+        let server = server(Some("master"));
+        let (_, all_names) = create_key(&server, vec![AdminRole::ReadStatus], None);
+        let (id, one_name) = create_key(&server, vec![AdminRole::ReadStatus], Some("example"));
+
+        // Listing subgraphs requires the role for all subgraph names
+        assert!(server.list_handler(auth(&all_names)).is_ok());
+        assert!(server.list_handler(auth(&one_name)).is_err());
+
+        // Neither key may create or revoke keys
+        let params = create_params(vec![AdminRole::ReadStatus], Some("example"));
+        assert!(server
+            .api_key_create_handler(params, auth(&one_name))
+            .is_err());
+        let params = ApiKeyRevokeParams { id };
+        assert!(server
+            .api_key_revoke_handler(params, auth(&all_names))
+            .is_err());
+        assert_eq!(last_audit_entry(&server).caller, "unauthorized");
+    }
+
+    #[test]
+    fn revoked_api_keys_are_rejected() {
+        let server = server(Some("master"));
+        let (id, token) = create_key(&server, vec![AdminRole::ReadStatus], None);
+        assert!(server.list_handler(auth(&token)).is_ok());
+
+        let revoke = |id: &str| {
+            let params = ApiKeyRevokeParams { id: id.to_owned() };
+            server.api_key_revoke_handler(params, auth("master"))
+        };
+        assert!(revoke(&id).is_ok());
+        assert_eq!(
+            revoke(&id).unwrap_err().message,
+            format!("API key not found or already revoked: {}", id)
+        );
+        assert_eq!(
+            revoke("unknown").unwrap_err().message,
+            "API key not found or already revoked: unknown"
+        );
+        assert_eq!(last_audit_entry(&server).method, "api_key_revoke");
+
+        assert_eq!(
+            server.list_handler(auth(&token)).unwrap_err().message,
+            "access token has expired or was revoked"
+        );
+    }
+
+    #[test]
+    fn revoking_unknown_api_keys_requires_authorization() {
+        let server = server(Some("master"));
+        let (_, token) = create_key(&server, vec![AdminRole::Authorize], Some("example"));
+
+        // A key limited to a subgraph name can't tell which keys exist
+        let params = ApiKeyRevokeParams {
+            id: "unknown".to_owned(),
+        };
+        let error = server
+            .api_key_revoke_handler(params, auth(&token))
+            .unwrap_err();
+        assert_eq!(
+            error.message,
+            "access token does not grant the `authorize` role"
+        );
+        assert_eq!(last_audit_entry(&server).caller, "unauthorized");
+    }
+
+    #[test]
+    fn subgraph_authorize_replaces_the_previous_access_token() {
+        let server = server(Some("master"));
+        let authorize = |token: &str| {
+            let mut tokens = BTreeMap::new();
+            tokens.insert("example".to_owned(), token.to_owned());
+            let params = SubgraphAuthorizeParams {
+                subgraph_access_tokens: tokens,
+            };
+            let response = server
+                .authorize_handler(params, auth("master"))
+                .expect("Failed to authorize subgraph name");
+            response["example"].as_str().unwrap().to_owned()
+        };
+
+        let allowed = |token: &str| {
+            server
+                .authorize(&auth(token), AdminRole::ReadStatus, &["example"])
+                .is_ok()
+        };
+
+        // Sending the same access token again changes nothing
+        let first = authorize("first");
+        assert_eq!(authorize("first"), first);
+        assert!(allowed("first"));
+
+        let second = authorize("second");
+        assert_ne!(second, first);
+        assert!(!allowed("first"));
+        assert!(allowed("second"));
+    }
+}
@@ -0,0 +1,8 @@
+extern crate futures;
+extern crate graph;
+extern crate http;
+extern crate hyper;
+
+mod server;
+
+pub use self::server::{MetricsServeError, PrometheusMetricsServer};
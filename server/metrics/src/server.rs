@@ -0,0 +1,111 @@
+use http::header;
+use hyper;
+use hyper::service::service_fn_ok;
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use std::error::Error;
+use std::fmt;
+use std::net::{Ipv4Addr, SocketAddrV4};
+
+use graph::prelude::{MetricsServer as MetricsServerTrait, *};
+use graph::prometheus::{self, Encoder, TextEncoder};
+
+/// Errors that may occur when starting the server.
+#[derive(Debug)]
+pub enum MetricsServeError {
+    BindError(hyper::Error),
+}
+
+impl Error for MetricsServeError {
+    fn description(&self) -> &str {
+        "Failed to start the server"
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        None
+    }
+}
+
+impl fmt::Display for MetricsServeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MetricsServeError::BindError(e) => write!(f, "Failed to bind metrics server: {}", e),
+        }
+    }
+}
+
+impl From<hyper::Error> for MetricsServeError {
+    fn from(err: hyper::Error) -> Self {
+        MetricsServeError::BindError(err)
+    }
+}
+
+/// Serves the metrics of the default Prometheus registry in the Prometheus
+/// text format.
+fn metrics_response() -> Response<Body> {
+    let encoder = TextEncoder::new();
+    let mut buffer = vec![];
+
+    match encoder.encode(&prometheus::gather(), &mut buffer) {
+        Ok(()) => Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, encoder.format_type())
+            .body(Body::from(buffer))
+            .unwrap(),
+        Err(e) => Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .body(Body::from(format!("Failed to encode metrics: {}", e)))
+            .unwrap(),
+    }
+}
+
+/// Handles a request to the metrics server.
+fn handle_request(request: Request<Body>) -> Response<Body> {
+    match (request.method(), request.uri().path()) {
+        (&Method::GET, "/metrics") => metrics_response(),
+        _ => Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::from("Not found"))
+            .unwrap(),
+    }
+}
+
+/// A server that exports Prometheus metrics over a GET /metrics endpoint.
+pub struct PrometheusMetricsServer {
+    logger: Logger,
+}
+
+impl PrometheusMetricsServer {
+    /// Creates a new metrics server.
+    pub fn new(logger: &Logger) -> Self {
+        PrometheusMetricsServer {
+            logger: logger.new(o!("component" => "MetricsServer")),
+        }
+    }
+}
+
+impl MetricsServerTrait for PrometheusMetricsServer {
+    type ServeError = MetricsServeError;
+
+    fn serve(
+        &mut self,
+        port: u16,
+    ) -> Result<Box<Future<Item = (), Error = ()> + Send>, Self::ServeError> {
+        let logger = self.logger.clone();
+
+        info!(
+            logger,
+            "Starting metrics server at: http://localhost:{}/metrics", port
+        );
+
+        let addr = SocketAddrV4::new(Ipv4Addr::new(0, 0, 0, 0), port);
+
+        let new_service = || service_fn_ok(handle_request);
+
+        // Create a task to run the server and handle HTTP requests
+        let task = Server::try_bind(&addr.into())?
+            .serve(new_service)
+            .map_err(move |e| error!(logger, "Server error"; "error" => format!("{}", e)));
+
+        Ok(Box::new(task))
+    }
+}
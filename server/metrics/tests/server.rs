@@ -0,0 +1,70 @@
+extern crate futures;
+extern crate graph;
+extern crate graph_server_metrics;
+extern crate http;
+extern crate hyper;
+
+use http::StatusCode;
+use hyper::{Body, Client, Request};
+
+use graph::components::metrics::GRAPHQL_QUERY_ERRORS;
+use graph::prelude::*;
+
+use graph_server_metrics::PrometheusMetricsServer;
+
+#[test]
+fn exports_metrics() {
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+    runtime
+        .block_on(futures::lazy(|| {
+            let logger = slog::Logger::root(slog::Discard, o!());
+
+            let mut server = PrometheusMetricsServer::new(&logger);
+            let metrics_server = server.serve(8041).expect("Failed to start metrics server");
+
+            // Launch the server to handle a single request
+            tokio::spawn(metrics_server.fuse());
+
+            GRAPHQL_QUERY_ERRORS.inc();
+
+            let client = Client::new();
+            let request = Request::get("http://localhost:8041/metrics")
+                .body(Body::empty())
+                .unwrap();
+
+            // The response must include the metrics in the Prometheus text format
+            client
+                .request(request)
+                .and_then(|response| {
+                    assert_eq!(response.status(), StatusCode::OK);
+                    response.into_body().concat2()
+                }).map(|body| {
+                    let body = String::from_utf8(body.to_vec()).unwrap();
+                    assert!(body.contains("# TYPE graphql_query_errors_total counter"));
+                })
+        })).unwrap()
+}
+
+#[test]
+fn rejects_unknown_paths() {
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+    runtime
+        .block_on(futures::lazy(|| {
+            let logger = slog::Logger::root(slog::Discard, o!());
+
+            let mut server = PrometheusMetricsServer::new(&logger);
+            let metrics_server = server.serve(8042).expect("Failed to start metrics server");
+
+            // Launch the server to handle a single request
+            tokio::spawn(metrics_server.fuse());
+
+            let client = Client::new();
+            let request = Request::get("http://localhost:8042/")
+                .body(Body::empty())
+                .unwrap();
+
+            client.request(request).map(|response| {
+                assert_eq!(response.status(), StatusCode::NOT_FOUND);
+            })
+        })).unwrap()
+}
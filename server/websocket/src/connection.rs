@@ -8,6 +8,7 @@ use tokio_tungstenite::tungstenite::{Error as WsError, Message as WsMessage};
 use tokio_tungstenite::WebSocketStream;
 use uuid::Uuid;
 
+use graph::components::metrics::GRAPHQL_ACTIVE_SUBSCRIPTIONS;
 use graph::prelude::*;
 use graph::serde_json;
 
@@ -110,6 +111,7 @@ impl Operations {
     }
 
     fn insert(&mut self, id: String, guard: CancelGuard) {
+        GRAPHQL_ACTIVE_SUBSCRIPTIONS.inc();
         self.operations.insert(id, guard);
     }
 
@@ -119,6 +121,7 @@ impl Operations {
             Some(stopper) => {
                 // Cancel the subscription result stream.
                 stopper.cancel();
+                GRAPHQL_ACTIVE_SUBSCRIPTIONS.dec();
 
                 // Send a GQL_COMPLETE to indicate the operation is been completed.
                 send_message(
@@ -259,7 +262,7 @@ where
                         }
                     };
 
-                    // TODO Parse query variables and operation name
+                    // TODO Parse query variables
 
                     // Construct a subscription
                     let subscription = Subscription {
@@ -267,6 +270,7 @@ where
                             schema,
                             document: query,
                             variables: None,
+                            operation_name: payload.operation_name,
                         },
                     };
 
@@ -0,0 +1,4 @@
+/**************************************************************
+* DROP COLUMN
+**************************************************************/
+ALTER TABLE subgraph_names DROP COLUMN pending_subgraph_id;
@@ -0,0 +1,6 @@
+/**************************************************************
+* ADD COLUMN
+**************************************************************/
+-- Subgraph version (ID) that is being indexed in the background and that the
+-- subgraph name will switch to once it has caught up with the chain head.
+ALTER TABLE subgraph_names ADD COLUMN pending_subgraph_id VARCHAR;
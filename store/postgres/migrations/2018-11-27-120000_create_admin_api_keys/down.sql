@@ -0,0 +1,5 @@
+/**************************************************************
+* DROP TABLES
+**************************************************************/
+DROP TABLE admin_audit_log;
+DROP TABLE admin_api_keys;
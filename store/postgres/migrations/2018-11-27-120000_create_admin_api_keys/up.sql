@@ -0,0 +1,25 @@
+/**************************************************************
+* CREATE TABLE
+**************************************************************/
+-- API keys for the JSON-RPC admin server. Only hashes of the tokens are
+-- stored; plaintext `subgraph_names.access_token` values are converted to API
+-- keys when the store starts up.
+CREATE TABLE IF NOT EXISTS admin_api_keys (
+    id VARCHAR PRIMARY KEY,
+    token_hash VARCHAR NOT NULL UNIQUE,
+    roles VARCHAR[] NOT NULL,
+    subgraph_name VARCHAR,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    expires_at TIMESTAMP WITH TIME ZONE,
+    revoked_at TIMESTAMP WITH TIME ZONE
+);
+
+-- Audit log of calls to the JSON-RPC admin server.
+CREATE TABLE IF NOT EXISTS admin_audit_log (
+    id BIGSERIAL PRIMARY KEY,
+    timestamp TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    caller VARCHAR NOT NULL,
+    method VARCHAR NOT NULL,
+    params VARCHAR NOT NULL,
+    outcome VARCHAR NOT NULL
+);
@@ -0,0 +1,4 @@
+/**************************************************************
+* DROP COLUMN
+**************************************************************/
+ALTER TABLE admin_api_keys DROP COLUMN subgraph_name_token;
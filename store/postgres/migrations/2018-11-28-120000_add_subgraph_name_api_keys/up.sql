@@ -0,0 +1,34 @@
+/**************************************************************
+* CHECK FOR DUPLICATE ACCESS TOKENS
+**************************************************************/
+-- Tokens of API keys are unique, so plaintext access tokens that are shared
+-- by several subgraph names cannot all be converted to API keys. Rather than
+-- silently dropping tokens, refuse to migrate until every subgraph name that
+-- shares a token has been given a token of its own.
+DO $$
+DECLARE
+    shared_by VARCHAR;
+BEGIN
+    SELECT string_agg(names, '; ') INTO shared_by
+    FROM (
+        SELECT string_agg(subgraph_name, ', ' ORDER BY subgraph_name) AS names
+        FROM subgraph_names
+        WHERE access_token IS NOT NULL
+        GROUP BY access_token
+        HAVING count(*) > 1
+    ) AS duplicates;
+
+    IF shared_by IS NOT NULL THEN
+        RAISE 'subgraph names share access tokens, set a separate token for each of them first: %',
+            shared_by;
+    END IF;
+END;
+$$;
+
+/**************************************************************
+* ADD COLUMN
+**************************************************************/
+-- Whether an API key holds the access token of its subgraph name, as set with
+-- `subgraph_authorize`. Setting a new token revokes the key of the old one.
+ALTER TABLE admin_api_keys
+  ADD COLUMN subgraph_name_token BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,17 @@
+/**************************************************************
+* RECREATE DELETE TRIGGERS
+**************************************************************/
+DROP TRIGGER after_delete_trigger ON entities;
+DROP TRIGGER entity_removed ON entities;
+
+CREATE TRIGGER after_delete_trigger
+    AFTER DELETE
+    ON entities
+    FOR EACH ROW
+    EXECUTE PROCEDURE log_delete();
+
+CREATE TRIGGER entity_removed
+    AFTER DELETE
+    ON entities
+    FOR EACH ROW
+    EXECUTE PROCEDURE notify_entity_removed();
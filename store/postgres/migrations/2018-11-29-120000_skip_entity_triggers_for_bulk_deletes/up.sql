@@ -0,0 +1,24 @@
+/**************************************************************
+* RECREATE DELETE TRIGGERS
+*
+* Removing all entities of a subgraph at once would otherwise log
+* and notify each of them individually. Such bulk deletes set
+* `vars.bulk_delete` for their transaction to skip the triggers;
+* they clean up history and send notifications themselves.
+**************************************************************/
+DROP TRIGGER after_delete_trigger ON entities;
+DROP TRIGGER entity_removed ON entities;
+
+CREATE TRIGGER after_delete_trigger
+    AFTER DELETE
+    ON entities
+    FOR EACH ROW
+    WHEN (current_setting('vars.bulk_delete', TRUE) IS DISTINCT FROM 'true')
+    EXECUTE PROCEDURE log_delete();
+
+CREATE TRIGGER entity_removed
+    AFTER DELETE
+    ON entities
+    FOR EACH ROW
+    WHEN (current_setting('vars.bulk_delete', TRUE) IS DISTINCT FROM 'true')
+    EXECUTE PROCEDURE notify_entity_removed();
@@ -0,0 +1,9 @@
+/**************************************************************
+* DROP FUNCTIONS
+**************************************************************/
+DROP FUNCTION revert_to_block(VARCHAR, BIGINT);
+
+/**************************************************************
+* DROP COLUMN
+**************************************************************/
+ALTER TABLE event_meta_data DROP COLUMN block_number;
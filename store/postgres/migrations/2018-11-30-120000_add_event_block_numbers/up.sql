@@ -0,0 +1,68 @@
+/**************************************************************
+* ADD COLUMN
+**************************************************************/
+-- Number of the block that the changes of a transaction were made for, set
+-- by the store when transacting or reverting a block. NULL for changes that
+-- are not tied to a block and for changes from before this column existed.
+ALTER TABLE event_meta_data ADD COLUMN block_number BIGINT;
+
+/**************************************************************
+* REVERT TO BLOCK
+*
+* Revert all row store events of a subgraph from blocks after the
+* target block, newest first, and discard their history along with
+* the history of reverting them
+* Parameters: subgraph, target block number
+**************************************************************/
+CREATE OR REPLACE FUNCTION revert_to_block(subgraph_id VARCHAR, target_block_number BIGINT)
+    RETURNS VOID AS
+$$
+DECLARE
+    event_row RECORD;
+    first_block_number BIGINT;
+BEGIN
+    -- Events without a block number precede all events with one; they are
+    -- only safe to keep if the target block is not before the first block
+    -- with a number
+    SELECT MIN(event_meta_data.block_number) INTO first_block_number
+    FROM entity_history
+    JOIN event_meta_data ON
+        entity_history.event_id = event_meta_data.id
+    WHERE entity_history.subgraph = subgraph_id;
+
+    IF
+        (first_block_number IS NULL OR target_block_number < first_block_number - 1) AND
+        EXISTS (
+            SELECT 1
+            FROM entity_history
+            JOIN event_meta_data ON
+                entity_history.event_id = event_meta_data.id
+            WHERE entity_history.subgraph = subgraph_id AND
+                event_meta_data.block_number IS NULL
+        )
+    THEN
+        RAISE 'cannot revert changes made before block numbers were recorded';
+    END IF;
+
+    FOR event_row IN
+        SELECT
+            entity_history.event_id AS event_id
+        FROM entity_history
+        JOIN event_meta_data ON
+            entity_history.event_id = event_meta_data.id
+        WHERE event_meta_data.block_number > target_block_number AND
+            entity_history.subgraph = subgraph_id
+        GROUP BY
+            entity_history.event_id
+        ORDER BY entity_history.event_id DESC
+    LOOP
+        PERFORM revert_transaction(event_row.event_id::integer);
+
+        -- Deletes the history of the event as well
+        DELETE FROM event_meta_data WHERE id = event_row.event_id;
+    END LOOP;
+
+    -- Discard the history logged while reverting
+    DELETE FROM event_meta_data WHERE db_transaction_id = txid_current();
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,10 @@
+/**************************************************************
+* CREATE TABLE
+**************************************************************/
+-- Subgraphs that were paused with `subgraph_pause`, so that they stay paused
+-- when the node restarts. Rows are kept when the data of a subgraph is
+-- reindexed and only removed when the subgraph is resumed or deleted.
+CREATE TABLE IF NOT EXISTS paused_subgraphs (
+    id VARCHAR PRIMARY KEY,
+    paused_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
@@ -44,5 +44,37 @@ table! {
         subgraph_name -> Varchar,
         subgraph_id -> Nullable<Varchar>,
         access_token -> Nullable<Varchar>,
+        pending_subgraph_id -> Nullable<Varchar>,
+    }
+}
+
+table! {
+    admin_api_keys (id) {
+        id -> Varchar,
+        token_hash -> Varchar,
+        roles -> Array<Varchar>,
+        subgraph_name -> Nullable<Varchar>,
+        created_at -> Timestamptz,
+        expires_at -> Nullable<Timestamptz>,
+        revoked_at -> Nullable<Timestamptz>,
+        subgraph_name_token -> Bool,
+    }
+}
+
+table! {
+    admin_audit_log (id) {
+        id -> BigInt,
+        timestamp -> Timestamptz,
+        caller -> Varchar,
+        method -> Varchar,
+        params -> Varchar,
+        outcome -> Varchar,
+    }
+}
+
+table! {
+    paused_subgraphs (id) {
+        id -> Varchar,
+        paused_at -> Timestamptz,
     }
 }
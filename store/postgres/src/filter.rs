@@ -15,6 +15,9 @@ use graph::serde_json;
 use db_schema::entities;
 use models::SqlValue;
 
+/// Maximum length of the regular expression of a `_matches` filter.
+pub(crate) const MAX_REGEX_LENGTH: usize = 256;
+
 pub(crate) struct UnsupportedFilter {
     pub filter: String,
     pub value: Value,
@@ -89,6 +92,20 @@ impl IntoFilter for BigInt {
     }
 }
 
+impl IntoFilter for scalar::BigDecimal {
+    fn into_filter(self, attribute: String, op: &str) -> FilterExpression {
+        Box::new(
+            sql("(data -> ")
+                .bind::<Text, _>(attribute)
+                .sql("->> 'data')::numeric")
+                .sql(op)
+                // Go through the string representation for the same reason as
+                // for `BigInt` above.
+                .bind::<Numeric, _>(BigDecimal::from_str(&self.to_string()).unwrap()),
+        ) as FilterExpression
+    }
+}
+
 trait IntoArrayFilter<T>
 where
     T: 'static,
@@ -120,6 +137,81 @@ impl IntoArrayFilter<SqlValue> for Vec<SqlValue> {
     }
 }
 
+/// Escapes the characters that have a special meaning in SQL `LIKE` patterns.
+pub(crate) fn escape_like_pattern(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// Returns true if `s` can safely be embedded in SQL as part of a full-text
+/// search expression. Attribute names and text search configurations are
+/// embedded rather than bound, so that Postgres can match the expression
+/// against the full-text search indexes.
+pub(crate) fn is_safe_identifier(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Returns true if the language and attributes of a full-text search can
+/// safely be embedded in SQL.
+pub(crate) fn is_safe_full_text_search(language: &str, attributes: &[Attribute]) -> bool {
+    is_safe_identifier(language)
+        && attributes
+            .iter()
+            .all(|attribute| is_safe_identifier(attribute))
+}
+
+/// Builds the SQL expression for the `tsvector` of the given attributes,
+/// using the text search configuration `language`.
+///
+/// Full-text search indexes are created on exactly this expression.
+pub(crate) fn full_text_document(language: &str, attributes: &[Attribute]) -> String {
+    format!(
+        "to_tsvector('{}'::regconfig, {})",
+        language,
+        attributes
+            .iter()
+            .map(|attribute| format!("coalesce(data -> '{}' ->> 'data', '')", attribute))
+            .collect::<Vec<_>>()
+            .join(" || ' ' || ")
+    )
+}
+
+/// Returns an expression that ranks entities by how well they match the
+/// first full-text search in `filter`, if there is one.
+pub(crate) fn full_text_rank(
+    filter: &EntityFilter,
+) -> Option<Box<BoxableExpression<entities::table, Pg, SqlType = Float>>> {
+    match filter {
+        EntityFilter::And(filters) => filters.iter().filter_map(full_text_rank).next(),
+        EntityFilter::FullTextMatch(attributes, language, text)
+            if is_safe_full_text_search(language, attributes) =>
+        {
+            Some(Box::new(
+                sql::<Float>(&format!(
+                    "ts_rank({}, plainto_tsquery('{}'::regconfig, ",
+                    full_text_document(language, attributes),
+                    language
+                )).bind::<Text, _>(text.to_owned())
+                .sql("))"),
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Returns true if the filter matches regular expressions, which can take
+/// arbitrarily long to evaluate.
+pub(crate) fn has_regex_filter(filter: &EntityFilter) -> bool {
+    match filter {
+        EntityFilter::And(filters) | EntityFilter::Or(filters) => {
+            filters.iter().any(has_regex_filter)
+        }
+        EntityFilter::Matches(..) => true,
+        _ => false,
+    }
+}
+
 /// Adds `filter` to a `SELECT data FROM entities` statement.
 pub(crate) fn store_filter(
     query: BoxedSelectStatement<Jsonb, entities::table, Pg>,
@@ -143,6 +235,24 @@ fn build_filter(filter: EntityFilter) -> Result<FilterExpression, UnsupportedFil
             build_filter(filter).map(|filter_expr| Box::new(p.or(filter_expr)) as FilterExpression)
         }),
 
+        FullTextMatch(attributes, language, text) => {
+            if !is_safe_full_text_search(&language, &attributes) {
+                return Err(UnsupportedFilter {
+                    filter: "fulltext".to_owned(),
+                    value: Value::String(language),
+                });
+            }
+
+            Ok(Box::new(
+                sql(&format!(
+                    "{} @@ plainto_tsquery('{}'::regconfig, ",
+                    full_text_document(&language, &attributes),
+                    language
+                )).bind::<Text, _>(text)
+                .sql(")"),
+            ) as FilterExpression)
+        }
+
         Contains(..) | NotContains(..) => {
             let (attribute, contains, op, value) = match filter {
                 EntityFilter::Contains(attribute, value) => (attribute, true, " LIKE ", value),
@@ -171,7 +281,8 @@ fn build_filter(filter: EntityFilter) -> Result<FilterExpression, UnsupportedFil
                 | Value::Float(_)
                 | Value::Int(_)
                 | Value::Bool(_)
-                | Value::BigInt(_) => {
+                | Value::BigInt(_)
+                | Value::BigDecimal(_) => {
                     return Err(UnsupportedFilter {
                         filter: if contains { "contains" } else { "not_contains" }.to_owned(),
                         value,
@@ -189,6 +300,7 @@ fn build_filter(filter: EntityFilter) -> Result<FilterExpression, UnsupportedFil
 
             match value {
                 Value::BigInt(n) => Ok(n.into_filter(attribute, op)),
+                Value::BigDecimal(n) => Ok(n.into_filter(attribute, op)),
                 Value::Bool(b) => Ok(b.into_filter(attribute, op)),
                 Value::Bytes(b) => Ok(b.to_string().into_filter(attribute, op)),
                 Value::Float(n) => Ok(n.into_filter(attribute, op)),
@@ -217,6 +329,7 @@ fn build_filter(filter: EntityFilter) -> Result<FilterExpression, UnsupportedFil
 
             match value {
                 Value::BigInt(n) => Ok(n.into_filter(attribute, op)),
+                Value::BigDecimal(n) => Ok(n.into_filter(attribute, op)),
                 Value::Float(n) => Ok(n.into_filter(attribute, op)),
                 Value::Int(n) => Ok(n.into_filter(attribute, op)),
                 Value::String(s) => Ok(s.into_filter(attribute, op)),
@@ -236,11 +349,8 @@ fn build_filter(filter: EntityFilter) -> Result<FilterExpression, UnsupportedFil
             let op = " = ANY ";
 
             match values[0] {
-                Value::BigInt(_) => Ok(SqlValue::new_array(values).into_array_filter::<Numeric>(
-                    attribute,
-                    op,
-                    "::numeric",
-                )),
+                Value::BigInt(_) | Value::BigDecimal(_) => Ok(SqlValue::new_array(values)
+                    .into_array_filter::<Numeric>(attribute, op, "::numeric")),
                 Value::Bool(_) => Ok(SqlValue::new_array(values).into_array_filter::<Bool>(
                     attribute,
                     op,
@@ -287,6 +397,7 @@ fn build_filter(filter: EntityFilter) -> Result<FilterExpression, UnsupportedFil
                 Value::String(s) => Ok(format!("{}%", s).into_filter(attribute, op)),
                 Value::Bool(_)
                 | Value::BigInt(_)
+                | Value::BigDecimal(_)
                 | Value::Bytes(_)
                 | Value::Float(_)
                 | Value::Int(_)
@@ -304,6 +415,60 @@ fn build_filter(filter: EntityFilter) -> Result<FilterExpression, UnsupportedFil
             }
         }
 
+        ContainsNoCase(..) | StartsWithNoCase(..) | EndsWithNoCase(..) => {
+            let (attribute, (prefix, suffix), filter_name, value) = match filter {
+                ContainsNoCase(attribute, value) => {
+                    (attribute, ("%", "%"), "contains_nocase", value)
+                }
+                StartsWithNoCase(attribute, value) => {
+                    (attribute, ("", "%"), "starts_with_nocase", value)
+                }
+                EndsWithNoCase(attribute, value) => {
+                    (attribute, ("%", ""), "ends_with_nocase", value)
+                }
+                _ => unreachable!(),
+            };
+
+            match value {
+                Value::String(s) => Ok(format!("{}{}{}", prefix, escape_like_pattern(&s), suffix)
+                    .into_filter(attribute, " ILIKE ")),
+                Value::Bool(_)
+                | Value::BigInt(_)
+                | Value::BigDecimal(_)
+                | Value::Bytes(_)
+                | Value::Float(_)
+                | Value::Int(_)
+                | Value::List(_)
+                | Value::Null => {
+                    return Err(UnsupportedFilter {
+                        filter: filter_name.to_owned(),
+                        value,
+                    })
+                }
+            }
+        }
+
+        Matches(attribute, value) => match value {
+            Value::String(ref s) if s.len() > MAX_REGEX_LENGTH => Err(UnsupportedFilter {
+                filter: format!("matches (at most {} characters)", MAX_REGEX_LENGTH),
+                value: value.clone(),
+            }),
+            Value::String(s) => Ok(s.into_filter(attribute, " ~* ")),
+            Value::Bool(_)
+            | Value::BigInt(_)
+            | Value::BigDecimal(_)
+            | Value::Bytes(_)
+            | Value::Float(_)
+            | Value::Int(_)
+            | Value::List(_)
+            | Value::Null => {
+                return Err(UnsupportedFilter {
+                    filter: "matches".to_owned(),
+                    value,
+                })
+            }
+        },
+
         EndsWith(..) | NotEndsWith(..) => {
             let (attribute, op, value) = match filter {
                 EndsWith(attribute, value) => (attribute, " LIKE ", value),
@@ -315,6 +480,7 @@ fn build_filter(filter: EntityFilter) -> Result<FilterExpression, UnsupportedFil
                 Value::String(s) => Ok(format!("%{}", s).into_filter(attribute, op)),
                 Value::Bool(_)
                 | Value::BigInt(_)
+                | Value::BigDecimal(_)
                 | Value::Bytes(_)
                 | Value::Float(_)
                 | Value::Int(_)
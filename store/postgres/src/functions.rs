@@ -26,3 +26,8 @@ sql_function! {
     LookupAncestorBlock,
     (start_block_hash: Varchar, ancestor_count: BigInt) -> Nullable<Jsonb>
 }
+sql_function! {
+    revert_to_block,
+    RevertToBlock,
+    (subgraph_id: Text, target_block_number: BigInt)
+}
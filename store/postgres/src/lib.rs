@@ -8,8 +8,10 @@ extern crate failure;
 extern crate fallible_iterator;
 extern crate futures;
 extern crate graph;
+extern crate hex;
 extern crate postgres;
 extern crate serde;
+extern crate sha2;
 extern crate uuid;
 
 mod chain_head_listener;
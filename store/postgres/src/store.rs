@@ -1,18 +1,29 @@
-use diesel::dsl::sql;
+use diesel::dsl::{exists, sql};
 use diesel::pg::Pg;
 use diesel::pg::PgConnection;
 use diesel::prelude::*;
-use diesel::sql_types::Text;
+use diesel::sql_types::{Jsonb, Text};
 use diesel::{delete, insert_into, select, update};
 use failure::*;
-use filter::store_filter;
+use filter::{
+    escape_like_pattern, full_text_document, full_text_rank, has_regex_filter,
+    is_safe_full_text_search, is_safe_identifier, store_filter,
+};
 use futures::sync::mpsc::{channel, Sender};
+use hex;
+use sha2::{Digest, Sha256};
+use std::cmp;
 use std::collections::HashMap;
+use std::env;
 use std::sync::{Arc, Mutex, RwLock};
 use std::time::{Duration, Instant};
 use uuid::Uuid;
 
+use graph::chrono::{DateTime, Utc};
+use graph::components::metrics::STORE_TRANSACTION_DURATION;
 use graph::components::store::Store as StoreTrait;
+use graph::data::admin::SUBGRAPH_NAME_ROLES;
+use graph::data::subgraph::schema::SUBGRAPHS_ID;
 use graph::prelude::*;
 use graph::serde_json;
 use graph::web3::types::H256;
@@ -20,10 +31,54 @@ use graph::{tokio, tokio::timer::Interval};
 
 use chain_head_listener::ChainHeadUpdateListener;
 use entity_changes::EntityChangeListener;
-use functions::{attempt_chain_head_update, lookup_ancestor_block, revert_block, set_config};
+use functions::{
+    attempt_chain_head_update, lookup_ancestor_block, revert_block, revert_to_block, set_config,
+};
 
 embed_migrations!("./migrations");
 
+/// Returns the cast needed to aggregate an attribute of the given type.
+fn aggregate_cast(value_type: &ValueType) -> &'static str {
+    match value_type {
+        ValueType::Int => "::bigint",
+        ValueType::Float => "::float",
+        _ => "::numeric",
+    }
+}
+
+/// Parses the result of an aggregate function over an attribute of the given
+/// type, as returned by `Store::aggregate` in the form of text.
+fn aggregate_value(
+    function: &str,
+    value_type: &ValueType,
+    value: &serde_json::Value,
+) -> Result<Value, QueryExecutionError> {
+    let text = match value {
+        serde_json::Value::String(text) => text,
+        _ => return Ok(Value::Null),
+    };
+    let invalid = || {
+        QueryExecutionError::ResolveEntitiesError(format!(
+            "Invalid result of {} aggregation: {}",
+            function, text
+        ))
+    };
+
+    match (function, value_type) {
+        ("avg", ValueType::Int) | (_, ValueType::Float) => {
+            text.parse().map(Value::Float).map_err(|_| invalid())
+        }
+        ("avg", _) | (_, ValueType::BigDecimal) => {
+            text.parse().map(Value::BigDecimal).map_err(|_| invalid())
+        }
+        ("sum", ValueType::Int) | (_, ValueType::BigInt) => {
+            text.parse().map(Value::BigInt).map_err(|_| invalid())
+        }
+        (_, ValueType::Int) => text.parse().map(Value::Int).map_err(|_| invalid()),
+        _ => Err(invalid()),
+    }
+}
+
 /// Internal representation of a Store subscription.
 struct Subscription {
     pub entities: Vec<SubgraphEntityPair>,
@@ -49,6 +104,226 @@ fn initiate_schema(logger: &slog::Logger, conn: &PgConnection) {
     }
 }
 
+/// Converts the plaintext access tokens of subgraph names, which were stored
+/// before admin API keys existed, into API keys that only keep a hash of the
+/// token.
+///
+/// Returns the number of converted tokens.
+fn convert_plaintext_access_tokens(conn: &PgConnection) -> Result<usize, Error> {
+    use db_schema::subgraph_names::dsl::*;
+
+    conn.transaction(|| {
+        let tokens = subgraph_names
+            .select((subgraph_name, access_token))
+            .filter(access_token.is_not_null())
+            .load::<(String, Option<String>)>(conn)?;
+
+        for (name, token) in &tokens {
+            if let Some(token) = token {
+                let key = ApiKey::with_token(
+                    token,
+                    SUBGRAPH_NAME_ROLES.to_vec(),
+                    Some(name.clone()),
+                    None,
+                );
+                insert_api_key(conn, &key, true)?;
+            }
+        }
+
+        update(subgraph_names)
+            .set(access_token.eq::<Option<String>>(None))
+            .execute(conn)?;
+        Ok(tokens.len())
+    })
+}
+
+/// Columns of the `admin_api_keys` table that make up an `ApiKey`.
+type ApiKeyRow = (
+    String,
+    String,
+    Vec<String>,
+    Option<String>,
+    Option<DateTime<Utc>>,
+    Option<DateTime<Utc>>,
+);
+
+fn api_key_from_row(row: ApiKeyRow) -> ApiKey {
+    let (id, token_hash, roles, subgraph_name, expires_at, revoked_at) = row;
+    ApiKey {
+        id,
+        token_hash,
+        roles: roles.iter().filter_map(|role| role.parse().ok()).collect(),
+        subgraph_name,
+        expires_at,
+        revoked: revoked_at.is_some(),
+    }
+}
+
+/// Inserts an admin API key into the `admin_api_keys` table.
+///
+/// `is_subgraph_name_token` marks keys that hold the access token of their
+/// subgraph name.
+fn insert_api_key(
+    conn: &PgConnection,
+    key: &ApiKey,
+    is_subgraph_name_token: bool,
+) -> Result<(), Error> {
+    use db_schema::admin_api_keys::dsl::*;
+
+    let role_names = key
+        .roles
+        .iter()
+        .map(|role| role.as_str())
+        .collect::<Vec<_>>();
+
+    insert_into(admin_api_keys)
+        .values((
+            id.eq(&key.id),
+            token_hash.eq(&key.token_hash),
+            roles.eq(&role_names),
+            subgraph_name.eq(&key.subgraph_name),
+            expires_at.eq(&key.expires_at),
+            subgraph_name_token.eq(is_subgraph_name_token),
+        )).execute(conn)
+        .map(|_| ())
+        .map_err(Error::from)
+}
+
+/// Deletes all entities of a subgraph, their history and the subgraph's block
+/// pointer.
+///
+/// The entities are deleted in bulk, without the per-row triggers that log and
+/// announce each deletion; a single `AllRemoved` change is sent for the
+/// subgraph instead. Must be called in a transaction. Returns the number of
+/// deleted entities.
+fn delete_subgraph_data(conn: &PgConnection, subgraph_id: &SubgraphId) -> Result<usize, Error> {
+    use db_schema::{entities, subgraphs};
+
+    select(set_config("vars.bulk_delete", "true", true)).execute(conn)?;
+    let removed =
+        delete(entities::table.filter(entities::subgraph.eq(subgraph_id))).execute(conn)?;
+    select(set_config("vars.bulk_delete", "false", true)).execute(conn)?;
+
+    // None of the history can be reverted to once the subgraph starts over or
+    // is gone
+    diesel::sql_query("DELETE FROM entity_history WHERE subgraph = $1")
+        .bind::<Text, _>(subgraph_id)
+        .execute(conn)?;
+
+    delete(subgraphs::table.filter(subgraphs::id.eq(subgraph_id))).execute(conn)?;
+
+    if removed > 0 {
+        let change = EntityChange {
+            subgraph_id: subgraph_id.clone(),
+            entity_type: String::new(),
+            entity_id: String::new(),
+            operation: EntityChangeOperation::AllRemoved,
+        };
+
+        // Postgres delivers the notification once the transaction commits
+        diesel::sql_query("SELECT pg_notify('entity_changes', $1)")
+            .bind::<Text, _>(serde_json::to_string(&change)?)
+            .execute(conn)?;
+    }
+
+    Ok(removed)
+}
+
+/// Deletes the metadata of a subgraph from the subgraph of subgraphs, along
+/// with its history and whether it was paused.
+///
+/// The IDs of all metadata entities of a subgraph start with the subgraph ID,
+/// e.g. `<subgraph ID>-manifest`.
+fn delete_subgraph_metadata(conn: &PgConnection, subgraph_id: &SubgraphId) -> Result<(), Error> {
+    use db_schema::entities::dsl::*;
+    use db_schema::paused_subgraphs;
+
+    let id_pattern = format!("{}-%", escape_like_pattern(subgraph_id));
+
+    delete(
+        entities
+            .filter(subgraph.eq(SUBGRAPHS_ID))
+            .filter(id.eq(subgraph_id).or(id.like(&id_pattern))),
+    ).execute(conn)?;
+
+    diesel::sql_query(
+        "DELETE FROM entity_history \
+         WHERE subgraph = $1 AND (entity_id = $2 OR entity_id LIKE $3)",
+    ).bind::<Text, _>(SUBGRAPHS_ID)
+    .bind::<Text, _>(subgraph_id)
+    .bind::<Text, _>(&id_pattern)
+    .execute(conn)?;
+
+    delete(paused_subgraphs::table.filter(paused_subgraphs::id.eq(subgraph_id))).execute(conn)?;
+
+    Ok(())
+}
+
+/// Records the number of the block that the entity changes of the current
+/// transaction were made for, so that they can be reverted by block number.
+fn set_event_block_number(conn: &PgConnection, block_number: u64) -> Result<(), Error> {
+    diesel::sql_query(
+        "UPDATE event_meta_data SET block_number = $1 \
+         WHERE db_transaction_id = txid_current()",
+    ).bind::<diesel::sql_types::BigInt, _>(block_number as i64)
+    .execute(conn)
+    .map(|_| ())
+    .map_err(Error::from)
+}
+
+/// Runs `load` in a transaction with a statement timeout, if one is given,
+/// so that Postgres cancels it instead of working on it indefinitely.
+///
+/// The timeout is rounded down to milliseconds, but is at least one
+/// millisecond, since Postgres treats a timeout of zero as no timeout.
+fn with_statement_timeout<T, F>(
+    conn: &PgConnection,
+    timeout: Option<Duration>,
+    load: F,
+) -> Result<T, diesel::result::Error>
+where
+    F: FnOnce() -> Result<T, diesel::result::Error>,
+{
+    match timeout {
+        Some(timeout) => conn.transaction(|| {
+            let millis = timeout.as_secs() * 1000 + u64::from(timeout.subsec_millis());
+            diesel::sql_query(format!(
+                "SET LOCAL statement_timeout = '{}ms'",
+                millis.max(1)
+            )).execute(conn)?;
+            load()
+        }),
+        None => load(),
+    }
+}
+
+/// Combines the statement timeout of a query with the time left until its
+/// deadline. Fails if the deadline has already passed.
+fn statement_timeout(
+    timeout: Option<Duration>,
+    deadline: Option<Instant>,
+) -> Result<Option<Duration>, QueryExecutionError> {
+    match deadline {
+        Some(deadline) => {
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(QueryExecutionError::Timeout);
+            }
+            let remaining = deadline - now;
+            Ok(Some(timeout.map_or(remaining, |t| cmp::min(t, remaining))))
+        }
+        None => Ok(timeout),
+    }
+}
+
+/// Reports a failed query as a timeout if it was cancelled at its deadline.
+fn query_error(e: diesel::result::Error, deadline: Option<Instant>) -> QueryExecutionError {
+    match deadline {
+        Some(deadline) if Instant::now() >= deadline => QueryExecutionError::Timeout,
+        _ => QueryExecutionError::ResolveEntitiesError(e.to_string()),
+    }
+}
+
 /// Configuration for the Diesel/Postgres store.
 pub struct StoreConfig {
     pub url: String,
@@ -63,6 +338,7 @@ pub struct Store {
     url: String,
     network_name: String,
     genesis_block_ptr: EthereumBlockPointer,
+    regex_filter_timeout: Duration,
     pub conn: Arc<Mutex<PgConnection>>,
 }
 
@@ -84,12 +360,32 @@ impl Store {
         // Create the entities table (if necessary)
         initiate_schema(&logger, &conn);
 
+        // Only keep hashes of admin API tokens
+        match convert_plaintext_access_tokens(&conn) {
+            Ok(0) => (),
+            Ok(count) => info!(
+                logger,
+                "Converted subgraph name access tokens to API keys";
+                "count" => count
+            ),
+            Err(e) => error!(
+                logger,
+                "Failed to convert subgraph name access tokens to API keys: {}", e
+            ),
+        }
+
         // Listen to entity changes in Postgres
         let mut change_listener = EntityChangeListener::new(config.url.clone());
         let entity_changes = change_listener
             .take_event_stream()
             .expect("Failed to listen to entity change events in Postgres");
 
+        // Regular expressions from `_matches` filters can take arbitrarily
+        // long to evaluate; cancel queries using them after this many seconds
+        let regex_filter_timeout = env::var_os("GRAPH_REGEX_FILTER_TIMEOUT")
+            .map(|s| Duration::from_secs(s.to_str().unwrap().parse().unwrap()))
+            .unwrap_or(Duration::from_secs(10));
+
         // Create the store
         let mut store = Store {
             logger: logger.clone(),
@@ -98,6 +394,7 @@ impl Store {
             url: config.url.clone(),
             network_name: config.network_name.clone(),
             genesis_block_ptr: (net_identifiers.genesis_block_hash, 0u64).into(),
+            regex_filter_timeout,
             conn: Arc::new(Mutex::new(conn)),
         };
 
@@ -196,7 +493,8 @@ impl Store {
                            "entity_type" => &change.entity_type,
                            "entity_id" => &change.entity_id);
 
-            // Obtain IDs and senders of subscriptions matching the entity change
+            // Obtain IDs and senders of subscriptions matching the entity change;
+            // removing all entities of a subgraph affects every entity type
             let matches = subscriptions
                 .read()
                 .unwrap()
@@ -204,7 +502,12 @@ impl Store {
                 .filter(|(_, subscription)| {
                     subscription
                         .entities
-                        .contains(&(change.subgraph_id.clone(), change.entity_type.clone()))
+                        .iter()
+                        .any(|(subgraph_id, entity_type)| {
+                            *subgraph_id == change.subgraph_id
+                                && (change.operation == EntityChangeOperation::AllRemoved
+                                    || *entity_type == change.entity_type)
+                        })
                 }).map(|(id, subscription)| (id.clone(), subscription.sender.clone()))
                 .collect::<Vec<_>>();
 
@@ -470,49 +773,92 @@ impl Store {
 }
 
 impl StoreTrait for Store {
-    fn authorize_subgraph_name(&self, name: String, new_access_token: String) -> Result<(), Error> {
-        use db_schema::subgraph_names::dsl::*;
+    fn add_api_key(&self, key: ApiKey) -> Result<(), Error> {
+        insert_api_key(&*self.conn.lock().unwrap(), &key, false)
+    }
 
-        insert_into(subgraph_names)
-            .values((
-                subgraph_name.eq(&name),
-                subgraph_id.eq::<Option<SubgraphId>>(None),
-                access_token.eq(&new_access_token),
-            )).on_conflict(subgraph_name)
-            .do_update()
-            .set(access_token.eq(&new_access_token))
-            .execute(&*self.conn.lock().unwrap())
-            .map(|_| ())
+    fn authorize_subgraph_name(&self, name: String, token: String) -> Result<String, Error> {
+        use db_schema::admin_api_keys::dsl::*;
+
+        let conn = self.conn.lock().unwrap();
+        conn.transaction(|| {
+            // Setting the current token again changes nothing
+            let hash = hash_api_token(&token);
+            let current_key_id = admin_api_keys
+                .select(id)
+                .filter(token_hash.eq(&hash))
+                .filter(subgraph_name.eq(&name))
+                .filter(subgraph_name_token.eq(true))
+                .filter(revoked_at.is_null())
+                .first::<String>(&*conn)
+                .optional()?;
+            if let Some(current_key_id) = current_key_id {
+                return Ok(current_key_id);
+            }
+
+            update(
+                admin_api_keys
+                    .filter(subgraph_name.eq(&name))
+                    .filter(subgraph_name_token.eq(true))
+                    .filter(revoked_at.is_null()),
+            ).set(revoked_at.eq(Utc::now()))
+            .execute(&*conn)?;
+
+            let key = ApiKey::with_token(&token, SUBGRAPH_NAME_ROLES.to_vec(), Some(name), None);
+            insert_api_key(&*conn, &key, true)?;
+            Ok(key.id)
+        })
+    }
+
+    fn find_api_key(&self, key_id: String) -> Result<Option<ApiKey>, Error> {
+        use db_schema::admin_api_keys::dsl::*;
+
+        admin_api_keys
+            .select((id, token_hash, roles, subgraph_name, expires_at, revoked_at))
+            .filter(id.eq(&key_id))
+            .first::<ApiKeyRow>(&*self.conn.lock().unwrap())
+            .optional()
+            .map(|row| row.map(api_key_from_row))
             .map_err(Error::from)
     }
 
-    fn check_subgraph_name_access_token(
-        &self,
-        name: String,
-        untrusted_access_token: String,
-    ) -> Result<bool, Error> {
-        use db_schema::subgraph_names::dsl::*;
+    fn find_api_key_by_token_hash(&self, hash: String) -> Result<Option<ApiKey>, Error> {
+        use db_schema::admin_api_keys::dsl::*;
 
-        let real_access_token_opt = subgraph_names
-            .select(access_token)
-            .filter(subgraph_name.eq(&name))
-            .first::<Option<String>>(&*self.conn.lock().unwrap())?;
+        admin_api_keys
+            .select((id, token_hash, roles, subgraph_name, expires_at, revoked_at))
+            .filter(token_hash.eq(&hash))
+            .first::<ApiKeyRow>(&*self.conn.lock().unwrap())
+            .optional()
+            .map(|row| row.map(api_key_from_row))
+            .map_err(Error::from)
+    }
 
-        match real_access_token_opt {
-            None => {
-                debug!(
-                    self.logger,
-                    "Subgraph name has no associated access token. Access denied by default.";
-                    "subgraph_name" => &name
-                );
+    fn revoke_api_key(&self, key_id: String) -> Result<bool, Error> {
+        use db_schema::admin_api_keys::dsl::*;
 
-                Ok(false)
-            }
-            Some(real_access_token) => {
-                // Issue #451: make this constant-time
-                Ok(real_access_token == untrusted_access_token)
-            }
-        }
+        update(
+            admin_api_keys
+                .filter(id.eq(&key_id))
+                .filter(revoked_at.is_null()),
+        ).set(revoked_at.eq(Utc::now()))
+        .execute(&*self.conn.lock().unwrap())
+        .map(|rows| rows > 0)
+        .map_err(Error::from)
+    }
+
+    fn add_admin_audit_entry(&self, entry: AdminAuditEntry) -> Result<(), Error> {
+        use db_schema::admin_audit_log::dsl::*;
+
+        insert_into(admin_audit_log)
+            .values((
+                caller.eq(&entry.caller),
+                method.eq(&entry.method),
+                params.eq(&entry.params),
+                outcome.eq(&entry.outcome),
+            )).execute(&*self.conn.lock().unwrap())
+            .map(|_| ())
+            .map_err(Error::from)
     }
 
     fn read_all_subgraph_names(&self) -> Result<Vec<(String, Option<SubgraphId>)>, Error> {
@@ -558,6 +904,56 @@ impl StoreTrait for Store {
             .map_err(Error::from)
     }
 
+    fn read_all_pending_subgraph_ids(&self) -> Result<Vec<(String, SubgraphId)>, Error> {
+        use db_schema::subgraph_names::dsl::*;
+
+        subgraph_names
+            .select((subgraph_name, pending_subgraph_id))
+            .filter(pending_subgraph_id.is_not_null())
+            .load::<(String, Option<String>)>(&*self.conn.lock().unwrap())
+            .map(|names| {
+                names
+                    .into_iter()
+                    .filter_map(|(name, id_opt)| id_opt.map(|id| (name, id)))
+                    .collect()
+            }).map_err(Error::from)
+    }
+
+    fn write_pending_subgraph_id(
+        &self,
+        name: String,
+        id_opt: Option<SubgraphId>,
+    ) -> Result<(), Error> {
+        use db_schema::subgraph_names::dsl::*;
+
+        insert_into(subgraph_names)
+            .values((
+                subgraph_name.eq(&name),
+                subgraph_id.eq::<Option<SubgraphId>>(None),
+                pending_subgraph_id.eq(&id_opt),
+            )).on_conflict(subgraph_name)
+            .do_update()
+            .set(pending_subgraph_id.eq(&id_opt))
+            .execute(&*self.conn.lock().unwrap())
+            .map_err(Error::from)
+            .map(|_| ())
+    }
+
+    fn promote_pending_subgraph_id(&self, name: String, id: SubgraphId) -> Result<bool, Error> {
+        use db_schema::subgraph_names::dsl::*;
+
+        update(
+            subgraph_names
+                .filter(subgraph_name.eq(&name))
+                .filter(pending_subgraph_id.eq(&id)),
+        ).set((
+            subgraph_id.eq(&id),
+            pending_subgraph_id.eq::<Option<SubgraphId>>(None),
+        )).execute(&*self.conn.lock().unwrap())
+        .map(|rows| rows > 0)
+        .map_err(Error::from)
+    }
+
     fn delete_subgraph_name(&self, name: String) -> Result<(), Error> {
         use db_schema::subgraph_names::dsl::*;
 
@@ -588,6 +984,151 @@ impl StoreTrait for Store {
             .map(|_| ())
     }
 
+    fn read_all_subgraph_ids(&self) -> Result<Vec<SubgraphId>, Error> {
+        use db_schema::{entities, subgraphs};
+
+        let conn = self.conn.lock().unwrap();
+
+        // Subgraphs that have not started processing blocks yet may only
+        // have metadata
+        let mut ids = subgraphs::table
+            .select(subgraphs::id)
+            .load::<SubgraphId>(&*conn)?;
+        ids.extend(
+            entities::table
+                .select(entities::id)
+                .filter(entities::subgraph.eq(SUBGRAPHS_ID))
+                .filter(entities::entity.eq("Subgraph"))
+                .load::<SubgraphId>(&*conn)?,
+        );
+        ids.sort();
+        ids.dedup();
+        Ok(ids)
+    }
+
+    fn count_entities(&self, subgraph_id: SubgraphId) -> Result<u64, Error> {
+        use db_schema::entities::dsl::*;
+
+        entities
+            .filter(subgraph.eq(&subgraph_id))
+            .count()
+            .get_result::<i64>(&*self.conn.lock().unwrap())
+            .map(|count| count as u64)
+            .map_err(Error::from)
+    }
+
+    fn remove_unused_subgraph(&self, subgraph_id: SubgraphId) -> Result<Option<u64>, Error> {
+        use db_schema::subgraph_names;
+
+        // Deleting a subgraph can take a long time; use a separate connection
+        // so other store operations aren't blocked in the meantime
+        let conn = PgConnection::establish(self.url.as_str())?;
+
+        conn.transaction::<_, Error, _>(|| {
+            // Keep subgraph names from being switched to the subgraph until it
+            // is gone; this only holds up changes to subgraph names
+            diesel::sql_query("LOCK TABLE subgraph_names IN SHARE MODE").execute(&conn)?;
+
+            let in_use = select(exists(
+                subgraph_names::table.filter(
+                    subgraph_names::subgraph_id
+                        .eq(&subgraph_id)
+                        .or(subgraph_names::pending_subgraph_id.eq(&subgraph_id)),
+                ),
+            )).get_result::<bool>(&conn)?;
+            if in_use {
+                return Ok(None);
+            }
+
+            let entity_count = delete_subgraph_data(&conn, &subgraph_id)?;
+            delete_subgraph_metadata(&conn, &subgraph_id)?;
+            Ok(Some(entity_count as u64))
+        })
+    }
+
+    fn remove_subgraph_data(&self, subgraph_id: SubgraphId) -> Result<(), Error> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.transaction::<(), Error, _>(|| delete_subgraph_data(&*conn, &subgraph_id).map(|_| ()))
+    }
+
+    fn set_subgraph_paused(&self, subgraph_id: SubgraphId, paused: bool) -> Result<(), Error> {
+        use db_schema::paused_subgraphs::dsl::*;
+
+        let conn = self.conn.lock().unwrap();
+        if paused {
+            insert_into(paused_subgraphs)
+                .values(id.eq(&subgraph_id))
+                .on_conflict(id)
+                .do_nothing()
+                .execute(&*conn)?;
+        } else {
+            delete(paused_subgraphs.filter(id.eq(&subgraph_id))).execute(&*conn)?;
+        }
+        Ok(())
+    }
+
+    fn is_subgraph_paused(&self, subgraph_id: SubgraphId) -> Result<bool, Error> {
+        use db_schema::paused_subgraphs::dsl::*;
+
+        select(exists(paused_subgraphs.filter(id.eq(&subgraph_id))))
+            .get_result::<bool>(&*self.conn.lock().unwrap())
+            .map_err(Error::from)
+    }
+
+    fn create_full_text_indexes(
+        &self,
+        subgraph_id: SubgraphId,
+        definitions: Vec<FullTextDefinition>,
+    ) -> Result<(), Error> {
+        // Building an index can take a long time; use a separate connection
+        // so other store operations aren't blocked in the meantime (and
+        // because `CREATE INDEX CONCURRENTLY` can't run in a transaction)
+        let conn = PgConnection::establish(self.url.as_str())?;
+
+        for definition in definitions {
+            if !is_safe_identifier(&subgraph_id)
+                || !is_safe_identifier(&definition.entity_type)
+                || !is_safe_full_text_search(&definition.language, &definition.fields)
+            {
+                return Err(format_err!(
+                    "Invalid full-text search `{}` in subgraph `{}`",
+                    definition.name,
+                    subgraph_id
+                ));
+            }
+
+            // The index is created on the same expression that full-text
+            // search filters use, restricted to the entities of the subgraph
+            // and type being searched
+            let document = full_text_document(&definition.language, &definition.fields);
+
+            // Index names must be stable across releases so that existing
+            // indexes are found again; Postgres truncates names to 63 bytes
+            let hash = hex::encode(Sha256::digest(
+                format!("{}\n{}\n{}", subgraph_id, definition.entity_type, document).as_bytes(),
+            ));
+            let index_name = format!("entities_fulltext_{}", &hash[..32]);
+
+            debug!(
+                self.logger,
+                "Create full-text search index";
+                "subgraph_id" => &subgraph_id,
+                "search" => &definition.name,
+                "entity_type" => &definition.entity_type,
+                "index" => &index_name
+            );
+
+            diesel::sql_query(format!(
+                "CREATE INDEX CONCURRENTLY IF NOT EXISTS {} ON entities USING gin ({}) \
+                 WHERE subgraph = '{}' AND entity = '{}'",
+                index_name, document, subgraph_id, definition.entity_type
+            )).execute(&conn)?;
+        }
+
+        Ok(())
+    }
+
     fn block_ptr(&self, subgraph_id: SubgraphId) -> Result<EthereumBlockPointer, Error> {
         use db_schema::subgraphs::dsl::*;
 
@@ -621,6 +1162,17 @@ impl StoreTrait for Store {
             .select(data)
             .into_boxed::<Pg>();
 
+        // Rank full-text search results by relevance, unless an explicit
+        // order is requested
+        let rank = query.filter.as_ref().and_then(full_text_rank);
+
+        let timeout = query
+            .filter
+            .as_ref()
+            .filter(|filter| has_regex_filter(filter))
+            .map(|_| self.regex_filter_timeout);
+        let deadline = query.deadline;
+
         // Add specified filter to query
         if let Some(filter) = query.filter {
             diesel_query = store_filter(diesel_query, filter).map_err(|e| {
@@ -638,6 +1190,7 @@ impl StoreTrait for Store {
                 }).unwrap_or("ASC");
             let cast_type = match value_type {
                 ValueType::BigInt => "::numeric",
+                ValueType::BigDecimal => "::numeric",
                 ValueType::Boolean => "::boolean",
                 ValueType::Bytes => "",
                 ValueType::Float => "::float",
@@ -654,6 +1207,8 @@ impl StoreTrait for Store {
                     .sql(direction)
                     .sql(" NULLS LAST"),
             );
+        } else if let Some(rank) = rank {
+            diesel_query = diesel_query.order(rank.desc());
         }
 
         // Add range filter to query
@@ -664,15 +1219,113 @@ impl StoreTrait for Store {
         }
 
         // Process results; deserialize JSON data
-        diesel_query
-            .load::<serde_json::Value>(&*self.conn.lock().unwrap())
-            .map(|values| {
-                values
-                    .into_iter()
-                    .map(|value| {
-                        serde_json::from_value::<Entity>(value).expect("Error parsing entity JSON")
-                    }).collect()
-            }).map_err(|e| QueryExecutionError::ResolveEntitiesError(e.to_string()))
+        let conn = self.conn.lock().unwrap();
+        let timeout = statement_timeout(timeout, deadline)?;
+        let values = with_statement_timeout(&*conn, timeout, || {
+            diesel_query.load::<serde_json::Value>(&*conn)
+        }).map_err(|e| query_error(e, deadline))?;
+
+        Ok(values
+            .into_iter()
+            .map(|value| {
+                serde_json::from_value::<Entity>(value).expect("Error parsing entity JSON")
+            })
+            .collect())
+    }
+
+    fn aggregate(
+        &self,
+        query: EntityQuery,
+        attributes: Vec<(Attribute, ValueType)>,
+    ) -> Result<EntityAggregates, QueryExecutionError> {
+        use db_schema::entities::dsl::*;
+
+        // Attribute names are embedded in the query below
+        if let Some((attribute, _)) = attributes
+            .iter()
+            .find(|(attribute, _)| !is_safe_identifier(attribute))
+        {
+            return Err(QueryExecutionError::ResolveEntitiesError(format!(
+                "Cannot aggregate attribute: {}",
+                attribute
+            )));
+        }
+
+        // Compute all aggregates at once, as a JSON object with one object
+        // per aggregate function; values are converted to text so that large
+        // numbers don't lose precision on their way through JSON
+        let keys = attributes
+            .iter()
+            .map(|(attribute, _)| format!("'{}'", attribute))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let aggregate_object = |function: &str| {
+            format!(
+                "jsonb_object(ARRAY[{}]::text[], ARRAY[{}]::text[])",
+                keys,
+                attributes
+                    .iter()
+                    .map(|(attribute, value_type)| format!(
+                        "{}((data -> '{}' ->> 'data'){})::text",
+                        function,
+                        attribute,
+                        aggregate_cast(value_type)
+                    ))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        };
+        let aggregates = format!(
+            "jsonb_build_object('count', count(*), 'sum', {}, 'min', {}, 'max', {}, 'avg', {})",
+            aggregate_object("sum"),
+            aggregate_object("min"),
+            aggregate_object("max"),
+            aggregate_object("avg"),
+        );
+
+        let mut diesel_query = entities
+            .filter(entity.eq(query.entity_type))
+            .filter(subgraph.eq(query.subgraph_id))
+            .select(sql::<Jsonb>(&aggregates))
+            .into_boxed::<Pg>();
+
+        let timeout = query
+            .filter
+            .as_ref()
+            .filter(|filter| has_regex_filter(filter))
+            .map(|_| self.regex_filter_timeout);
+        let deadline = query.deadline;
+
+        // Aggregate over the same entities that `find` would return
+        if let Some(filter) = query.filter {
+            diesel_query = store_filter(diesel_query, filter).map_err(|e| {
+                QueryExecutionError::FilterNotSupportedError(format!("{}", e.value), e.filter)
+            })?;
+        }
+
+        let conn = self.conn.lock().unwrap();
+        let timeout = statement_timeout(timeout, deadline)?;
+        let result = with_statement_timeout(&*conn, timeout, || {
+            diesel_query.first::<serde_json::Value>(&*conn)
+        }).map_err(|e| query_error(e, deadline))?;
+
+        let values = |function: &str| -> Result<HashMap<Attribute, Value>, QueryExecutionError> {
+            attributes
+                .iter()
+                .map(|(attribute, value_type)| {
+                    aggregate_value(function, value_type, &result[function][attribute.as_str()])
+                        .map(|value| (attribute.clone(), value))
+                })
+                .collect()
+        };
+
+        Ok(EntityAggregates {
+            count: result["count"].as_u64().unwrap_or(0),
+            sum: values("sum")?,
+            min: values("min")?,
+            max: values("max")?,
+            avg: values("avg")?,
+        })
     }
 
     fn set_block_ptr_with_no_changes(
@@ -702,8 +1355,13 @@ impl StoreTrait for Store {
 
         let conn = self.conn.lock().unwrap();
 
+        let _timer = STORE_TRANSACTION_DURATION
+            .with_label_values(&["transact_block_operations"])
+            .start_timer();
+
         conn.transaction::<(), _, _>(|| {
             self.apply_entity_operations(&*conn, operations, block_ptr_to)?;
+            set_event_block_number(&*conn, block_ptr_to.number)?;
             self.update_subgraph_block_pointer(&*conn, subgraph_id, block_ptr_from, block_ptr_to)
         })
     }
@@ -729,14 +1387,51 @@ impl StoreTrait for Store {
             panic!("revert_block_operations must revert a single block only");
         }
 
-        select(revert_block(
-            &block_ptr_from.hash_hex(),
-            block_ptr_from.number as i64,
-            &block_ptr_to.hash_hex(),
-            subgraph_id,
-        )).execute(&*self.conn.lock().unwrap())
-        .map_err(|e| format_err!("Error reverting block: {}", e))
-        .map(|_| ())
+        let conn = self.conn.lock().unwrap();
+
+        let _timer = STORE_TRANSACTION_DURATION
+            .with_label_values(&["revert_block_operations"])
+            .start_timer();
+
+        conn.transaction::<(), _, _>(|| {
+            select(revert_block(
+                &block_ptr_from.hash_hex(),
+                block_ptr_from.number as i64,
+                &block_ptr_to.hash_hex(),
+                subgraph_id,
+            )).execute(&*conn)
+            .map_err(|e| format_err!("Error reverting block: {}", e))?;
+
+            // Reverting belongs to the reverted block, so that reverting to
+            // an earlier block undoes it along with the block
+            set_event_block_number(&*conn, block_ptr_from.number)
+        })
+    }
+
+    fn revert_to_block(
+        &self,
+        subgraph_id: SubgraphId,
+        block_ptr_from: EthereumBlockPointer,
+        block_ptr_to: EthereumBlockPointer,
+    ) -> Result<(), Error> {
+        if block_ptr_to.number > block_ptr_from.number {
+            panic!("revert_to_block must revert to an earlier block");
+        }
+
+        let conn = self.conn.lock().unwrap();
+
+        let _timer = STORE_TRANSACTION_DURATION
+            .with_label_values(&["revert_to_block"])
+            .start_timer();
+
+        conn.transaction::<(), _, _>(|| {
+            select(revert_to_block(
+                subgraph_id.as_str(),
+                block_ptr_to.number as i64,
+            )).execute(&*conn)
+            .map_err(|e| format_err!("Error reverting to block: {}", e))?;
+            self.update_subgraph_block_pointer(&*conn, subgraph_id, block_ptr_from, block_ptr_to)
+        })
     }
 
     fn subscribe(&self, entities: Vec<SubgraphEntityPair>) -> EntityChangeStream {
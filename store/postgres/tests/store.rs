@@ -12,9 +12,11 @@ use std::fmt::Debug;
 use std::panic;
 use std::str::FromStr;
 use std::sync::Mutex;
+use std::time::Instant;
 
 use graph::components::store::{EntityFilter, EntityKey, EntityOrder, EntityQuery, EntityRange};
 use graph::data::store::scalar;
+use graph::data::subgraph::schema::SUBGRAPHS_ID;
 use graph::prelude::*;
 use graph::web3::types::H256;
 use graph_store_postgres::{db_schema, Store as DieselStore, StoreConfig};
@@ -131,6 +133,7 @@ fn insert_test_data(store: Arc<DieselStore>) {
         "tonofjohn@email.com",
         67 as i32,
         184.4 as f32,
+        "1000.5",
         false,
     );
     store
@@ -148,6 +151,7 @@ fn insert_test_data(store: Arc<DieselStore>) {
         "dinici@email.com",
         43 as i32,
         159.1 as f32,
+        "99.000001",
         true,
     );
     let test_entity_3_1 = create_test_entity(
@@ -157,6 +161,7 @@ fn insert_test_data(store: Arc<DieselStore>) {
         "queensha@email.com",
         28 as i32,
         111.7 as f32,
+        "-12.75",
         false,
     );
     store
@@ -174,6 +179,7 @@ fn insert_test_data(store: Arc<DieselStore>) {
         "teeko@email.com",
         28 as i32,
         111.7 as f32,
+        "-12.75",
         false,
     );
     store
@@ -193,6 +199,7 @@ fn create_test_entity(
     email: &str,
     age: i32,
     weight: f32,
+    balance: &str,
     coffee: bool,
 ) -> EntityOperation {
     let mut test_entity = Entity::new();
@@ -204,6 +211,10 @@ fn create_test_entity(
     test_entity.insert("email".to_owned(), Value::String(email.to_owned()));
     test_entity.insert("age".to_owned(), Value::Int(age));
     test_entity.insert("weight".to_owned(), Value::Float(weight));
+    test_entity.insert(
+        "balance".to_owned(),
+        Value::BigDecimal(scalar::BigDecimal::from_str(balance).unwrap()),
+    );
     test_entity.insert("coffee".to_owned(), Value::Bool(coffee));
 
     EntityOperation::Set {
@@ -229,6 +240,9 @@ fn remove_test_data() {
     delete(subgraphs)
         .execute(&conn)
         .expect("Failed to remove subgraph test data");
+    delete(db_schema::paused_subgraphs::table)
+        .execute(&conn)
+        .expect("Failed to remove paused subgraph test data");
 }
 
 #[test]
@@ -305,6 +319,7 @@ fn insert_entity() {
             "wanawana@email.com",
             76 as i32,
             111.7 as f32,
+            "-12.75",
             true,
         );
         store
@@ -342,6 +357,7 @@ fn update_existing() {
             "wanawana@email.com",
             76 as i32,
             111.7 as f32,
+            "-12.75",
             true,
         );
         let mut new_data = match op {
@@ -460,6 +476,7 @@ fn find_string_contains() {
             order_by: None,
             order_direction: None,
             range: None,
+            deadline: None,
         },
     )
 }
@@ -478,6 +495,7 @@ fn find_string_equal() {
             order_by: None,
             order_direction: None,
             range: None,
+            deadline: None,
         },
     )
 }
@@ -496,6 +514,7 @@ fn find_string_not_equal() {
             order_by: Some(("name".to_owned(), ValueType::String)),
             order_direction: Some(EntityOrder::Ascending),
             range: None,
+            deadline: None,
         },
     )
 }
@@ -514,6 +533,7 @@ fn find_string_greater_than() {
             order_by: None,
             order_direction: None,
             range: None,
+            deadline: None,
         },
     )
 }
@@ -532,6 +552,7 @@ fn find_string_less_than_order_by_asc() {
             order_by: Some(("name".to_owned(), ValueType::String)),
             order_direction: Some(EntityOrder::Ascending),
             range: None,
+            deadline: None,
         },
     )
 }
@@ -550,6 +571,7 @@ fn find_string_less_than_order_by_desc() {
             order_by: Some(("name".to_owned(), ValueType::String)),
             order_direction: Some(EntityOrder::Descending),
             range: None,
+            deadline: None,
         },
     )
 }
@@ -568,6 +590,7 @@ fn find_string_less_than_range() {
             order_by: Some(("name".to_owned(), ValueType::String)),
             order_direction: Some(EntityOrder::Descending),
             range: Some(EntityRange { first: 1, skip: 1 }),
+            deadline: None,
         },
     )
 }
@@ -586,6 +609,7 @@ fn find_string_multiple_and() {
             order_by: Some(("name".to_owned(), ValueType::String)),
             order_direction: Some(EntityOrder::Descending),
             range: None,
+            deadline: None,
         },
     )
 }
@@ -604,6 +628,7 @@ fn find_string_ends_with() {
             order_by: Some(("name".to_owned(), ValueType::String)),
             order_direction: Some(EntityOrder::Descending),
             range: None,
+            deadline: None,
         },
     )
 }
@@ -622,6 +647,7 @@ fn find_string_not_ends_with() {
             order_by: Some(("name".to_owned(), ValueType::String)),
             order_direction: Some(EntityOrder::Descending),
             range: None,
+            deadline: None,
         },
     )
 }
@@ -640,6 +666,7 @@ fn find_string_in() {
             order_by: Some(("name".to_owned(), ValueType::String)),
             order_direction: Some(EntityOrder::Descending),
             range: None,
+            deadline: None,
         },
     )
 }
@@ -658,10 +685,287 @@ fn find_string_not_in() {
             order_by: Some(("name".to_owned(), ValueType::String)),
             order_direction: Some(EntityOrder::Descending),
             range: None,
+            deadline: None,
+        },
+    )
+}
+
+#[test]
+fn find_string_contains_nocase() {
+    test_find(
+        vec!["2"],
+        EntityQuery {
+            subgraph_id: TEST_SUBGRAPH_ID.clone(),
+            entity_type: "user".to_owned(),
+            filter: Some(EntityFilter::And(vec![EntityFilter::ContainsNoCase(
+                "name".to_owned(),
+                "IND".into(),
+            )])),
+            order_by: None,
+            order_direction: None,
+            range: None,
+            deadline: None,
+        },
+    )
+}
+
+#[test]
+fn find_string_starts_with_nocase() {
+    test_find(
+        vec!["1"],
+        EntityQuery {
+            subgraph_id: TEST_SUBGRAPH_ID.clone(),
+            entity_type: "user".to_owned(),
+            filter: Some(EntityFilter::And(vec![EntityFilter::StartsWithNoCase(
+                "name".to_owned(),
+                "john".into(),
+            )])),
+            order_by: None,
+            order_direction: None,
+            range: None,
+            deadline: None,
+        },
+    )
+}
+
+#[test]
+fn find_string_ends_with_nocase() {
+    test_find(
+        vec!["3"],
+        EntityQuery {
+            subgraph_id: TEST_SUBGRAPH_ID.clone(),
+            entity_type: "user".to_owned(),
+            filter: Some(EntityFilter::And(vec![EntityFilter::EndsWithNoCase(
+                "name".to_owned(),
+                "EENA".into(),
+            )])),
+            order_by: None,
+            order_direction: None,
+            range: None,
+            deadline: None,
         },
     )
 }
 
+#[test]
+fn find_string_contains_nocase_escapes_wildcards() {
+    test_find(
+        vec![],
+        EntityQuery {
+            subgraph_id: TEST_SUBGRAPH_ID.clone(),
+            entity_type: "user".to_owned(),
+            filter: Some(EntityFilter::And(vec![EntityFilter::ContainsNoCase(
+                "name".to_owned(),
+                "j_hn%".into(),
+            )])),
+            order_by: None,
+            order_direction: None,
+            range: None,
+            deadline: None,
+        },
+    )
+}
+
+#[test]
+fn find_string_matches() {
+    test_find(
+        vec!["2"],
+        EntityQuery {
+            subgraph_id: TEST_SUBGRAPH_ID.clone(),
+            entity_type: "user".to_owned(),
+            filter: Some(EntityFilter::And(vec![EntityFilter::Matches(
+                "name".to_owned(),
+                "^c.*i$".into(),
+            )])),
+            order_by: None,
+            order_direction: None,
+            range: None,
+            deadline: None,
+        },
+    )
+}
+
+#[test]
+fn find_string_matches_rejects_long_patterns() {
+    run_test(|store| -> Result<(), ()> {
+        let result = store.find(EntityQuery {
+            subgraph_id: TEST_SUBGRAPH_ID.clone(),
+            entity_type: "user".to_owned(),
+            filter: Some(EntityFilter::And(vec![EntityFilter::Matches(
+                "name".to_owned(),
+                "a".repeat(257).into(),
+            )])),
+            order_by: None,
+            order_direction: None,
+            range: None,
+            deadline: None,
+        });
+        assert!(result.is_err());
+
+        Ok(())
+    })
+}
+
+#[test]
+fn find_times_out_after_the_deadline() {
+    run_test(|store| -> Result<(), ()> {
+        let result = store.find(EntityQuery {
+            subgraph_id: TEST_SUBGRAPH_ID.clone(),
+            entity_type: "user".to_owned(),
+            filter: None,
+            order_by: None,
+            order_direction: None,
+            range: None,
+            deadline: Some(Instant::now()),
+        });
+        match result {
+            Err(QueryExecutionError::Timeout) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+
+        Ok(())
+    })
+}
+
+#[test]
+fn find_full_text_match() {
+    test_find(
+        vec!["2"],
+        EntityQuery {
+            subgraph_id: TEST_SUBGRAPH_ID.clone(),
+            entity_type: "user".to_owned(),
+            filter: Some(EntityFilter::And(vec![EntityFilter::FullTextMatch(
+                vec!["name".to_owned(), "email".to_owned()],
+                "english".to_owned(),
+                "cindini".to_owned(),
+            )])),
+            order_by: None,
+            order_direction: None,
+            range: None,
+            deadline: None,
+        },
+    )
+}
+
+#[test]
+fn create_full_text_indexes() {
+    run_test(|store| -> Result<(), ()> {
+        let definition = FullTextDefinition {
+            name: "userSearch".to_owned(),
+            entity_type: "user".to_owned(),
+            language: "english".to_owned(),
+            fields: vec!["name".to_owned(), "email".to_owned()],
+        };
+
+        // Creating the same indexes twice is fine
+        store
+            .create_full_text_indexes(TEST_SUBGRAPH_ID.clone(), vec![definition.clone()])
+            .expect("Failed to create full-text search indexes");
+        store
+            .create_full_text_indexes(TEST_SUBGRAPH_ID.clone(), vec![definition])
+            .expect("Failed to create full-text search indexes twice");
+
+        // The index only covers the entities of the searched subgraph and type
+        let index_definitions = select(dsl::sql::<sql_types::Text>(
+            "indexdef FROM pg_indexes WHERE indexname LIKE 'entities_fulltext_%'",
+        )).load::<String>(&*store.conn.lock().unwrap())
+        .unwrap();
+        assert_eq!(index_definitions.len(), 1);
+        assert!(index_definitions[0].contains("(subgraph)::text = 'test_subgraph'::text"));
+        assert!(index_definitions[0].contains("(entity)::text = 'user'::text"));
+
+        Ok(())
+    })
+}
+
+#[test]
+fn aggregate() {
+    run_test(|store| -> Result<(), ()> {
+        let aggregates = store
+            .aggregate(
+                EntityQuery {
+                    subgraph_id: TEST_SUBGRAPH_ID.clone(),
+                    entity_type: "user".to_owned(),
+                    filter: Some(EntityFilter::And(vec![EntityFilter::GreaterThan(
+                        "age".to_owned(),
+                        Value::Int(30),
+                    )])),
+                    order_by: None,
+                    order_direction: None,
+                    range: None,
+                    deadline: None,
+                },
+                vec![
+                    ("age".to_owned(), ValueType::Int),
+                    ("weight".to_owned(), ValueType::Float),
+                    ("balance".to_owned(), ValueType::BigDecimal),
+                ],
+            ).expect("Failed to aggregate users");
+        let big_decimal = |s: &str| Value::BigDecimal(scalar::BigDecimal::from_str(s).unwrap());
+
+        assert_eq!(aggregates.count, 2);
+
+        assert_eq!(
+            aggregates.sum.get("age"),
+            Some(&Value::BigInt(scalar::BigInt::from(110)))
+        );
+        assert_eq!(aggregates.min.get("age"), Some(&Value::Int(43)));
+        assert_eq!(aggregates.max.get("age"), Some(&Value::Int(67)));
+        assert_eq!(aggregates.avg.get("age"), Some(&Value::Float(55.0)));
+
+        match aggregates.sum.get("weight") {
+            Some(Value::Float(sum)) => assert!((sum - 343.5).abs() < 0.001),
+            value => panic!("Unexpected sum of weights: {:?}", value),
+        }
+        assert_eq!(aggregates.min.get("weight"), Some(&Value::Float(159.1)));
+        assert_eq!(aggregates.max.get("weight"), Some(&Value::Float(184.4)));
+
+        assert_eq!(
+            aggregates.sum.get("balance"),
+            Some(&big_decimal("1099.500001"))
+        );
+        assert_eq!(
+            aggregates.min.get("balance"),
+            Some(&big_decimal("99.000001"))
+        );
+        assert_eq!(aggregates.max.get("balance"), Some(&big_decimal("1000.5")));
+        assert_eq!(
+            aggregates.avg.get("balance"),
+            Some(&big_decimal("549.7500005"))
+        );
+
+        Ok(())
+    })
+}
+
+#[test]
+fn aggregate_without_matching_entities() {
+    run_test(|store| -> Result<(), ()> {
+        let aggregates = store
+            .aggregate(
+                EntityQuery {
+                    subgraph_id: TEST_SUBGRAPH_ID.clone(),
+                    entity_type: "user".to_owned(),
+                    filter: Some(EntityFilter::And(vec![EntityFilter::GreaterThan(
+                        "age".to_owned(),
+                        Value::Int(100),
+                    )])),
+                    order_by: None,
+                    order_direction: None,
+                    range: None,
+                    deadline: None,
+                },
+                vec![("age".to_owned(), ValueType::Int)],
+            ).expect("Failed to aggregate users");
+
+        assert_eq!(aggregates.count, 0);
+        assert_eq!(aggregates.sum.get("age"), Some(&Value::Null));
+        assert_eq!(aggregates.avg.get("age"), Some(&Value::Null));
+
+        Ok(())
+    })
+}
+
 #[test]
 fn find_float_equal() {
     test_find(
@@ -676,6 +980,7 @@ fn find_float_equal() {
             order_by: None,
             order_direction: None,
             range: None,
+            deadline: None,
         },
     )
 }
@@ -694,6 +999,7 @@ fn find_float_not_equal() {
             order_by: Some(("name".to_owned(), ValueType::String)),
             order_direction: Some(EntityOrder::Descending),
             range: None,
+            deadline: None,
         },
     )
 }
@@ -712,6 +1018,7 @@ fn find_float_greater_than() {
             order_by: None,
             order_direction: None,
             range: None,
+            deadline: None,
         },
     )
 }
@@ -730,6 +1037,7 @@ fn find_float_less_than() {
             order_by: Some(("name".to_owned(), ValueType::String)),
             order_direction: Some(EntityOrder::Ascending),
             range: None,
+            deadline: None,
         },
     )
 }
@@ -748,6 +1056,7 @@ fn find_float_less_than_order_by_desc() {
             order_by: Some(("name".to_owned(), ValueType::String)),
             order_direction: Some(EntityOrder::Descending),
             range: None,
+            deadline: None,
         },
     )
 }
@@ -766,6 +1075,7 @@ fn find_float_less_than_range() {
             order_by: Some(("name".to_owned(), ValueType::String)),
             order_direction: Some(EntityOrder::Descending),
             range: Some(EntityRange { first: 1, skip: 1 }),
+            deadline: None,
         },
     )
 }
@@ -784,6 +1094,7 @@ fn find_float_in() {
             order_by: Some(("name".to_owned(), ValueType::String)),
             order_direction: Some(EntityOrder::Descending),
             range: Some(EntityRange { first: 5, skip: 0 }),
+            deadline: None,
         },
     )
 }
@@ -802,6 +1113,7 @@ fn find_float_not_in() {
             order_by: Some(("name".to_owned(), ValueType::String)),
             order_direction: Some(EntityOrder::Descending),
             range: Some(EntityRange { first: 5, skip: 0 }),
+            deadline: None,
         },
     )
 }
@@ -820,6 +1132,7 @@ fn find_int_equal() {
             order_by: Some(("name".to_owned(), ValueType::String)),
             order_direction: Some(EntityOrder::Descending),
             range: None,
+            deadline: None,
         },
     )
 }
@@ -838,6 +1151,7 @@ fn find_int_not_equal() {
             order_by: Some(("name".to_owned(), ValueType::String)),
             order_direction: Some(EntityOrder::Descending),
             range: None,
+            deadline: None,
         },
     )
 }
@@ -856,6 +1170,7 @@ fn find_int_greater_than() {
             order_by: None,
             order_direction: None,
             range: None,
+            deadline: None,
         },
     )
 }
@@ -874,6 +1189,7 @@ fn find_int_greater_or_equal() {
             order_by: Some(("name".to_owned(), ValueType::String)),
             order_direction: Some(EntityOrder::Ascending),
             range: None,
+            deadline: None,
         },
     )
 }
@@ -892,6 +1208,7 @@ fn find_int_less_than() {
             order_by: Some(("name".to_owned(), ValueType::String)),
             order_direction: Some(EntityOrder::Ascending),
             range: None,
+            deadline: None,
         },
     )
 }
@@ -910,6 +1227,7 @@ fn find_int_less_or_equal() {
             order_by: Some(("name".to_owned(), ValueType::String)),
             order_direction: Some(EntityOrder::Ascending),
             range: None,
+            deadline: None,
         },
     )
 }
@@ -928,6 +1246,7 @@ fn find_int_less_than_order_by_desc() {
             order_by: Some(("name".to_owned(), ValueType::String)),
             order_direction: Some(EntityOrder::Descending),
             range: None,
+            deadline: None,
         },
     )
 }
@@ -946,6 +1265,7 @@ fn find_int_less_than_range() {
             order_by: Some(("name".to_owned(), ValueType::String)),
             order_direction: Some(EntityOrder::Descending),
             range: Some(EntityRange { first: 1, skip: 1 }),
+            deadline: None,
         },
     )
 }
@@ -964,6 +1284,7 @@ fn find_int_in() {
             order_by: Some(("name".to_owned(), ValueType::String)),
             order_direction: Some(EntityOrder::Descending),
             range: Some(EntityRange { first: 5, skip: 0 }),
+            deadline: None,
         },
     )
 }
@@ -982,6 +1303,67 @@ fn find_int_not_in() {
             order_by: Some(("name".to_owned(), ValueType::String)),
             order_direction: Some(EntityOrder::Descending),
             range: Some(EntityRange { first: 5, skip: 0 }),
+            deadline: None,
+        },
+    )
+}
+
+#[test]
+fn find_big_decimal_equal() {
+    test_find(
+        vec!["2"],
+        EntityQuery {
+            subgraph_id: TEST_SUBGRAPH_ID.clone(),
+            entity_type: "user".to_owned(),
+            filter: Some(EntityFilter::And(vec![EntityFilter::Equal(
+                "balance".to_owned(),
+                Value::BigDecimal(scalar::BigDecimal::from_str("99.000001").unwrap()),
+            )])),
+            order_by: None,
+            order_direction: None,
+            range: None,
+            deadline: None,
+        },
+    )
+}
+
+#[test]
+fn find_big_decimal_greater_than() {
+    test_find(
+        vec!["1", "2"],
+        EntityQuery {
+            subgraph_id: TEST_SUBGRAPH_ID.clone(),
+            entity_type: "user".to_owned(),
+            filter: Some(EntityFilter::And(vec![EntityFilter::GreaterThan(
+                "balance".to_owned(),
+                Value::BigDecimal(scalar::BigDecimal::from_str("99").unwrap()),
+            )])),
+            order_by: Some(("name".to_owned(), ValueType::String)),
+            order_direction: Some(EntityOrder::Descending),
+            range: None,
+            deadline: None,
+        },
+    )
+}
+
+#[test]
+fn find_big_decimal_in() {
+    test_find(
+        vec!["3", "1"],
+        EntityQuery {
+            subgraph_id: TEST_SUBGRAPH_ID.clone(),
+            entity_type: "user".to_owned(),
+            filter: Some(EntityFilter::And(vec![EntityFilter::In(
+                "balance".to_owned(),
+                vec![
+                    Value::BigDecimal(scalar::BigDecimal::from_str("1000.50").unwrap()),
+                    Value::BigDecimal(scalar::BigDecimal::from_str("-12.75").unwrap()),
+                ],
+            )])),
+            order_by: Some(("name".to_owned(), ValueType::String)),
+            order_direction: Some(EntityOrder::Descending),
+            range: None,
+            deadline: None,
         },
     )
 }
@@ -1000,6 +1382,7 @@ fn find_bool_equal() {
             order_by: Some(("name".to_owned(), ValueType::String)),
             order_direction: Some(EntityOrder::Descending),
             range: None,
+            deadline: None,
         },
     )
 }
@@ -1018,6 +1401,7 @@ fn find_bool_not_equal() {
             order_by: Some(("name".to_owned(), ValueType::String)),
             order_direction: Some(EntityOrder::Ascending),
             range: None,
+            deadline: None,
         },
     )
 }
@@ -1036,6 +1420,7 @@ fn find_bool_in() {
             order_by: Some(("name".to_owned(), ValueType::String)),
             order_direction: Some(EntityOrder::Descending),
             range: Some(EntityRange { first: 5, skip: 0 }),
+            deadline: None,
         },
     )
 }
@@ -1054,6 +1439,7 @@ fn find_bool_not_in() {
             order_by: Some(("name".to_owned(), ValueType::String)),
             order_direction: Some(EntityOrder::Descending),
             range: Some(EntityRange { first: 5, skip: 0 }),
+            deadline: None,
         },
     )
 }
@@ -1072,6 +1458,7 @@ fn find_bytes_equal() {
             order_by: Some(("name".to_owned(), ValueType::String)),
             order_direction: Some(EntityOrder::Descending),
             range: None,
+            deadline: None,
         },
     )
 }
@@ -1087,6 +1474,7 @@ fn find_order_by_float() {
             order_by: Some(("weight".to_owned(), ValueType::Float)),
             order_direction: Some(EntityOrder::Ascending),
             range: None,
+            deadline: None,
         },
     );
     test_find(
@@ -1098,6 +1486,7 @@ fn find_order_by_float() {
             order_by: Some(("weight".to_owned(), ValueType::Float)),
             order_direction: Some(EntityOrder::Descending),
             range: None,
+            deadline: None,
         },
     );
 }
@@ -1113,6 +1502,7 @@ fn find_order_by_id() {
             order_by: Some(("id".to_owned(), ValueType::ID)),
             order_direction: Some(EntityOrder::Ascending),
             range: None,
+            deadline: None,
         },
     );
     test_find(
@@ -1124,6 +1514,7 @@ fn find_order_by_id() {
             order_by: Some(("id".to_owned(), ValueType::ID)),
             order_direction: Some(EntityOrder::Descending),
             range: None,
+            deadline: None,
         },
     );
 }
@@ -1139,6 +1530,7 @@ fn find_order_by_int() {
             order_by: Some(("age".to_owned(), ValueType::Int)),
             order_direction: Some(EntityOrder::Ascending),
             range: None,
+            deadline: None,
         },
     );
     test_find(
@@ -1150,6 +1542,36 @@ fn find_order_by_int() {
             order_by: Some(("age".to_owned(), ValueType::Int)),
             order_direction: Some(EntityOrder::Descending),
             range: None,
+            deadline: None,
+        },
+    );
+}
+
+#[test]
+fn find_order_by_big_decimal() {
+    // Sorting numerically, not by the string representation
+    test_find(
+        vec!["3", "2", "1"],
+        EntityQuery {
+            subgraph_id: TEST_SUBGRAPH_ID.clone(),
+            entity_type: "user".to_owned(),
+            filter: None,
+            order_by: Some(("balance".to_owned(), ValueType::BigDecimal)),
+            order_direction: Some(EntityOrder::Ascending),
+            range: None,
+            deadline: None,
+        },
+    );
+    test_find(
+        vec!["1", "2", "3"],
+        EntityQuery {
+            subgraph_id: TEST_SUBGRAPH_ID.clone(),
+            entity_type: "user".to_owned(),
+            filter: None,
+            order_by: Some(("balance".to_owned(), ValueType::BigDecimal)),
+            order_direction: Some(EntityOrder::Descending),
+            range: None,
+            deadline: None,
         },
     );
 }
@@ -1165,6 +1587,7 @@ fn find_order_by_string() {
             order_by: Some(("name".to_owned(), ValueType::String)),
             order_direction: Some(EntityOrder::Ascending),
             range: None,
+            deadline: None,
         },
     );
     test_find(
@@ -1176,6 +1599,7 @@ fn find_order_by_string() {
             order_by: Some(("name".to_owned(), ValueType::String)),
             order_direction: Some(EntityOrder::Descending),
             range: None,
+            deadline: None,
         },
     );
 }
@@ -1194,10 +1618,153 @@ fn find_where_nested_and_or() {
             order_by: Some(("id".to_owned(), ValueType::String)),
             order_direction: Some(EntityOrder::Ascending),
             range: None,
+            deadline: None,
         },
     )
 }
 
+#[test]
+fn remove_subgraph_data() {
+    run_test(|store| -> Result<(), ()> {
+        let user_query = EntityQuery {
+            subgraph_id: TEST_SUBGRAPH_ID.clone(),
+            entity_type: "user".to_owned(),
+            filter: None,
+            order_by: None,
+            order_direction: None,
+            range: None,
+            deadline: None,
+        };
+        assert!(!store.find(user_query.clone()).unwrap().is_empty());
+
+        store
+            .remove_subgraph_data(TEST_SUBGRAPH_ID.clone())
+            .unwrap();
+
+        // All entities, their history and the block pointer are gone; the
+        // bulk delete doesn't log the removed entities to the history
+        assert!(store.find(user_query).unwrap().is_empty());
+        assert!(store.block_ptr(TEST_SUBGRAPH_ID.clone()).is_err());
+        let history = select(dsl::sql::<sql_types::BigInt>(
+            "COUNT(*) FROM entity_history WHERE subgraph = 'test_subgraph'",
+        )).get_result::<i64>(&*store.conn.lock().unwrap())
+        .unwrap();
+        assert_eq!(history, 0);
+
+        // The subgraph starts over from the block it is added with
+        store
+            .add_subgraph_if_missing(TEST_SUBGRAPH_ID.clone(), *TEST_BLOCK_0_PTR)
+            .unwrap();
+        assert_eq!(
+            store.block_ptr(TEST_SUBGRAPH_ID.clone()).unwrap(),
+            *TEST_BLOCK_0_PTR
+        );
+
+        Ok(())
+    })
+}
+
+#[test]
+fn paused_subgraphs_stay_paused_until_resumed() {
+    run_test(|store| -> Result<(), ()> {
+        let paused = || store.is_subgraph_paused(TEST_SUBGRAPH_ID.clone()).unwrap();
+        assert!(!paused());
+
+        // Pausing twice is harmless, and reindexing keeps the subgraph paused
+        store
+            .set_subgraph_paused(TEST_SUBGRAPH_ID.clone(), true)
+            .unwrap();
+        store
+            .set_subgraph_paused(TEST_SUBGRAPH_ID.clone(), true)
+            .unwrap();
+        store
+            .remove_subgraph_data(TEST_SUBGRAPH_ID.clone())
+            .unwrap();
+        assert!(paused());
+
+        store
+            .set_subgraph_paused(TEST_SUBGRAPH_ID.clone(), false)
+            .unwrap();
+        assert!(!paused());
+
+        Ok(())
+    })
+}
+
+#[test]
+fn remove_unused_subgraph() {
+    run_test(|store| -> Result<(), ()> {
+        // Add metadata for the test subgraph and for another subgraph
+        for (entity_type, entity_id) in &[
+            ("Subgraph", "test_subgraph"),
+            ("SubgraphManifest", "test_subgraph-manifest"),
+            ("Subgraph", "other_subgraph"),
+            ("SubgraphManifest", "other_subgraph-manifest"),
+        ] {
+            let mut data = Entity::new();
+            data.insert("id".to_owned(), Value::String(entity_id.to_string()));
+            store
+                .apply_set_operation(
+                    EntityOperation::Set {
+                        key: EntityKey {
+                            subgraph_id: SUBGRAPHS_ID.to_owned(),
+                            entity_type: entity_type.to_string(),
+                            entity_id: entity_id.to_string(),
+                        },
+                        data,
+                    },
+                    "subgraph-added".to_owned(),
+                ).unwrap();
+        }
+
+        assert_eq!(
+            store.read_all_subgraph_ids().unwrap(),
+            vec!["other_subgraph", "test_subgraph"]
+        );
+        let entity_count = store.count_entities(TEST_SUBGRAPH_ID.clone()).unwrap();
+        assert!(entity_count > 0);
+
+        // Subgraphs that a name refers to are left alone
+        store.write_subgraph_name("test".to_owned(), None).unwrap();
+        store
+            .write_pending_subgraph_id("test".to_owned(), Some(TEST_SUBGRAPH_ID.clone()))
+            .unwrap();
+        assert_eq!(
+            store
+                .remove_unused_subgraph(TEST_SUBGRAPH_ID.clone())
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            store.count_entities(TEST_SUBGRAPH_ID.clone()).unwrap(),
+            entity_count
+        );
+        store.delete_subgraph_name("test".to_owned()).unwrap();
+
+        assert_eq!(
+            store
+                .remove_unused_subgraph(TEST_SUBGRAPH_ID.clone())
+                .unwrap(),
+            Some(entity_count)
+        );
+
+        // Only the data and metadata of the test subgraph are gone
+        assert_eq!(
+            store.read_all_subgraph_ids().unwrap(),
+            vec!["other_subgraph"]
+        );
+        assert_eq!(store.count_entities(TEST_SUBGRAPH_ID.clone()).unwrap(), 0);
+        let other_manifest_key = EntityKey {
+            subgraph_id: SUBGRAPHS_ID.to_owned(),
+            entity_type: "SubgraphManifest".to_owned(),
+            entity_id: "other_subgraph-manifest".to_owned(),
+        };
+        assert!(store.get(other_manifest_key).unwrap().is_some());
+
+        Ok(())
+    })
+}
+
 #[test]
 fn revert_block() {
     run_test(|store| -> Result<(), ()> {
@@ -1211,6 +1778,7 @@ fn revert_block() {
             order_by: Some(("name".to_owned(), ValueType::String)),
             order_direction: Some(EntityOrder::Descending),
             range: None,
+            deadline: None,
         };
 
         // Revert block 3
@@ -1251,6 +1819,7 @@ fn revert_block_with_delete() {
             order_by: Some(("name".to_owned(), ValueType::String)),
             order_direction: Some(EntityOrder::Descending),
             range: None,
+            deadline: None,
         };
 
         // Delete entity with id=2
@@ -1348,6 +1917,89 @@ fn revert_block_with_partial_update() {
     })
 }
 
+#[test]
+fn revert_to_block() {
+    run_test(|store| -> Result<(), ()> {
+        let user = |id: &str| {
+            store
+                .get(EntityKey {
+                    subgraph_id: TEST_SUBGRAPH_ID.clone(),
+                    entity_type: "user".to_owned(),
+                    entity_id: id.to_owned(),
+                }).unwrap()
+        };
+        let history = || {
+            select(dsl::sql::<sql_types::BigInt>(
+                "COUNT(*) FROM entity_history WHERE subgraph = 'test_subgraph'",
+            )).get_result::<i64>(&*store.conn.lock().unwrap())
+            .unwrap()
+        };
+
+        // Delete an entity in block 4 and revert that block, as in a reorg
+        let remove_user_2 = EntityOperation::Remove {
+            key: EntityKey {
+                subgraph_id: TEST_SUBGRAPH_ID.clone(),
+                entity_type: "user".to_owned(),
+                entity_id: "2".to_owned(),
+            },
+        };
+        store
+            .transact_block_operations(
+                TEST_SUBGRAPH_ID.clone(),
+                *TEST_BLOCK_3_PTR,
+                *TEST_BLOCK_4_PTR,
+                vec![remove_user_2.clone()],
+            ).unwrap();
+        store
+            .revert_block_operations(
+                TEST_SUBGRAPH_ID.clone(),
+                *TEST_BLOCK_4_PTR,
+                *TEST_BLOCK_3_PTR,
+            ).unwrap();
+        store
+            .transact_block_operations(
+                TEST_SUBGRAPH_ID.clone(),
+                *TEST_BLOCK_3_PTR,
+                *TEST_BLOCK_4_PTR,
+                vec![remove_user_2],
+            ).unwrap();
+        assert!(user("2").is_none());
+
+        // Reverting fails as a whole if the block pointer doesn't match
+        let result = store.revert_to_block(
+            TEST_SUBGRAPH_ID.clone(),
+            *TEST_BLOCK_3_PTR,
+            *TEST_BLOCK_1_PTR,
+        );
+        assert!(result.is_err());
+        assert!(user("2").is_none());
+        assert_eq!(
+            store.block_ptr(TEST_SUBGRAPH_ID.clone()).unwrap(),
+            *TEST_BLOCK_4_PTR
+        );
+
+        // Revert blocks 2 to 4 at once; none of them are in the chain store
+        store
+            .revert_to_block(
+                TEST_SUBGRAPH_ID.clone(),
+                *TEST_BLOCK_4_PTR,
+                *TEST_BLOCK_1_PTR,
+            ).unwrap();
+        assert_eq!(
+            store.block_ptr(TEST_SUBGRAPH_ID.clone()).unwrap(),
+            *TEST_BLOCK_1_PTR
+        );
+        assert!(user("1").is_some());
+        assert!(user("2").is_none());
+        assert!(user("3").is_none());
+
+        // Only the history of block 1 is left
+        assert_eq!(history(), 1);
+
+        Ok(())
+    })
+}
+
 // Disabled due to issue #332
 #[test]
 #[ignore]